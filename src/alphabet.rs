@@ -0,0 +1,227 @@
+//! Enigma alphabet module.
+//!
+//! An [Alphabet](Alphabet) lists the keys of a machine in contact order. Most
+//! Enigmas had the 26 letters `A` - `Z`, which is the default everywhere in
+//! the crate. The Enigma Z had the ten digits instead, and any other set of
+//! characters can be used to experiment with custom components.
+//!
+//! Components work on contact numbers, `0` up to the size of the alphabet, so
+//! the alphabet is only needed to turn characters into contacts and back, and
+//! to read the wiring of custom components.
+//!
+//! ```
+//! use enigma::alphabet::Alphabet;
+//!
+//! let digits = Alphabet::digits();
+//! assert_eq!(digits.len(), 10);
+//! assert_eq!(digits.index_of('7'), Some(7));
+//! assert_eq!(digits.letter(3), '3');
+//! assert_eq!(digits.index_of('A'), None);
+//!
+//! assert!(Alphabet::new("ABCA").is_err());
+//! ```
+//!
+//! With the `serde` feature an [Alphabet](Alphabet) is serialized as its
+//! characters, e.g., `"0123456789"`.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The letters `A` - `Z` of most Enigma machines.
+pub const ALPHABET_LATIN: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The digits `0` - `9`.
+pub const ALPHABET_DIGITS: &str = "0123456789";
+
+/// The digits of the Enigma Z in contact order, the order of its keyboard.
+pub const ALPHABET_Z: &str = "1234567890";
+
+/// The keys of a machine in contact order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct Alphabet {
+    letters: Vec<char>,
+}
+
+impl Alphabet {
+    /// Create an alphabet.
+    ///
+    /// * `letters` - Between 2 and 255 characters, each appearing once. Upper
+    ///   and lower case letters are different characters.
+    pub fn new(letters: &str) -> Result<Alphabet, String> {
+        let letters: Vec<char> = letters.chars().collect();
+        if !(2..=255).contains(&letters.len()) {
+            return Err(format!(
+                "Invalid alphabet: expected 2 - 255 characters, received {}",
+                letters.len()
+            ));
+        }
+
+        for (i, c) in letters.iter().enumerate() {
+            if c.is_whitespace() {
+                return Err("Invalid alphabet: whitespace can't be a key".to_string());
+            }
+            if letters[..i].contains(c) {
+                return Err(format!("Invalid alphabet: {} appears more than once", c));
+            }
+        }
+
+        Ok(Alphabet { letters })
+    }
+
+    /// The letters `A` - `Z`.
+    pub fn latin() -> Alphabet {
+        Alphabet::new(ALPHABET_LATIN).unwrap()
+    }
+
+    /// The digits `0` - `9`.
+    pub fn digits() -> Alphabet {
+        Alphabet::new(ALPHABET_DIGITS).unwrap()
+    }
+
+    /// The digits of the Enigma Z, `1` - `9` followed by `0`.
+    pub fn z() -> Alphabet {
+        Alphabet::new(ALPHABET_Z).unwrap()
+    }
+
+    /// Whether this is the default `A` - `Z` alphabet.
+    pub fn is_latin(&self) -> bool {
+        self.letters.iter().copied().eq(ALPHABET_LATIN.chars())
+    }
+
+    /// The number of keys.
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    /// Always false, an alphabet has at least two keys.
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    /// The contact of a key, if it is part of the alphabet.
+    ///
+    /// Lower case letters are matched to upper case ones when the alphabet
+    /// only holds the latter.
+    pub fn index_of(&self, c: char) -> Option<u8> {
+        self.letters
+            .iter()
+            .position(|&letter| letter == c)
+            .or_else(|| {
+                let upper = c.to_ascii_uppercase();
+                self.letters.iter().position(|&letter| letter == upper)
+            })
+            .map(|i| i as u8)
+    }
+
+    /// The key at a contact.
+    ///
+    /// Panics if the contact is out of range.
+    pub fn letter(&self, index: u8) -> char {
+        self.letters[index as usize]
+    }
+
+    /// The keys in contact order.
+    pub fn letters(&self) -> &[char] {
+        &self.letters
+    }
+
+    /// Convert a wiring to contacts, checking that it lists every key of the
+    /// alphabet exactly once.
+    ///
+    /// * `wiring` - The key each contact is wired to, in contact order
+    pub fn wiring(&self, wiring: &str) -> Result<Vec<u8>, String> {
+        let mut contacts = Vec::with_capacity(self.len());
+        for c in wiring.trim().chars() {
+            let contact = self
+                .index_of(c)
+                .ok_or_else(|| format!("Invalid wiring: {}. {} is not a key", wiring, c))?;
+            if contacts.contains(&contact) {
+                return Err(format!(
+                    "Invalid wiring: {}. {} is wired more than once",
+                    wiring, c
+                ));
+            }
+            contacts.push(contact);
+        }
+
+        if contacts.len() != self.len() {
+            return Err(format!(
+                "Invalid wiring: {}. Expected {} keys",
+                wiring,
+                self.len()
+            ));
+        }
+
+        Ok(contacts)
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self::latin()
+    }
+}
+
+impl fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letters.iter().collect::<String>())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<String> for Alphabet {
+    type Error = String;
+
+    fn try_from(letters: String) -> Result<Self, Self::Error> {
+        Alphabet::new(&letters)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Alphabet> for String {
+    fn from(alphabet: Alphabet) -> Self {
+        alphabet.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin() {
+        let latin = Alphabet::default();
+        assert!(latin.is_latin());
+        assert_eq!(latin.len(), 26);
+        assert_eq!(latin.index_of('a'), Some(0));
+        assert_eq!(latin.index_of('Z'), Some(25));
+        assert_eq!(latin.index_of('1'), None);
+        assert!(!Alphabet::digits().is_latin());
+    }
+
+    #[test]
+    fn test_wiring() {
+        let digits = Alphabet::digits();
+        assert_eq!(
+            digits.wiring("9876543210").unwrap(),
+            vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 0]
+        );
+        assert!(digits.wiring("987654321").is_err());
+        assert!(digits.wiring("9876543219").is_err());
+        assert!(digits.wiring("987654321A").is_err());
+    }
+
+    #[test]
+    fn test_invalid_alphabets() {
+        assert!(Alphabet::new("A").is_err());
+        assert!(Alphabet::new("AB C").is_err());
+        assert!(Alphabet::new("ABCB").is_err());
+        assert!(Alphabet::new("aA").is_ok());
+    }
+}
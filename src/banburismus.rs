@@ -0,0 +1,382 @@
+//! Enigma Banburismus module.
+//!
+//! Banburismus compares messages sent under the same day key (wheel order,
+//! ring settings and plugboard) but with different message settings. When
+//! two messages are slid against each other so that they line up with the
+//! same machine state they are said to be "in depth" and letters repeat far
+//! more often than they would for two random strings.
+//!
+//! The [Banburismus](Banburismus) struct scores these repeats in decibans, a
+//! Bayesian weight of evidence that two messages are in depth at a given
+//! offset. Messages whose settings share the left and middle letters differ
+//! only by the right rotor, so whether or not they're in depth at the
+//! distance between their right letters tells us if the middle rotor turned
+//! over in between. That in turn tells us which rotors could be in the right
+//! position and cuts down the wheel orders a bombe run (see
+//! [CribAttack](crate::crib::CribAttack)) needs to test.
+//!
+//! ```
+//! use enigma::banburismus::{Banburismus, Message};
+//!
+//! let messages = vec![
+//!     Message::new("VFC", "QKZRUWTPNHDJOIBEYAPNFBSGWLKMKOBLTQIRTHGKEXNRCMVDZUSUYHXPQWAI").unwrap(),
+//!     Message::new("VFH", "NBAUWHOSAFLKQPTJXGZRCTHNEGLNPQUMMJRBSZYOVHDTEFKWQOBXSWBYEXKS").unwrap(),
+//! ];
+//! let banburismus = Banburismus::new(messages);
+//! let candidates = banburismus.right_rotor_candidates(&["I", "II", "III", "IV", "V"]);
+//! ```
+use crate::rotor::Rotor;
+
+/// A message intercepted under the day key being attacked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    setting: [u8; 3],
+    ciphertext: Vec<u8>,
+}
+
+impl Message {
+    /// Create a new message.
+    ///
+    /// * `setting` - The three letter message setting (left, middle and
+    ///   right rotor window letters) used to encrypt the message
+    /// * `ciphertext` - The encrypted message. Non-alphabetic characters are
+    ///   ignored.
+    pub fn new(setting: &str, ciphertext: &str) -> Result<Message, String> {
+        let letters: Vec<char> = setting.chars().collect();
+        if letters.len() != 3 || !letters.iter().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!(
+                "Invalid message setting {}. Three alphabetic characters required",
+                setting
+            ));
+        }
+
+        let mut values = [0; 3];
+        for (val, c) in values.iter_mut().zip(letters) {
+            *val = c.to_ascii_uppercase() as u8 - 65;
+        }
+
+        Ok(Message {
+            setting: values,
+            ciphertext: ciphertext
+                .chars()
+                .filter(|c| c.is_ascii_alphabetic())
+                .map(|c| c.to_ascii_uppercase() as u8 - 65)
+                .collect(),
+        })
+    }
+}
+
+/// The result of sliding one message against another.
+///
+/// `offset` is the number of letters the second message is shifted to the
+/// right relative to the first message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alignment {
+    pub offset: isize,
+    pub overlap: usize,
+    pub repeats: usize,
+    pub decibans: f64,
+}
+
+/// Evidence from a pair of messages that differ only in their right rotor
+/// setting.
+///
+/// The `first` message's right rotor is `distance` steps behind the
+/// `second` message's right rotor. If the middle rotor doesn't turn over in
+/// that span the messages are in depth at that offset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthEvidence {
+    pub first: usize,
+    pub second: usize,
+    pub distance: u8,
+    pub alignment: Alignment,
+}
+
+/// Deciban weighted Banburismus over a collection of messages.
+#[derive(Debug, Clone)]
+pub struct Banburismus {
+    messages: Vec<Message>,
+    depth_rate: f64,
+    random_rate: f64,
+}
+
+impl Banburismus {
+    /// Create a new Banburismus over the given messages.
+    ///
+    /// Defaults to Turing's rate of 1/17 for repeats between messages in
+    /// depth and 1/26 for repeats between random text.
+    pub fn new(messages: Vec<Message>) -> Banburismus {
+        Banburismus {
+            messages,
+            depth_rate: 1.0 / 17.0,
+            random_rate: 1.0 / 26.0,
+        }
+    }
+
+    /// Set the rate at which letters repeat between two messages in depth.
+    ///
+    /// This is the index of coincidence of the plaintext language.
+    pub fn depth_rate(mut self, rate: f64) -> Banburismus {
+        self.depth_rate = rate;
+        self
+    }
+
+    /// The weight of evidence, in decibans, that `first` and `second` are in
+    /// depth when `second` is shifted `offset` letters to the right.
+    pub fn score(&self, first: usize, second: usize, offset: isize) -> Alignment {
+        let a = &self.messages[first].ciphertext;
+        let b = &self.messages[second].ciphertext;
+
+        let (a_start, b_start) = if offset >= 0 {
+            (offset as usize, 0)
+        } else {
+            (0, offset.unsigned_abs())
+        };
+
+        let mut overlap = 0;
+        let mut repeats = 0;
+        for (x, y) in a.iter().skip(a_start).zip(b.iter().skip(b_start)) {
+            overlap += 1;
+            if x == y {
+                repeats += 1;
+            }
+        }
+
+        let repeat_weight = 10.0 * (self.depth_rate / self.random_rate).log10();
+        let miss_weight = 10.0 * ((1.0 - self.depth_rate) / (1.0 - self.random_rate)).log10();
+
+        Alignment {
+            offset,
+            overlap,
+            repeats,
+            decibans: repeats as f64 * repeat_weight + (overlap - repeats) as f64 * miss_weight,
+        }
+    }
+
+    /// Slide `second` against `first` over every offset up to `max_offset`
+    /// in either direction, strongest evidence of depth first.
+    pub fn slide(&self, first: usize, second: usize, max_offset: usize) -> Vec<Alignment> {
+        let max_offset = max_offset as isize;
+        let mut alignments: Vec<Alignment> = (-max_offset..=max_offset)
+            .map(|offset| self.score(first, second, offset))
+            .filter(|alignment| alignment.overlap > 0)
+            .collect();
+
+        alignments.sort_by(|a, b| b.decibans.total_cmp(&a.decibans));
+        alignments
+    }
+
+    /// Score every ordered pair of messages that share left and middle
+    /// rotor settings at the distance between their right rotor settings.
+    pub fn depth_evidence(&self) -> Vec<DepthEvidence> {
+        let mut evidence = Vec::new();
+
+        for (i, first) in self.messages.iter().enumerate() {
+            for (j, second) in self.messages.iter().enumerate() {
+                if first.setting[..2] != second.setting[..2]
+                    || first.setting[2] == second.setting[2]
+                {
+                    continue;
+                }
+
+                let distance = (second.setting[2] + 26 - first.setting[2]) % 26;
+                evidence.push(DepthEvidence {
+                    first: i,
+                    second: j,
+                    distance,
+                    alignment: self.score(i, j, distance as isize),
+                });
+            }
+        }
+
+        evidence
+    }
+
+    /// Score every right rotor turnover letter, strongest first.
+    ///
+    /// The turnover letter is the right rotor window letter that steps the
+    /// middle rotor on the next key press. Each candidate collects the
+    /// evidence of every message pair that would be in depth if the right
+    /// rotor turned over at that letter.
+    pub fn turnover_candidates(&self) -> Vec<(char, f64)> {
+        let evidence = self.depth_evidence();
+        let mut candidates: Vec<(char, f64)> = (0..26)
+            .map(|notch| {
+                let score = self.notch_score(&evidence, &[notch]);
+                ((notch + 65) as char, score)
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates
+    }
+
+    /// Score each rotor as the right rotor of the day key, strongest first.
+    ///
+    /// Rotors with two notches (`VI` through `VIII`) are handled.
+    pub fn right_rotor_candidates(&self, rotor_ids: &[&str]) -> Vec<(String, f64)> {
+        let evidence = self.depth_evidence();
+        let mut candidates: Vec<(String, f64)> = rotor_ids
+            .iter()
+            .map(|&id| {
+                let mut rotor = Rotor::new(id);
+                let notches: Vec<u8> = (0..26)
+                    .filter(|&pos| {
+                        rotor.set_rotor_pos(pos);
+                        rotor.will_step_next_rotor()
+                    })
+                    .collect();
+                (id.to_string(), self.notch_score(&evidence, &notches))
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates
+    }
+
+    /// Wheel orders that put one of the `keep` strongest right rotor
+    /// candidates in the right position.
+    ///
+    /// Wheel orders list the right (fast) rotor first to match
+    /// [CribAttack::wheel_orders](crate::crib::CribAttack::wheel_orders).
+    pub fn wheel_orders(&self, rotor_ids: &[&str], keep: usize) -> Vec<Vec<String>> {
+        let right_rotors: Vec<String> = self
+            .right_rotor_candidates(rotor_ids)
+            .into_iter()
+            .take(keep)
+            .map(|(id, _)| id)
+            .collect();
+
+        let mut wheel_orders = Vec::new();
+        for right in &right_rotors {
+            for &middle in rotor_ids {
+                for &left in rotor_ids {
+                    if middle == right || left == right || left == middle {
+                        continue;
+                    }
+                    wheel_orders.push(vec![right.clone(), middle.to_string(), left.to_string()]);
+                }
+            }
+        }
+
+        wheel_orders
+    }
+
+    /// Total evidence for the pairs that are in depth given a set of notches.
+    fn notch_score(&self, evidence: &[DepthEvidence], notches: &[u8]) -> f64 {
+        evidence
+            .iter()
+            .filter(|e| {
+                let start = self.messages[e.first].setting[2];
+                (0..e.distance).all(|step| !notches.contains(&((start + step) % 26)))
+            })
+            .map(|e| e.alignment.decibans)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::EnigmaMachine;
+
+    const PLAINTEXT: &str =
+        "Loremipsumdolorsitametconsecteturadipiscingelitseddoeiusmodtemporincididuntutl\
+         aboreetdoloremagnaaliquaUtenimadminimveniamquisnostrudexercitationullamcolabor\
+         isnisiutaliquipexeacommodoconsequatDuisauteiruredolorinreprehenderitinvoluptat\
+         evelitessecillumdoloreeufugiatnullapariaturExcepteursintoccaecatcupidatatnonpr\
+         oidentsuntinculpaquiofficiadeseruntmollitanimidestlaborum";
+
+    fn message(setting: &str, skip: usize) -> Message {
+        let pos: Vec<u8> = setting.bytes().map(|b| b - 65).collect();
+        let mut em = EnigmaMachine::builder()
+            .reflector("B")
+            .rotors(vec![
+                ("III".to_string(), pos[2], 4),
+                ("I".to_string(), pos[1], 11),
+                ("IV".to_string(), pos[0], 19),
+            ])
+            .plugboard(vec![('A', 'N'), ('E', 'T'), ('L', 'Q'), ('R', 'S')])
+            .build()
+            .unwrap();
+
+        let plaintext: String = PLAINTEXT.chars().skip(skip).collect();
+        Message::new(setting, &em.translate_text(plaintext.chars())).unwrap()
+    }
+
+    #[test]
+    fn test_message_setting_validation() {
+        assert!(Message::new("AB", "XYZ").is_err());
+        assert!(Message::new("A1C", "XYZ").is_err());
+        assert_eq!(Message::new("abc", "x.y z").unwrap().setting, [0, 1, 2]);
+        assert_eq!(
+            Message::new("abc", "x.y z").unwrap().ciphertext,
+            [23, 24, 25]
+        );
+    }
+
+    #[test]
+    fn test_score_counts_repeats() {
+        let b = Banburismus::new(vec![
+            Message::new("AAA", "ABCDEF").unwrap(),
+            Message::new("AAB", "XBCDYZ").unwrap(),
+        ]);
+
+        let alignment = b.score(0, 1, 0);
+        assert_eq!(alignment.overlap, 6);
+        assert_eq!(alignment.repeats, 3);
+        assert!(alignment.decibans > 0.0);
+
+        let alignment = b.score(0, 1, 1);
+        assert_eq!(alignment.overlap, 5);
+        assert_eq!(alignment.repeats, 0);
+        assert!(alignment.decibans < 0.0);
+
+        let alignment = b.score(0, 1, -2);
+        assert_eq!(alignment.overlap, 4);
+    }
+
+    #[test]
+    fn test_slide_finds_depth() {
+        // "AAF" is five right rotor steps past "AAA" so the messages line up
+        // at an offset of five.
+        let b = Banburismus::new(vec![message("AAA", 0), message("AAF", 30)]);
+        let best = b.slide(0, 1, 25)[0];
+        assert_eq!(best.offset, 5);
+        assert!(best.decibans > 0.0);
+    }
+
+    #[test]
+    fn test_right_rotor_identified() {
+        let messages = vec![
+            message("KMB", 0),
+            message("KMG", 40),
+            message("KMH", 80),
+            message("KMM", 120),
+            message("KMN", 10),
+            message("KMT", 60),
+            message("KMW", 100),
+            message("KMC", 20),
+        ];
+        let b = Banburismus::new(messages);
+
+        // Rotor III turns over at V. T and U fall between the same pair of
+        // message settings so they can't be told apart from V.
+        let turnovers = b.turnover_candidates();
+        let best = turnovers[0].1;
+        assert!(turnovers
+            .iter()
+            .any(|&(c, score)| c == 'V' && score == best));
+        assert!(turnovers
+            .iter()
+            .filter(|&&(_, score)| score == best)
+            .all(|&(c, _)| "TUV".contains(c)));
+
+        let candidates = b.right_rotor_candidates(&["I", "II", "III", "IV", "V"]);
+        assert_eq!(candidates[0].0, "III");
+
+        let wheel_orders = b.wheel_orders(&["I", "II", "III", "IV", "V"], 1);
+        assert_eq!(wheel_orders.len(), 12);
+        assert!(wheel_orders.iter().all(|order| order[0] == "III"));
+    }
+}
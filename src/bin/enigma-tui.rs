@@ -0,0 +1,533 @@
+//! Full-screen terminal Enigma simulator.
+//!
+//! Build and run with `cargo run --features tui --bin enigma-tui`. The
+//! simulator shows the rotor windows and ring settings, the lampboard, the
+//! keyboard and the plugboard of a machine built from an **enigma**
+//! configuration string.
+//!
+//! - Letters are typed on the machine and light the matching lamp
+//! - `Left` / `Right` select a rotor, `Up` / `Down` turn it and `PageUp` /
+//!   `PageDown` change its ring setting
+//! - Clicking a rotor window turns it forward (left button) or back (right
+//!   button). The scroll wheel works as well.
+//! - `F2` toggles plug mode. In plug mode type two letters to connect them
+//!   or a connected pair to remove the cable.
+//! - `F5` clears the tape and `Esc` quits
+use std::io;
+
+use clap::Parser;
+use enigma::config::Config;
+use enigma::session::Session;
+use ratatui::crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::crossterm::execute;
+use ratatui::layout::{Alignment, Constraint, Layout, Position, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+/// Keyboard and lampboard layout of the Enigma I.
+const KEY_ROWS: [&str; 3] = ["QWERTZUIO", "ASDFGHJK", "PYXCVBNML"];
+
+#[derive(Parser)]
+#[clap(author, version, about)]
+/// Full-screen terminal Enigma simulator.
+struct Cli {
+    /// Enigma machine configuration string. See `enigma --help` for details.
+    #[clap(short, long, value_parser, default_value = "B;I-A-A,II-A-A,III-A-A")]
+    config: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+    Typing,
+    Plugging,
+}
+
+struct Simulator {
+    // The machine and its settings. Changes go through the session, which
+    // rebuilds the machine with everything the configuration carries
+    session: Session,
+    mode: Mode,
+    selected: usize,
+    pending_plug: Option<char>,
+    key: Option<char>,
+    lamp: Option<char>,
+    input: String,
+    output: String,
+    status: String,
+    // Rotor window areas from the last draw, left rotor first
+    window_areas: Vec<Rect>,
+}
+
+impl Simulator {
+    fn new(cfg: Config) -> Result<Simulator, String> {
+        Ok(Simulator {
+            session: Session::from_config(cfg)?,
+            mode: Mode::Typing,
+            selected: 0,
+            pending_plug: None,
+            key: None,
+            lamp: None,
+            input: String::new(),
+            output: String::new(),
+            status: String::new(),
+            window_areas: Vec::new(),
+        })
+    }
+
+    /// Rotor id, position and ring setting of each rotor, right rotor first.
+    fn rotors(&self) -> Vec<(String, u8, u8)> {
+        self.session.config().rotor_config.rotor_ids
+    }
+
+    fn plugs(&self) -> Vec<(char, char)> {
+        self.session
+            .config()
+            .plugboard_config
+            .map(|pb| pb.plugboard_maps)
+            .unwrap_or_default()
+    }
+
+    /// Map a rotor index in display order (left rotor first) to builder
+    /// order.
+    fn rotor_index(&self, display_index: usize) -> usize {
+        self.session.positions().len() - 1 - display_index
+    }
+
+    /// One step forward or back, left to the session to wrap around.
+    fn step(&self, forward: bool) -> u8 {
+        if forward {
+            1
+        } else {
+            self.session.alphabet().len() as u8 - 1
+        }
+    }
+
+    fn turn_rotor(&mut self, display_index: usize, forward: bool) {
+        let i = self.rotor_index(display_index);
+        let mut positions = self.session.positions();
+        positions[i] += self.step(forward);
+        if let Err(err) = self.session.set_positions(&positions) {
+            self.status = err;
+        }
+    }
+
+    fn turn_ring(&mut self, display_index: usize, forward: bool) {
+        let i = self.rotor_index(display_index);
+        let mut rings: Vec<u8> = self.rotors().iter().map(|&(_, _, ring)| ring).collect();
+        rings[i] += self.step(forward);
+        if let Err(err) = self.session.set_rings(&rings) {
+            self.status = err;
+        }
+    }
+
+    fn press(&mut self, c: char) {
+        if self.session.alphabet().index_of(c).is_none() {
+            return;
+        }
+
+        let lamp = self.session.translate_text(&c.to_string());
+        if let Some(lamp) = lamp.chars().next() {
+            self.key = Some(c);
+            self.lamp = Some(lamp);
+            self.input.push(c);
+            self.output.push(lamp);
+        }
+    }
+
+    fn plug(&mut self, c: char) {
+        if self.session.config().uhr_config.is_some() {
+            self.status = "The Uhr cables can't be changed".to_string();
+            return;
+        }
+
+        let first = match self.pending_plug.take() {
+            Some(first) => first,
+            None => {
+                self.pending_plug = Some(c);
+                self.status = format!("Plug {} to ...", c);
+                return;
+            }
+        };
+
+        let plugged = self
+            .plugs()
+            .iter()
+            .any(|&(a, b)| (a, b) == (first, c) || (a, b) == (c, first));
+        self.status = if plugged {
+            match self.session.remove_plug(first, c) {
+                Ok(()) => format!("Removed plug {}-{}", first, c),
+                Err(err) => err,
+            }
+        } else {
+            match self.session.add_plug(first, c) {
+                Ok(()) => format!("Added plug {}-{}", first, c),
+                Err(err) => err,
+            }
+        };
+    }
+
+    /// Handle a key press. Returns false when the simulator should exit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.kind != KeyEventKind::Press {
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::F(2) => {
+                self.mode = match self.mode {
+                    Mode::Typing => Mode::Plugging,
+                    Mode::Plugging => Mode::Typing,
+                };
+                self.pending_plug = None;
+                self.status.clear();
+            }
+            KeyCode::F(5) => {
+                self.input.clear();
+                self.output.clear();
+                self.key = None;
+                self.lamp = None;
+            }
+            KeyCode::Left => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Right => {
+                self.selected = (self.selected + 1).min(self.session.positions().len() - 1)
+            }
+            KeyCode::Up => self.turn_rotor(self.selected, true),
+            KeyCode::Down => self.turn_rotor(self.selected, false),
+            KeyCode::PageUp => self.turn_ring(self.selected, true),
+            KeyCode::PageDown => self.turn_ring(self.selected, false),
+            KeyCode::Char(c) if c.is_ascii_alphabetic() => {
+                let c = c.to_ascii_uppercase();
+                match self.mode {
+                    Mode::Typing => self.press(c),
+                    Mode::Plugging => self.plug(c),
+                }
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let position = Position::new(mouse.column, mouse.row);
+        let clicked = match self
+            .window_areas
+            .iter()
+            .position(|area| area.contains(position))
+        {
+            Some(clicked) => clicked,
+            None => return,
+        };
+
+        self.selected = clicked;
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::ScrollUp => {
+                self.turn_rotor(clicked, true)
+            }
+            MouseEventKind::Down(MouseButton::Right) | MouseEventKind::ScrollDown => {
+                self.turn_rotor(clicked, false)
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [windows_area, lamps_area, keys_area, plugs_area, tape_area, help_area] =
+            Layout::vertical([
+                Constraint::Length(6),
+                Constraint::Length(5),
+                Constraint::Length(5),
+                Constraint::Length(3),
+                Constraint::Length(4),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+
+        self.draw_windows(frame, windows_area);
+        frame.render_widget(board("Lampboard", self.lamp, Color::Yellow), lamps_area);
+        frame.render_widget(board("Keyboard", self.key, Color::Gray), keys_area);
+        self.draw_plugboard(frame, plugs_area);
+
+        let tape = Paragraph::new(vec![
+            Line::from(format!("In:  {}", self.input)),
+            Line::from(format!("Out: {}", self.output)),
+        ])
+        .block(Block::default().borders(Borders::ALL).title(" Tape "));
+        frame.render_widget(tape, tape_area);
+
+        let help = if self.status.is_empty() {
+            "Type to encrypt | ←/→ select rotor | ↑/↓ position | PgUp/PgDn ring | \
+             F2 plugs | F5 clear | Esc quit"
+                .to_string()
+        } else {
+            self.status.clone()
+        };
+        frame.render_widget(
+            Paragraph::new(help).style(Style::default().add_modifier(Modifier::DIM)),
+            help_area,
+        );
+    }
+
+    fn draw_windows(&mut self, frame: &mut Frame, area: Rect) {
+        let cfg = self.session.config();
+        let rotors = &cfg.rotor_config.rotor_ids;
+        let mut constraints = vec![Constraint::Length(9)];
+        constraints.extend(rotors.iter().map(|_| Constraint::Length(11)));
+        constraints.push(Constraint::Min(0));
+        let areas = Layout::horizontal(constraints).split(area);
+
+        let reflector = Paragraph::new(vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                cfg.reflector_config.reflector_id.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ])
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).title(" UKW "));
+        frame.render_widget(reflector, areas[0]);
+
+        self.window_areas.clear();
+        for display_index in 0..rotors.len() {
+            let (id, pos, ring) = &rotors[self.rotor_index(display_index)];
+            let border = if display_index == self.selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+
+            let window = Paragraph::new(vec![
+                Line::from(""),
+                Line::from(Span::styled(
+                    format!("[ {} ]", self.session.alphabet().letter(*pos)),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+                Line::from(""),
+                Line::from(format!("Ring {:02}", ring + 1)),
+            ])
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border)
+                    .title(format!(" {} ", id)),
+            );
+
+            let window_area = areas[display_index + 1];
+            frame.render_widget(window, window_area);
+            self.window_areas.push(window_area);
+        }
+    }
+
+    fn draw_plugboard(&self, frame: &mut Frame, area: Rect) {
+        let mut spans: Vec<Span> = self
+            .plugs()
+            .iter()
+            .map(|(a, b)| Span::raw(format!("{}{} ", a, b)))
+            .collect();
+
+        if let Some(pending) = self.pending_plug {
+            spans.push(Span::styled(
+                format!("{}_", pending),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        let title = match self.mode {
+            Mode::Typing => " Plugboard ".to_string(),
+            Mode::Plugging => " Plugboard (plug mode, F2 to finish) ".to_string(),
+        };
+        let border = match self.mode {
+            Mode::Typing => Style::default(),
+            Mode::Plugging => Style::default().fg(Color::Yellow),
+        };
+
+        let plugboard = Paragraph::new(Line::from(spans)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border)
+                .title(title),
+        );
+        frame.render_widget(plugboard, area);
+    }
+}
+
+/// A lampboard or keyboard with the `lit` letter highlighted.
+fn board(title: &str, lit: Option<char>, color: Color) -> Paragraph<'static> {
+    let lines: Vec<Line> = KEY_ROWS
+        .iter()
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .chars()
+                .map(|c| {
+                    let style = if Some(c) == lit {
+                        Style::default()
+                            .bg(color)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    };
+                    Span::styled(format!(" {} ", c), style)
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+
+    Paragraph::new(lines).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", title)),
+    )
+}
+
+fn run(terminal: &mut DefaultTerminal, simulator: &mut Simulator) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| simulator.draw(frame))?;
+
+        match event::read()? {
+            Event::Key(key) if !simulator.handle_key(key) => return Ok(()),
+            Event::Mouse(mouse) => simulator.handle_mouse(mouse),
+            _ => {}
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    let cfg = Config::parse(cli.config).expect("Invalid enigma config provided");
+    let mut simulator = Simulator::new(cfg).unwrap_or_else(|err| {
+        eprintln!("Invalid enigma config provided: {}", err);
+        std::process::exit(1);
+    });
+
+    let mut terminal = ratatui::init();
+    execute!(io::stdout(), EnableMouseCapture)?;
+    let result = run(&mut terminal, &mut simulator);
+    execute!(io::stdout(), DisableMouseCapture)?;
+    ratatui::restore();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enigma::entry_wheel::EntryWheel;
+    use enigma::machine::EnigmaMachine;
+    use enigma::model::EnigmaModel;
+    use enigma::stepping::Stepping;
+    use ratatui::crossterm::event::KeyModifiers;
+
+    fn simulator(cfg: &str) -> Simulator {
+        Simulator::new(Config::parse(cfg.to_string()).unwrap()).unwrap()
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_press() {
+        let mut sim = simulator("B;I-A-A,II-A-A,III-A-A");
+        for _ in 0..5 {
+            sim.press('A');
+        }
+        assert_eq!(sim.output, "BDZGO");
+        assert_eq!((sim.key, sim.lamp), (Some('A'), Some('O')));
+        assert_eq!(sim.session.config_string(), "B;I-A-A,II-A-A,III-F-A");
+    }
+
+    #[test]
+    fn test_turn_rotors_and_rings() {
+        let mut sim = simulator("B;I-A-A,II-A-A,III-Z-A");
+        sim.turn_rotor(2, true);
+        sim.turn_rotor(0, false);
+        sim.turn_ring(1, false);
+        sim.turn_ring(1, true);
+        sim.turn_ring(1, true);
+        assert_eq!(sim.session.config_string(), "B;I-Z-A,II-A-B,III-A-A");
+    }
+
+    #[test]
+    fn test_plug() {
+        let mut sim = simulator("B;I-A-A,II-A-A,III-A-A;a-b");
+        sim.plug('C');
+        sim.plug('C');
+        assert_eq!(sim.status, "Can't plug C into itself");
+        sim.plug('A');
+        sim.plug('C');
+        assert_eq!(sim.plugs(), vec![('A', 'B')]);
+
+        sim.plug('D');
+        sim.plug('C');
+        sim.plug('B');
+        sim.plug('A');
+        assert_eq!(sim.plugs(), vec![('C', 'D')]);
+        assert_eq!(sim.status, "Removed plug B-A");
+
+        let mut sim =
+            simulator("B;I-A-A,II-A-A,III-A-A;a-b,c-d,e-f,g-h,i-j,k-l,m-n,o-p,q-r,s-t;UHR-1");
+        sim.plug('U');
+        sim.plug('V');
+        assert_eq!(sim.status, "The Uhr cables can't be changed");
+    }
+
+    #[test]
+    fn test_handle_key() {
+        let mut sim = simulator("B;I-A-A,II-A-A,III-A-A");
+        assert!(sim.handle_key(key(KeyCode::F(2))));
+        assert_eq!(sim.mode, Mode::Plugging);
+        sim.handle_key(key(KeyCode::Char('a')));
+        sim.handle_key(key(KeyCode::Char('b')));
+        sim.handle_key(key(KeyCode::F(2)));
+        sim.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(sim.output, "B");
+
+        sim.handle_key(key(KeyCode::Right));
+        sim.handle_key(key(KeyCode::Right));
+        sim.handle_key(key(KeyCode::Right));
+        assert_eq!(sim.selected, 2);
+        sim.handle_key(key(KeyCode::Down));
+        assert_eq!(sim.session.config_string(), "B;I-A-A,II-A-A,III-A-A;a-b");
+        assert!(!sim.handle_key(key(KeyCode::Esc)));
+    }
+
+    #[test]
+    fn test_rebuild_keeps_model_entry_wheel_and_stepping() {
+        let mut em = EnigmaMachine::builder()
+            .entry_wheel(EntryWheel::qwertz())
+            .stepping(Stepping::Odometer)
+            .reflector("B")
+            .rotors(vec![
+                ("I".to_string(), 0, 0),
+                ("II".to_string(), 0, 0),
+                ("III".to_string(), 0, 0),
+            ])
+            .try_build()
+            .unwrap();
+        let mut sim = Simulator::new(em.config()).unwrap();
+        sim.turn_rotor(0, true);
+        sim.turn_rotor(0, false);
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(30);
+        for c in text.chars() {
+            sim.press(c);
+        }
+        assert_eq!(sim.output, em.translate_text(text.chars()));
+
+        // The Enigma K has no plugboard
+        let cfg = Config::parse("UKWK;IIIK-A-A,IIK-A-A,IK-A-A".to_string()).unwrap();
+        let mut sim = Simulator::new(cfg.with_model(EnigmaModel::K)).unwrap();
+        sim.plug('A');
+        sim.plug('B');
+        assert!(sim.plugs().is_empty());
+        assert_eq!(sim.status, "The Enigma K has no plugboard");
+    }
+}
@@ -0,0 +1,1104 @@
+//! Enigma configuration string module.
+//!
+//! [Config](Config) parses the configuration strings accepted by the
+//! **enigma** binaries and builds the [EnigmaMachine](EnigmaMachine) they
+//! describe. See the crate documentation for the string format.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+use crate::entry_wheel::EntryWheel;
+use crate::machine::EnigmaMachine;
+use crate::model::EnigmaModel;
+use crate::reflector::Reflector;
+use crate::stepping::Stepping;
+use crate::uhr::{Uhr, UHR_POSITIONS};
+
+pub(crate) const REFLECTOR_IDS: [&str; 11] = [
+    "A", "B", "C", "B-thin", "C-thin", "UKWK", "UKWR", "UKWG", "UKWG312", "UKWT", "UKWZ",
+];
+pub(crate) const ROTOR_IDS: [&str; 36] = [
+    "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "Beta", "Gamma", "IK", "IIK", "IIIK", "ISK",
+    "IISK", "IIISK", "IR", "IIR", "IIIR", "IG", "IIG", "IIIG", "IG312", "IIG312", "IIIG312", "IT",
+    "IIT", "IIIT", "IVT", "VT", "VIT", "VIIT", "VIIIT", "IZ", "IIZ", "IIIZ",
+];
+
+/// Look up the canonical spelling of a reflector id, ignoring case.
+pub(crate) fn reflector_id(id: &str) -> Result<&'static str, String> {
+    REFLECTOR_IDS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(id.trim()))
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Invalid reflector id: {}. Expected one of {}",
+                id,
+                REFLECTOR_IDS.join(", ")
+            )
+        })
+}
+
+/// Look up the canonical spelling of a rotor id, ignoring case.
+pub(crate) fn rotor_id(id: &str) -> Result<&'static str, String> {
+    ROTOR_IDS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(id.trim()))
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Invalid rotor id: {}. Expected one of {}",
+                id,
+                ROTOR_IDS.join(", ")
+            )
+        })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Config {
+    pub reflector_config: ReflectorConfig,
+    pub rotor_config: RotorConfig,
+    pub plugboard_config: Option<PlugBoardConfig>,
+    /// An Uhr plugged into the plugboard. The plugboard pairs are then its
+    /// cables `1` - `10`, listing the `a` plug first.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub uhr_config: Option<UhrConfig>,
+    /// The model the machine is built as. Without one the entry wheel and
+    /// stepping come from the reflector's model and nothing is checked.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub model: Option<EnigmaModel>,
+    /// An entry wheel other than the one the reflector's model shipped
+    /// with. Configuration strings can't describe one.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub entry_wheel: Option<EntryWheel>,
+    /// Stepping other than that of the reflector's model. Configuration
+    /// strings can't describe it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub stepping: Option<Stepping>,
+}
+
+impl Config {
+    /// Create a new configuration from its parts.
+    ///
+    /// Rotors are listed right (fast) rotor first, matching
+    /// [EnigmaMachineBuilder::rotors](crate::machine::EnigmaMachineBuilder::rotors).
+    pub fn new(
+        reflector_id: &str,
+        rotor_ids: Vec<(String, u8, u8)>,
+        plugboard_maps: Vec<(char, char)>,
+    ) -> Config {
+        Config {
+            reflector_config: ReflectorConfig {
+                reflector_id: reflector_id.to_string(),
+                position: 0,
+                ring: 0,
+            },
+            rotor_config: RotorConfig { rotor_ids },
+            plugboard_config: if plugboard_maps.is_empty() {
+                None
+            } else {
+                Some(PlugBoardConfig { plugboard_maps })
+            },
+            uhr_config: None,
+            model: None,
+            entry_wheel: None,
+            stepping: None,
+        }
+    }
+
+    pub fn parse(config: String) -> Option<Config> {
+        let mut cfg_parts: Vec<String> = config.split(';').map(|s| s.to_string()).collect();
+
+        if !(2..=4).contains(&cfg_parts.len()) {
+            eprintln!(
+                "Invalid number of config components encountered: {} {:?}",
+                cfg_parts.len(),
+                config
+            );
+            return None;
+        }
+
+        let reflector_config = ReflectorConfig::parse(&mut cfg_parts[0]);
+        let alphabet = reflector_config
+            .as_ref()
+            .map(|reflcfg| model_alphabet(None, &reflcfg.reflector_id))
+            .unwrap_or_default();
+        let rotor_config = RotorConfig::parse(&mut cfg_parts[1], &alphabet);
+        let plugboard_config = if cfg_parts.len() >= 3 {
+            match PlugBoardConfig::parse(&mut cfg_parts[2]) {
+                Some(pbcfg) => Some(pbcfg),
+                None => return None,
+            }
+        } else {
+            None
+        };
+        let uhr_config = if cfg_parts.len() == 4 {
+            match UhrConfig::parse(&mut cfg_parts[3]) {
+                Some(uhrcfg) => Some(uhrcfg),
+                None => return None,
+            }
+        } else {
+            None
+        };
+
+        if reflector_config.is_none() || rotor_config.is_none() {
+            eprintln!(
+                "Missing required config component. Refl: {:?} Rotor: {:?}",
+                reflector_config, rotor_config
+            );
+            return None;
+        }
+
+        let cfg = Config {
+            reflector_config: reflector_config.unwrap(),
+            rotor_config: rotor_config.unwrap(),
+            plugboard_config,
+            uhr_config,
+            model: None,
+            entry_wheel: None,
+            stepping: None,
+        };
+        if let Err(err) = cfg.uhr() {
+            eprintln!("{}", err);
+            return None;
+        }
+
+        Some(cfg)
+    }
+
+    /// Set the position and ring setting of a settable reflector, e.g.,
+    /// `UKWK`.
+    pub fn with_reflector_state(mut self, position: u8, ring: u8) -> Config {
+        self.reflector_config.position = position;
+        self.reflector_config.ring = ring;
+        self
+    }
+
+    /// Plug in an [Uhr](Uhr), replacing the plugboard pairs with its cables.
+    pub fn with_uhr(mut self, uhr: &Uhr) -> Config {
+        self.plugboard_config = Some(PlugBoardConfig {
+            plugboard_maps: uhr.plugs(),
+        });
+        self.uhr_config = Some(UhrConfig {
+            position: uhr.position(),
+        });
+        self
+    }
+
+    /// Build the machine as a model, rejecting settings it never had.
+    pub fn with_model(mut self, model: EnigmaModel) -> Config {
+        self.model = Some(model);
+        self
+    }
+
+    /// Use an entry wheel other than the one the reflector's model shipped
+    /// with.
+    pub fn with_entry_wheel(mut self, entry_wheel: EntryWheel) -> Config {
+        self.entry_wheel = Some(entry_wheel);
+        self
+    }
+
+    /// Use stepping other than that of the reflector's model.
+    pub fn with_stepping(mut self, stepping: Stepping) -> Config {
+        self.stepping = Some(stepping);
+        self
+    }
+
+    /// The keys of the machine, those of the model or else of the
+    /// reflector's model. Positions and ring settings are keys of it.
+    pub fn alphabet(&self) -> Alphabet {
+        model_alphabet(self.model, &self.reflector_config.reflector_id)
+    }
+
+    /// The [Uhr](Uhr) described by the configuration, if any.
+    pub fn uhr(&self) -> Result<Option<Uhr>, String> {
+        let uhrcfg = match &self.uhr_config {
+            Some(uhrcfg) => uhrcfg,
+            None => return Ok(None),
+        };
+        let plugs = self
+            .plugboard_config
+            .as_ref()
+            .map(|pbcfg| pbcfg.plugboard_maps.clone())
+            .unwrap_or_default();
+
+        Uhr::new(plugs, uhrcfg.position).map(Some)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReflectorConfig {
+    pub reflector_id: String,
+    /// Position of a settable reflector, `0` for `A`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub position: u8,
+    /// Ring setting of a settable reflector, `0` for `A`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ring: u8,
+}
+
+impl ReflectorConfig {
+    pub fn parse(config: &mut String) -> Option<ReflectorConfig> {
+        config.retain(|c| !c.is_whitespace());
+
+        match parse_reflector(config) {
+            Ok((id, position, ring)) => Some(ReflectorConfig {
+                reflector_id: id.to_string(),
+                position,
+                ring,
+            }),
+            Err(err) => {
+                eprintln!("{}", err);
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RotorConfig {
+    pub rotor_ids: Vec<(String, u8, u8)>,
+}
+
+impl RotorConfig {
+    /// Parse rotors listed left rotor first, with positions and ring
+    /// settings given as keys of `alphabet`, e.g., `I-A-A,II-A-A,III-A-A`.
+    pub fn parse(config: &mut String, alphabet: &Alphabet) -> Option<RotorConfig> {
+        config.retain(|c| !c.is_whitespace() && c != '(' && c != ')');
+
+        //TODO: This should be something supported in the library
+
+        let rotor_cfgs: Vec<String> = config.split(',').map(|s| s.to_string()).rev().collect();
+
+        if rotor_cfgs.len() != 3 && rotor_cfgs.len() != 4 {
+            eprintln!("Invalid rotor config count: {:?}", rotor_cfgs);
+            return None;
+        }
+
+        let mut rotor_ids: Vec<(String, u8, u8)> = Vec::new();
+        for rotor_cfg in rotor_cfgs {
+            let cfg_elems: Vec<&str> = rotor_cfg.split('-').collect();
+            if cfg_elems.len() != 3 {
+                eprintln!("Invalid rotor config: {}", rotor_cfg);
+                return None;
+            }
+
+            let id = match rotor_id(cfg_elems[0]) {
+                Ok(id) => id,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return None;
+                }
+            };
+
+            let pos: char = match cfg_elems[1].chars().next() {
+                Some(pos) => pos,
+                None => {
+                    eprintln!("Invalid rotor position: empty");
+                    return None;
+                }
+            };
+            let pos = match alphabet.index_of(pos) {
+                Some(pos) => pos,
+                None => {
+                    eprintln!("Invalid rotor position: {}", pos);
+                    return None;
+                }
+            };
+
+            let ring_loc: char = match cfg_elems[2].chars().next() {
+                Some(ring_loc) => ring_loc,
+                None => {
+                    eprintln!("Invalid rotor ring location: empty");
+                    return None;
+                }
+            };
+            let ring_loc = match alphabet.index_of(ring_loc) {
+                Some(ring_loc) => ring_loc,
+                None => {
+                    eprintln!("Invalid rotor ring location: {}", ring_loc);
+                    return None;
+                }
+            };
+
+            rotor_ids.push((id.to_string(), pos, ring_loc))
+        }
+
+        Some(RotorConfig { rotor_ids })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlugBoardConfig {
+    pub plugboard_maps: Vec<(char, char)>,
+}
+
+impl PlugBoardConfig {
+    pub fn parse(config: &mut String) -> Option<PlugBoardConfig> {
+        config.retain(|c| !c.is_whitespace() && c != '(' && c != ')');
+
+        // TODO: This should be something supported in the library
+        let pb_maps: Vec<String> = config.split(',').map(|s| s.to_string()).collect();
+
+        let mut plugboard_maps: Vec<(char, char)> = Vec::new();
+        for map in pb_maps {
+            let chs: Vec<char> = map.to_ascii_uppercase().chars().collect();
+            if chs.len() != 3 {
+                eprintln!("Invalid plugboard config received: {}", map);
+                return None;
+            }
+
+            if !chs[0].is_ascii_alphabetic() || !chs[2].is_ascii_alphabetic() {
+                eprintln!("Plugboard config contains non alphabetic chars: {}", map);
+                return None;
+            }
+
+            plugboard_maps.push((chs[0], chs[2]));
+        }
+
+        Some(PlugBoardConfig { plugboard_maps })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UhrConfig {
+    /// Switch position, `0` - `39`
+    pub position: u8,
+}
+
+impl UhrConfig {
+    /// Parse an Uhr setting, e.g., `UHR-27`.
+    pub fn parse(config: &mut String) -> Option<UhrConfig> {
+        config.retain(|c| !c.is_whitespace());
+
+        match parse_uhr(config) {
+            Ok(position) => Some(UhrConfig { position }),
+            Err(err) => {
+                eprintln!("{}", err);
+                None
+            }
+        }
+    }
+}
+
+impl Config {
+    pub fn build_machine(self) -> EnigmaMachine {
+        let uhr = self.uhr().unwrap();
+        let builder = EnigmaMachine::builder();
+        let builder = builder
+            .reflector_with_state(
+                &self.reflector_config.reflector_id,
+                self.reflector_config.position,
+                self.reflector_config.ring,
+            )
+            .rotors(self.rotor_config.rotor_ids);
+
+        // TODO: This is a friggen hideous way to need to handle this ...
+        let builder = match (uhr, self.plugboard_config) {
+            (Some(uhr), _) => builder.uhr(uhr),
+            (None, Some(pbcfg)) => builder.plugboard(pbcfg.plugboard_maps),
+            (None, None) => builder,
+        };
+        let builder = match self.model {
+            Some(model) => builder.model(model),
+            None => builder,
+        };
+        let builder = match self.entry_wheel {
+            Some(entry_wheel) => builder.entry_wheel(entry_wheel),
+            None => builder,
+        };
+        let builder = match self.stepping {
+            Some(stepping) => builder.stepping(stepping),
+            None => builder,
+        };
+
+        builder.build().unwrap()
+    }
+
+    /// Check that the configuration could have been set on the given model.
+    pub fn validate_model(&self, model: EnigmaModel) -> Result<(), String> {
+        let rotor_ids: Vec<&str> = self
+            .rotor_config
+            .rotor_ids
+            .iter()
+            .map(|(id, _, _)| id.as_str())
+            .collect();
+        let has_plugs = self
+            .plugboard_config
+            .as_ref()
+            .is_some_and(|pbcfg| !pbcfg.plugboard_maps.is_empty());
+
+        model.validate(&self.reflector_config.reflector_id, &rotor_ids, has_plugs)
+    }
+}
+
+/// Formats the configuration as a configuration string that
+/// [Config::parse](Config::parse) accepts.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alphabet = self.alphabet();
+        let rotors: Vec<String> = self
+            .rotor_config
+            .rotor_ids
+            .iter()
+            .rev()
+            .map(|(id, pos, ring)| {
+                format!(
+                    "{}-{}-{}",
+                    id,
+                    alphabet.letter(*pos),
+                    alphabet.letter(*ring)
+                )
+            })
+            .collect();
+        let refl = &self.reflector_config;
+        if refl.position == 0 && refl.ring == 0 {
+            write!(f, "{};{}", refl.reflector_id, rotors.join(","))?;
+        } else {
+            write!(
+                f,
+                "{}-{}-{};{}",
+                refl.reflector_id,
+                alphabet.letter(refl.position),
+                alphabet.letter(refl.ring),
+                rotors.join(",")
+            )?;
+        }
+
+        if let Some(pbcfg) = &self.plugboard_config {
+            let plugs: Vec<String> = pbcfg
+                .plugboard_maps
+                .iter()
+                .map(|(a, b)| format!("{}-{}", a.to_ascii_lowercase(), b.to_ascii_lowercase()))
+                .collect();
+            write!(f, ";{}", plugs.join(","))?;
+        }
+
+        if let Some(uhrcfg) = &self.uhr_config {
+            write!(f, ";UHR-{:02}", uhrcfg.position)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Machine settings given piece by piece, e.g., from command line flags.
+///
+/// Every setting is optional and replaces the matching part of a base
+/// configuration in [apply](ConfigOverrides::apply). Rotor settings are
+/// listed as seen from the front of the machine, i.e., left rotor first.
+///
+/// * `reflector` - Reflector id, e.g., `B`, or a settable reflector with its
+///   position and ring setting, e.g., `UKWK-B-A`
+/// * `rotors` - Wheel order, e.g., `I II III`
+/// * `rings` - Ring settings as numbers or letters, e.g., `01 01 01` or `AAA`.
+///   The Enigma Z takes digits, e.g., `111`.
+/// * `positions` - Start positions, e.g., `AAA`, or digits on the Enigma Z
+/// * `plugs` - Plugboard pairs, e.g., `AB CD`. An empty string removes all
+///   plugs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigOverrides {
+    pub reflector: Option<String>,
+    pub rotors: Option<String>,
+    pub rings: Option<String>,
+    pub positions: Option<String>,
+    pub plugs: Option<String>,
+}
+
+impl ConfigOverrides {
+    /// Returns true if no setting is given.
+    pub fn is_empty(&self) -> bool {
+        *self == ConfigOverrides::default()
+    }
+
+    /// Layer the settings over `base` and validate the result.
+    ///
+    /// Without a base configuration both `reflector` and `rotors` are
+    /// required. Rotors keep their positions and ring settings from the base
+    /// configuration unless the number of rotors changes, in which case they
+    /// start at `A`.
+    pub fn apply(&self, base: Option<Config>) -> Result<Config, String> {
+        let reflector = match (&self.reflector, &base) {
+            (Some(spec), _) => {
+                let (id, position, ring) = parse_reflector(spec)?;
+                ReflectorConfig {
+                    reflector_id: id.to_string(),
+                    position,
+                    ring,
+                }
+            }
+            (None, Some(cfg)) => cfg.reflector_config.clone(),
+            (None, None) => {
+                return Err("No reflector given. Use --reflector or --config".to_string())
+            }
+        };
+
+        let alphabet = model_alphabet(
+            base.as_ref().and_then(|cfg| cfg.model),
+            &reflector.reflector_id,
+        );
+        let base_rotors = base.as_ref().map(|cfg| cfg.rotor_config.rotor_ids.clone());
+        let mut rotor_ids = match (&self.rotors, base_rotors) {
+            (Some(rotors), base_rotors) => {
+                let ids = parse_wheel_order(rotors)?;
+                match base_rotors {
+                    Some(base_rotors) if base_rotors.len() == ids.len() => ids
+                        .into_iter()
+                        .zip(base_rotors)
+                        .map(|(id, (_, pos, ring))| (id, pos, ring))
+                        .collect(),
+                    _ => ids.into_iter().map(|id| (id, 0, 0)).collect(),
+                }
+            }
+            (None, Some(base_rotors)) => base_rotors,
+            (None, None) => return Err("No rotors given. Use --rotors or --config".to_string()),
+        };
+
+        if let Some(rings) = &self.rings {
+            let rings = parse_rings(rings, &alphabet)?;
+            check_rotor_count("ring settings", rings.len(), rotor_ids.len())?;
+            for ((_, _, ring), new_ring) in rotor_ids.iter_mut().zip(rings) {
+                *ring = new_ring;
+            }
+        }
+
+        if let Some(positions) = &self.positions {
+            let positions = parse_positions(positions, &alphabet)?;
+            check_rotor_count("positions", positions.len(), rotor_ids.len())?;
+            for ((_, pos, _), new_pos) in rotor_ids.iter_mut().zip(positions) {
+                *pos = new_pos;
+            }
+        }
+
+        let uhr_config = base.as_ref().and_then(|cfg| cfg.uhr_config.clone());
+        let model = base.as_ref().and_then(|cfg| cfg.model);
+        let entry_wheel = base.as_ref().and_then(|cfg| cfg.entry_wheel.clone());
+        let stepping = base.as_ref().and_then(|cfg| cfg.stepping.clone());
+        let plugs = match (&self.plugs, base) {
+            (Some(plugs), _) => parse_plugs(plugs)?,
+            (None, Some(cfg)) => cfg
+                .plugboard_config
+                .map(|pbcfg| pbcfg.plugboard_maps)
+                .unwrap_or_default(),
+            (None, None) => Vec::new(),
+        };
+
+        let mut cfg = Config::new(&reflector.reflector_id, rotor_ids, plugs)
+            .with_reflector_state(reflector.position, reflector.ring);
+        cfg.uhr_config = uhr_config;
+        cfg.model = model;
+        cfg.entry_wheel = entry_wheel;
+        cfg.stepping = stepping;
+        cfg.uhr()?;
+        Ok(cfg)
+    }
+}
+
+/// The alphabet of a machine built as `model`, or without one as the
+/// reflector's model, matching
+/// [EnigmaMachineBuilder::build](crate::machine::EnigmaMachineBuilder::build).
+pub(crate) fn model_alphabet(model: Option<EnigmaModel>, reflector_id: &str) -> Alphabet {
+    model
+        .or_else(|| EnigmaModel::for_reflector(reflector_id))
+        .map(|model| model.alphabet())
+        .unwrap_or_default()
+}
+
+/// Parse a reflector, optionally followed by the position and ring setting
+/// of a settable reflector, e.g., `B`, `UKWK-B-A` or `UKWZ-1-1`.
+///
+/// Returns the canonical reflector id, position and ring setting.
+pub fn parse_reflector(spec: &str) -> Result<(&'static str, u8, u8), String> {
+    if let Ok(id) = reflector_id(spec) {
+        return Ok((id, 0, 0));
+    }
+
+    let parts: Vec<&str> = spec.trim().rsplitn(3, '-').collect();
+    if parts.len() != 3 {
+        // Report the unknown id
+        return reflector_id(spec).map(|id| (id, 0, 0));
+    }
+
+    let id = reflector_id(parts[2])?;
+    let alphabet = model_alphabet(None, id);
+    let mut settings = [0; 2];
+    for (setting, part) in settings.iter_mut().zip([parts[1], parts[0]]) {
+        let mut chars = part.chars();
+        *setting = match (chars.next(), chars.next()) {
+            (Some(c), None) => alphabet.index_of(c),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            format!(
+                "Invalid reflector setting: {}. Expected <id>-<position>-<ring>, e.g., UKWK-B-A",
+                spec
+            )
+        })?;
+    }
+
+    if !Reflector::new(id).is_settable() {
+        return Err(format!("Reflector {} can't be set", id));
+    }
+
+    Ok((id, settings[0], settings[1]))
+}
+
+/// Parse an Uhr setting, e.g., `UHR-27`, returning the switch position.
+pub fn parse_uhr(spec: &str) -> Result<u8, String> {
+    let err = || {
+        format!(
+            "Invalid Uhr setting: {}. Expected UHR-<position>, e.g., UHR-27",
+            spec
+        )
+    };
+
+    let (id, position) = spec.trim().split_once('-').ok_or_else(err)?;
+    if !id.eq_ignore_ascii_case("UHR") {
+        return Err(err());
+    }
+    match position.parse::<u8>() {
+        Ok(position) if position < UHR_POSITIONS => Ok(position),
+        _ => Err(format!(
+            "Invalid Uhr position: {}. Expected 00 - 39",
+            position
+        )),
+    }
+}
+
+fn check_rotor_count(setting: &str, count: usize, rotor_count: usize) -> Result<(), String> {
+    if count != rotor_count {
+        return Err(format!(
+            "Expected {} {}, one for each rotor. Received {}",
+            rotor_count, setting, count
+        ));
+    }
+    Ok(())
+}
+
+fn split_list(list: &str) -> Vec<&str> {
+    list.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Parse a wheel order listed left rotor first, e.g., `I II III`.
+///
+/// The rotors are returned right rotor first, matching
+/// [EnigmaMachineBuilder::rotors](crate::machine::EnigmaMachineBuilder::rotors).
+pub fn parse_wheel_order(rotors: &str) -> Result<Vec<String>, String> {
+    let ids: Vec<String> = split_list(rotors)
+        .iter()
+        .rev()
+        .map(|id| rotor_id(id).map(String::from))
+        .collect::<Result<_, _>>()?;
+
+    if ids.len() != 3 && ids.len() != 4 {
+        return Err(format!(
+            "Expected 3 or 4 rotors. Received {}: {}",
+            ids.len(),
+            rotors
+        ));
+    }
+
+    Ok(ids)
+}
+
+/// Parse a ring setting given as a number (`01` - `26`) or a key of the
+/// alphabet, e.g., a letter.
+///
+/// The ring settings of the Enigma Z are numbered `01` - `10`, so its digit
+/// keys `1` - `9` and `0` are the same settings as the numbers.
+pub fn parse_ring(ring: &str, alphabet: &Alphabet) -> Result<u8, String> {
+    let mut chars = ring.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if let Some(val) = alphabet.index_of(c) {
+            return Ok(val);
+        }
+    }
+    if let Ok(num) = ring.parse::<usize>() {
+        if (1..=alphabet.len()).contains(&num) {
+            return Ok(num as u8 - 1);
+        }
+    }
+
+    Err(format!(
+        "Invalid ring setting: {}. Expected a number 01 - {:02} or one of {}",
+        ring,
+        alphabet.len(),
+        alphabet
+    ))
+}
+
+/// Parse ring settings listed left rotor first, e.g., `01 02 03` or `ABC`.
+///
+/// The ring settings are returned right rotor first.
+pub fn parse_rings(rings: &str, alphabet: &Alphabet) -> Result<Vec<u8>, String> {
+    let parts = split_list(rings);
+    let mut rings = match parts.as_slice() {
+        [letters]
+            if letters.chars().count() > 1
+                && letters.chars().all(|c| alphabet.index_of(c).is_some()) =>
+        {
+            letters
+                .chars()
+                .map(|c| parse_ring(&c.to_string(), alphabet))
+                .collect::<Result<Vec<u8>, String>>()
+        }
+        parts => parts
+            .iter()
+            .map(|ring| parse_ring(ring, alphabet))
+            .collect(),
+    }?;
+    rings.reverse();
+    Ok(rings)
+}
+
+/// Parse rotor positions listed left rotor first as keys of the alphabet,
+/// e.g., `ABC` or `A B C`.
+///
+/// The positions are returned right rotor first.
+pub fn parse_positions(positions: &str, alphabet: &Alphabet) -> Result<Vec<u8>, String> {
+    positions
+        .chars()
+        .filter(|&c| !c.is_whitespace() && c != ',')
+        .rev()
+        .map(|c| {
+            alphabet.index_of(c).ok_or_else(|| {
+                format!(
+                    "Invalid rotor position: {}. Expected one of {}",
+                    c, alphabet
+                )
+            })
+        })
+        .collect()
+}
+
+/// Parse a pair of letters, e.g., `AB` or `A-B`.
+pub fn parse_plug(plug: &str) -> Result<(char, char), String> {
+    let chars: Vec<char> = plug.chars().filter(|&c| c != '-').collect();
+    if chars.len() != 2 || !chars.iter().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "Invalid plug: {}. Expected a pair of letters, e.g., AB",
+            plug
+        ));
+    }
+    if chars[0].eq_ignore_ascii_case(&chars[1]) {
+        return Err(format!(
+            "Invalid plug: {}. A letter can't be plugged into itself",
+            plug
+        ));
+    }
+    Ok((chars[0].to_ascii_uppercase(), chars[1].to_ascii_uppercase()))
+}
+
+/// Parse plugboard pairs, e.g., `AB CD`, and check that no letter is
+/// plugged twice.
+pub fn parse_plugs(plugs: &str) -> Result<Vec<(char, char)>, String> {
+    let plugs: Vec<(char, char)> = split_list(plugs)
+        .iter()
+        .map(|plug| parse_plug(plug))
+        .collect::<Result<_, _>>()?;
+
+    if plugs.len() > 13 {
+        return Err(format!(
+            "The plugboard holds at most 13 cables. Received {}",
+            plugs.len()
+        ));
+    }
+
+    let mut used: Vec<char> = Vec::new();
+    for &(in1, in2) in &plugs {
+        for c in [in1, in2] {
+            if used.contains(&c) {
+                return Err(format!("Letter {} is plugged more than once", c));
+            }
+            used.push(c);
+        }
+    }
+
+    Ok(plugs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_config() {
+        let cfg = Config::parse("A;III-A-A,II-A-A,I-A-A;a-b".to_string()).unwrap();
+        assert_eq!(cfg.reflector_config.reflector_id, "A");
+
+        // Rotors are listed from the front so the right rotor comes last
+        let ids: Vec<&str> = cfg
+            .rotor_config
+            .rotor_ids
+            .iter()
+            .map(|(id, _, _)| id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["I", "II", "III"]);
+        assert_eq!(
+            cfg.plugboard_config.unwrap().plugboard_maps,
+            vec![('A', 'B')]
+        );
+    }
+
+    #[test]
+    fn test_parse_settable_reflector() {
+        let cfg = Config::parse("UKWK-B-C;IIIK-A-A,IIK-A-A,IK-A-A".to_string()).unwrap();
+        assert_eq!(cfg.reflector_config.reflector_id, "UKWK");
+        assert_eq!(cfg.reflector_config.position, 1);
+        assert_eq!(cfg.reflector_config.ring, 2);
+        assert_eq!(cfg.to_string(), "UKWK-B-C;IIIK-A-A,IIK-A-A,IK-A-A");
+
+        let cfg = Config::parse("B-thin;Beta-A-A,II-A-A,IV-A-A,I-A-A".to_string()).unwrap();
+        assert_eq!(cfg.reflector_config.reflector_id, "B-thin");
+        assert_eq!(cfg.reflector_config.position, 0);
+
+        assert!(Config::parse("B-B-A;I-A-A,II-A-A,III-A-A".to_string()).is_none());
+        assert!(Config::parse("UKWK-B;IK-A-A,IIK-A-A,IIIK-A-A".to_string()).is_none());
+        assert!(Config::parse("UKWK-1-A;IK-A-A,IIK-A-A,IIIK-A-A".to_string()).is_none());
+        assert_eq!(parse_reflector("ukwr-z-a").unwrap(), ("UKWR", 25, 0));
+    }
+
+    #[test]
+    fn test_parse_uhr() {
+        let plugs = "b-a,c-d,e-f,g-h,i-j,k-l,m-n,o-p,q-r,s-t";
+        let cfg = Config::parse(format!("B;I-A-A,II-A-A,III-A-A;{};uhr-7", plugs)).unwrap();
+        assert_eq!(cfg.uhr_config, Some(UhrConfig { position: 7 }));
+        let uhr = cfg.uhr().unwrap().unwrap();
+        assert_eq!(uhr.plugs()[0], ('B', 'A'));
+        assert_eq!(
+            cfg.to_string(),
+            format!("B;I-A-A,II-A-A,III-A-A;{};UHR-07", plugs)
+        );
+
+        assert!(Config::parse(format!("B;I-A-A,II-A-A,III-A-A;{};UHR-40", plugs)).is_none());
+        assert!(Config::parse(format!("B;I-A-A,II-A-A,III-A-A;{};UKW-07", plugs)).is_none());
+        assert!(Config::parse("B;I-A-A,II-A-A,III-A-A;a-b,c-d;UHR-07".to_string()).is_none());
+        assert_eq!(parse_uhr("UHR-39"), Ok(39));
+    }
+
+    #[test]
+    fn test_parse_without_plugboard() {
+        let cfg = Config::parse("B;I-A-A,II-B-B,III-C-C".to_string()).unwrap();
+        assert!(cfg.plugboard_config.is_none());
+        assert_eq!(cfg.rotor_config.rotor_ids[0], ("III".to_string(), 2, 2));
+    }
+
+    #[test]
+    fn test_parse_ring_apart_from_position() {
+        let cfg = Config::parse("B;I-A-A,II-B-A,III-C-D".to_string()).unwrap();
+        assert_eq!(cfg.rotor_config.rotor_ids[0], ("III".to_string(), 2, 3));
+        assert_eq!(cfg.rotor_config.rotor_ids[1], ("II".to_string(), 1, 0));
+    }
+
+    #[test]
+    fn test_parse_enigma_z() {
+        // The Enigma Z is set with its digit keys, `1` - `9` then `0`
+        let cfg_str = "UKWZ-2-3;IIIZ-1-1,IIZ-5-0,IZ-0-9";
+        let cfg = Config::parse(cfg_str.to_string()).unwrap();
+        assert_eq!(cfg.alphabet(), Alphabet::z());
+        assert_eq!(
+            (cfg.reflector_config.position, cfg.reflector_config.ring),
+            (1, 2)
+        );
+        assert_eq!(cfg.rotor_config.rotor_ids[0], ("IZ".to_string(), 9, 8));
+        assert_eq!(cfg.rotor_config.rotor_ids[1], ("IIZ".to_string(), 4, 9));
+        assert_eq!(cfg.to_string(), cfg_str);
+
+        let mut em = cfg.clone().build_machine();
+        let cipher = em.translate_text("0815".chars());
+        let mut em = cfg.build_machine();
+        assert_eq!(em.translate_text(cipher.chars()), "0815");
+
+        assert!(Config::parse("UKWZ;IIIZ-A-1,IIZ-1-1,IZ-1-1".to_string()).is_none());
+        assert!(Config::parse("UKWZ-A-1;IIIZ-1-1,IIZ-1-1,IZ-1-1".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_parse_rings_and_positions() {
+        let latin = Alphabet::latin();
+        assert_eq!(parse_ring("26", &latin), Ok(25));
+        assert_eq!(parse_ring("b", &latin), Ok(1));
+        assert!(parse_ring("27", &latin).is_err());
+        assert_eq!(parse_rings("01 02 03", &latin), Ok(vec![2, 1, 0]));
+        assert_eq!(parse_rings("ABC", &latin), Ok(vec![2, 1, 0]));
+        assert_eq!(parse_positions("a b,c", &latin), Ok(vec![2, 1, 0]));
+        assert!(parse_positions("A1C", &latin).is_err());
+
+        // The digit keys of the Enigma Z are the ring numbers, `0` being `10`
+        let z = Alphabet::z();
+        assert_eq!(parse_ring("0", &z), Ok(9));
+        assert_eq!(parse_ring("10", &z), Ok(9));
+        assert!(parse_ring("11", &z).is_err());
+        assert!(parse_ring("A", &z).is_err());
+        assert_eq!(parse_rings("120", &z), Ok(vec![9, 1, 0]));
+        assert_eq!(parse_rings("10 01 02", &z), Ok(vec![1, 0, 9]));
+        assert_eq!(parse_positions("901", &z), Ok(vec![0, 9, 8]));
+        assert!(parse_positions("ABC", &z).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_config() {
+        assert!(Config::parse("B".to_string()).is_none());
+        assert!(Config::parse("D;I-A-A,II-A-A,III-A-A".to_string()).is_none());
+        assert!(Config::parse("B;I-A-A,II-A-A".to_string()).is_none());
+        assert!(Config::parse("B;I-A-A,II-A-A,IX-A-A".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_parse_empty_rotor_settings() {
+        assert!(Config::parse("B;I--A,II-A-A,III-A-A".to_string()).is_none());
+        assert!(Config::parse("B;I-A-,II-A-A,III-A-A".to_string()).is_none());
+        assert!(Config::parse("B;I--,II-A-A,III-A-A".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_build_machine() {
+        let mut em = Config::parse("A;III-A-A,II-A-A,I-A-A;a-b".to_string())
+            .unwrap()
+            .build_machine();
+        assert_eq!(
+            em.translate_text("hello, how are you doing".chars()),
+            "LZFAD, AMT GPJ FND IFMJY"
+        );
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for cfg_str in [
+            "A;III-A-A,II-A-A,I-A-A;a-b",
+            "C;IV-Q-B,V-E-Z,I-A-M;q-w,e-r",
+            "B;I-A-A,II-A-A,III-A-A",
+        ] {
+            let cfg = Config::parse(cfg_str.to_string()).unwrap();
+            assert_eq!(cfg.to_string(), cfg_str);
+        }
+
+        let cfg = Config::new(
+            "B",
+            vec![("III".to_string(), 1, 2), ("I".to_string(), 0, 25)],
+            vec![('X', 'Y')],
+        );
+        assert_eq!(cfg.to_string(), "B;I-A-Z,III-B-C;x-y");
+    }
+
+    #[test]
+    fn test_parse_invalid_plugboard() {
+        assert!(Config::parse("B;I-A-A,II-A-A,III-A-A;a-1".to_string()).is_none());
+        assert!(Config::parse("B;I-A-A,II-A-A,III-A-A;ab".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_overrides_without_base() {
+        let overrides = ConfigOverrides {
+            reflector: Some("b".to_string()),
+            rotors: Some("I II III".to_string()),
+            rings: Some("01 02 03".to_string()),
+            positions: Some("XYZ".to_string()),
+            plugs: Some("AB cd".to_string()),
+        };
+        let cfg = overrides.apply(None).unwrap();
+        assert_eq!(cfg.to_string(), "B;I-X-A,II-Y-B,III-Z-C;a-b,c-d");
+
+        assert!(ConfigOverrides::default().apply(None).is_err());
+        let missing_reflector = ConfigOverrides {
+            rotors: Some("I II III".to_string()),
+            ..Default::default()
+        };
+        assert!(missing_reflector.apply(None).is_err());
+
+        let enigma_z = ConfigOverrides {
+            reflector: Some("ukwz-1-2".to_string()),
+            rotors: Some("IIIZ IIZ IZ".to_string()),
+            rings: Some("10 01 02".to_string()),
+            positions: Some("123".to_string()),
+            ..Default::default()
+        };
+        let cfg = enigma_z.apply(None).unwrap();
+        assert_eq!(cfg.to_string(), "UKWZ-1-2;IIIZ-1-0,IIZ-2-1,IZ-3-2");
+    }
+
+    #[test]
+    fn test_overrides_merge_with_base() {
+        let base = Config::parse("A;III-A-B,II-C-D,I-E-F;a-b".to_string());
+
+        let overrides = ConfigOverrides {
+            positions: Some("QRS".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            overrides.apply(base.clone()).unwrap().to_string(),
+            "A;III-Q-B,II-R-D,I-S-F;a-b"
+        );
+
+        // Swapping rotors keeps the ring settings and positions
+        let overrides = ConfigOverrides {
+            reflector: Some("C".to_string()),
+            rotors: Some("IV V VI".to_string()),
+            plugs: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(
+            overrides.apply(base).unwrap().to_string(),
+            "C;IV-A-B,V-C-D,VI-E-F"
+        );
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        let base = Config::parse("A;III-A-A,II-A-A,I-A-A".to_string());
+        let invalid = [
+            ConfigOverrides {
+                reflector: Some("D".to_string()),
+                ..Default::default()
+            },
+            ConfigOverrides {
+                rotors: Some("I II IX".to_string()),
+                ..Default::default()
+            },
+            ConfigOverrides {
+                rotors: Some("I II".to_string()),
+                ..Default::default()
+            },
+            ConfigOverrides {
+                rings: Some("01 02 27".to_string()),
+                ..Default::default()
+            },
+            ConfigOverrides {
+                positions: Some("AB".to_string()),
+                ..Default::default()
+            },
+            ConfigOverrides {
+                plugs: Some("AB BC".to_string()),
+                ..Default::default()
+            },
+            ConfigOverrides {
+                plugs: Some("AB C-C".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        for overrides in invalid {
+            assert!(overrides.apply(base.clone()).is_err(), "{:?}", overrides);
+        }
+    }
+}
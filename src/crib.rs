@@ -0,0 +1,804 @@
+//! Enigma known-plaintext (crib) attack module.
+//!
+//! The [CribAttack](CribAttack) struct recovers an Enigma key from a
+//! ciphertext and a crib, a guessed fragment of the plaintext at a known
+//! offset in the message. The search works the same way the Turing-Welchman
+//! bombe did. Every wheel order and rotor core position is tested against the
+//! "menu" formed by the crib, a stecker (plugboard) hypothesis is propagated
+//! through the menu, and positions that lead to a contradiction are
+//! discarded. Every surviving stop is turned into a full key and verified by
+//! decrypting the ciphertext with
+//! [EnigmaMachine::translate_text](crate::machine::EnigmaMachine::translate_text).
+//!
+//! As with the bombe, the search assumes that the left rotor is not stepped
+//! (i.e., no middle rotor turnover) between the start of the message and the
+//! end of the crib. Right rotor turnovers of the middle rotor are handled.
+//!
+//! Also like the bombe, hypotheses are only tested on the most connected
+//! group of menu letters, ideally one that closes a loop. Other groups with
+//! a loop are solved at each stop, but the steckers of letters the menu
+//! doesn't tie down are left unknown. A crib without any loop stops at
+//! almost every position, so the number of keys returned is capped with
+//! [max_solutions](CribAttack::max_solutions).
+//!
+//! ```
+//! use enigma::crib::CribAttack;
+//!
+//! let attack = CribAttack::new("QMJIDOMZWZJFJR", "HELLOWORLD", 2)
+//!     .unwrap()
+//!     .reflectors(vec!["B".to_string()])
+//!     .unwrap()
+//!     .wheel_orders(vec![vec!["III".to_string(), "II".to_string(), "I".to_string()]])
+//!     .unwrap();
+//!
+//! // The search only knows the straight through entry wheel of the Enigma I,
+//! // M3 and M4
+//! assert!(CribAttack::new("QMJIDOMZWZJFJR", "HELLOWORLD", 2)
+//!     .unwrap()
+//!     .reflectors(vec!["UKWK".to_string()])
+//!     .is_err());
+//! ```
+use crate::config::{reflector_id, rotor_id, Config};
+use crate::machine::EnigmaMachine;
+use crate::model::EnigmaModel;
+use crate::reflector::Reflector;
+use crate::rotor::Rotor;
+
+/// Rotors that are combined into the default wheel orders searched.
+const DEFAULT_ROTORS: [&str; 5] = ["I", "II", "III", "IV", "V"];
+
+/// Marker for a plugboard letter whose stecker partner is still unknown.
+const UNKNOWN: u8 = 255;
+
+/// The number of keys a [CribAttack](CribAttack) returns by default.
+pub const DEFAULT_MAX_SOLUTIONS: usize = 100;
+
+/// A key recovered by a [CribAttack](CribAttack).
+///
+/// Rotors are listed in the same order that
+/// [EnigmaMachineBuilder::rotors](crate::machine::EnigmaMachineBuilder::rotors)
+/// expects them, i.e., the right (fast) rotor first. Only plugboard pairs that
+/// could be deduced from the crib are included, and the letters whose
+/// stecker partner is still unknown are listed in `unknown`. The plaintext is
+/// decrypted as if those letters were unsteckered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CribSolution {
+    pub reflector: String,
+    pub rotors: Vec<(String, u8, u8)>,
+    pub plugboard: Vec<(char, char)>,
+    pub unknown: Vec<char>,
+    pub plaintext: String,
+}
+
+impl CribSolution {
+    /// The recovered key as a [Config](Config).
+    pub fn config(&self) -> Config {
+        Config::new(&self.reflector, self.rotors.clone(), self.plugboard.clone())
+    }
+
+    /// Build an [EnigmaMachine](EnigmaMachine) set to the start of the
+    /// message using the recovered key.
+    pub fn machine(&self) -> Result<EnigmaMachine, String> {
+        EnigmaMachine::builder()
+            .reflector(&self.reflector)
+            .rotors(self.rotors.clone())
+            .plugboard(self.plugboard.clone())
+            .try_build()
+    }
+}
+
+/// Known-plaintext attack against a three rotor Enigma.
+///
+/// By default every wheel order of rotors `I` through `V` is tested with
+/// reflector `B`. Both can be restricted (or extended) with
+/// [reflectors](CribAttack::reflectors) and
+/// [wheel_orders](CribAttack::wheel_orders).
+#[derive(Debug, Clone)]
+pub struct CribAttack {
+    ciphertext: Vec<u8>,
+    crib: Vec<u8>,
+    offset: usize,
+    reflectors: Vec<String>,
+    wheel_orders: Vec<Vec<String>>,
+    max_solutions: usize,
+}
+
+impl CribAttack {
+    /// Create a new attack for the given ciphertext and crib.
+    ///
+    /// Non-alphabetic characters are dropped from both the ciphertext and
+    /// the crib. `offset` is the index of the first crib letter within the
+    /// (alphabetic only) ciphertext.
+    ///
+    /// * `ciphertext` - The intercepted message
+    /// * `crib` - The guessed plaintext fragment
+    /// * `offset` - Location of the crib within the message
+    pub fn new(ciphertext: &str, crib: &str, offset: usize) -> Result<CribAttack, String> {
+        let ciphertext = letter_values(ciphertext);
+        let crib = letter_values(crib);
+
+        if crib.is_empty() {
+            return Err("Crib must contain at least one letter".to_string());
+        }
+
+        if offset + crib.len() > ciphertext.len() {
+            return Err(format!(
+                "Crib of length {} at offset {} runs past the end of the ciphertext ({})",
+                crib.len(),
+                offset,
+                ciphertext.len()
+            ));
+        }
+
+        if let Some(i) = (0..crib.len()).find(|&i| crib[i] == ciphertext[offset + i]) {
+            return Err(format!(
+                "Crib cannot be placed at offset {}. Enigma never encrypts a letter to itself ({} at {})",
+                offset,
+                (crib[i] + 65) as char,
+                offset + i
+            ));
+        }
+
+        let mut wheel_orders = Vec::new();
+        for left in DEFAULT_ROTORS {
+            for middle in DEFAULT_ROTORS {
+                for right in DEFAULT_ROTORS {
+                    if left != middle && middle != right && left != right {
+                        wheel_orders.push(vec![
+                            right.to_string(),
+                            middle.to_string(),
+                            left.to_string(),
+                        ]);
+                    }
+                }
+            }
+        }
+
+        Ok(CribAttack {
+            ciphertext,
+            crib,
+            offset,
+            reflectors: vec!["B".to_string()],
+            wheel_orders,
+            max_solutions: DEFAULT_MAX_SOLUTIONS,
+        })
+    }
+
+    /// Set the reflectors that are tested.
+    ///
+    /// The search assumes the straight through entry wheel of the Enigma I,
+    /// M3 and M4, so only their reflectors are accepted.
+    pub fn reflectors<T>(mut self, reflector_ids: T) -> Result<CribAttack, String>
+    where
+        T: IntoIterator<Item = String>,
+    {
+        self.reflectors = reflector_ids
+            .into_iter()
+            .map(|id| attack_reflector_id(&id))
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Set the wheel orders that are tested.
+    ///
+    /// Each wheel order lists three rotor ids with the right (fast) rotor
+    /// first, matching the order used by
+    /// [EnigmaMachineBuilder::rotors](crate::machine::EnigmaMachineBuilder::rotors).
+    /// Greek rotors never step, so they are rejected.
+    pub fn wheel_orders<T>(mut self, wheel_orders: T) -> Result<CribAttack, String>
+    where
+        T: IntoIterator<Item = Vec<String>>,
+    {
+        self.wheel_orders = wheel_orders
+            .into_iter()
+            .map(|wheel_order| {
+                if wheel_order.len() != 3 {
+                    return Err(format!(
+                        "Invalid wheel order of {} rotors. Three rotors required",
+                        wheel_order.len()
+                    ));
+                }
+                wheel_order.iter().map(|id| attack_rotor_id(id)).collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Set the most keys that are returned. The search ends once this many
+    /// distinct keys have been verified. Defaults to
+    /// [DEFAULT_MAX_SOLUTIONS](DEFAULT_MAX_SOLUTIONS).
+    pub fn max_solutions(mut self, max_solutions: usize) -> CribAttack {
+        self.max_solutions = max_solutions;
+        self
+    }
+
+    /// Run the attack and return every verified key, up to
+    /// [max_solutions](CribAttack::max_solutions).
+    pub fn solve(&self) -> Vec<CribSolution> {
+        let menu = Menu::new(&self.crib, &self.ciphertext[self.offset..]);
+        let mut solutions = Vec::new();
+
+        for reflector_id in &self.reflectors {
+            for wheel_order in &self.wheel_orders {
+                let table = scrambler_table(reflector_id, wheel_order);
+                self.search_wheel_order(reflector_id, wheel_order, &table, &menu, &mut solutions);
+                if solutions.len() >= self.max_solutions {
+                    return solutions;
+                }
+            }
+        }
+
+        solutions
+    }
+
+    fn search_wheel_order(
+        &self,
+        reflector_id: &str,
+        wheel_order: &[String],
+        table: &[[u8; 26]],
+        menu: &Menu,
+        solutions: &mut Vec<CribSolution>,
+    ) {
+        let mut perms: Vec<&[u8; 26]> = Vec::with_capacity(self.crib.len());
+
+        for (right_pos, middle_steps) in self.turnover_schedules(&wheel_order[0]) {
+            let crib_steps = &middle_steps[self.offset..];
+
+            for left in 0..26 {
+                for middle in 0..26 {
+                    for right in 0..26 {
+                        perms.clear();
+                        for (i, steps) in crib_steps.iter().enumerate() {
+                            let m = (middle + steps - crib_steps[0]) % 26;
+                            let r = (right + i) % 26;
+                            perms.push(&table[(left * 26 + m) * 26 + r]);
+                        }
+
+                        for values in menu.solve(&perms) {
+                            let cores = [
+                                (right + 26 * 26 - self.offset - 1) % 26,
+                                (middle + 26 * 26 - crib_steps[0]) % 26,
+                                left,
+                            ];
+                            let solution = self.verify(
+                                reflector_id,
+                                wheel_order,
+                                cores,
+                                right_pos,
+                                &middle_steps,
+                                &values,
+                            );
+
+                            if let Some(solution) = solution {
+                                if !solutions.contains(&solution) {
+                                    solutions.push(solution);
+                                    if solutions.len() >= self.max_solutions {
+                                        return;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The distinct middle rotor stepping schedules over the crib.
+    ///
+    /// Returns a right rotor window position along with the cumulative
+    /// number of middle rotor steps at every keystroke up to the end of the
+    /// crib. Window positions that step the middle rotor at the same points
+    /// within the crib are equivalent so only one of them is returned.
+    fn turnover_schedules(&self, right_rotor_id: &str) -> Vec<(u8, Vec<usize>)> {
+        let mut schedules: Vec<(u8, Vec<usize>)> = Vec::new();
+
+        for right_pos in 0..26 {
+            let mut rotor = Rotor::new_with_state(right_rotor_id, right_pos, 0);
+            let mut steps = 0;
+            let mut middle_steps = Vec::with_capacity(self.offset + self.crib.len());
+
+            for _ in 0..self.offset + self.crib.len() {
+                if rotor.will_step_next_rotor() {
+                    steps += 1;
+                }
+                rotor.rotate();
+                middle_steps.push(steps);
+            }
+
+            let relative = |s: &[usize]| -> Vec<usize> {
+                s[self.offset..]
+                    .iter()
+                    .map(|v| v - s[self.offset])
+                    .collect()
+            };
+
+            if !schedules
+                .iter()
+                .any(|(_, s)| relative(s) == relative(&middle_steps))
+            {
+                schedules.push((right_pos, middle_steps));
+            }
+        }
+
+        schedules
+    }
+
+    /// Turn a bombe stop into a full key and check it against the crib.
+    fn verify(
+        &self,
+        reflector_id: &str,
+        wheel_order: &[String],
+        cores: [usize; 3],
+        right_pos: u8,
+        middle_steps: &[usize],
+        values: &[u8; 26],
+    ) -> Option<CribSolution> {
+        let right_ring = (right_pos as usize + 26 - cores[0]) % 26;
+        let total_middle_steps = *middle_steps.last().unwrap_or(&0);
+
+        // Pick ring settings that keep the middle and left rotors away from
+        // their notches so that the left rotor doesn't move during the crib.
+        let middle_ring = (0..26).find(|ring| {
+            let rotor = Rotor::new_with_state(&wheel_order[1], 0, 0);
+            (0..=total_middle_steps).all(|s| {
+                let mut r = rotor.clone();
+                r.set_rotor_pos(((cores[1] + ring + s) % 26) as u8);
+                !r.will_step_next_rotor()
+            })
+        })?;
+        let left_ring = (0..26).find(|ring| {
+            let mut rotor = Rotor::new(&wheel_order[2]);
+            rotor.set_rotor_pos(((cores[2] + ring) % 26) as u8);
+            !rotor.will_step_next_rotor()
+        })?;
+
+        let mut plugboard = Vec::new();
+        let mut unknown = Vec::new();
+        for (letter, &value) in values.iter().enumerate() {
+            if value == UNKNOWN {
+                unknown.push((letter as u8 + 65) as char);
+            } else if value as usize > letter {
+                plugboard.push(((letter as u8 + 65) as char, (value + 65) as char));
+            }
+        }
+
+        let rotors = vec![
+            (wheel_order[0].clone(), right_pos, right_ring as u8),
+            (
+                wheel_order[1].clone(),
+                ((cores[1] + middle_ring) % 26) as u8,
+                middle_ring as u8,
+            ),
+            (
+                wheel_order[2].clone(),
+                ((cores[2] + left_ring) % 26) as u8,
+                left_ring as u8,
+            ),
+        ];
+
+        let mut solution = CribSolution {
+            reflector: reflector_id.to_string(),
+            rotors,
+            plugboard,
+            unknown,
+            plaintext: String::new(),
+        };
+
+        let ciphertext = self.ciphertext.iter().map(|&c| (c + 65) as char);
+        solution.plaintext = solution.machine().ok()?.translate_text(ciphertext);
+
+        // Letters the menu doesn't tie down have no known stecker, so only
+        // the crib letters with one can be checked
+        let crib_matches = solution.plaintext.as_bytes()[self.offset..]
+            .iter()
+            .zip(self.crib.iter())
+            .filter(|&(_, &c)| values[c as usize] != UNKNOWN)
+            .all(|(&p, &c)| p - 65 == c);
+
+        if crib_matches {
+            Some(solution)
+        } else {
+            None
+        }
+    }
+}
+
+/// Find every offset at which `crib` could sit within `ciphertext`.
+///
+/// Enigma never encrypts a letter to itself, so any offset where a crib
+/// letter lines up with the same ciphertext letter can be ruled out.
+pub fn possible_offsets(ciphertext: &str, crib: &str) -> Vec<usize> {
+    let ciphertext = letter_values(ciphertext);
+    let crib = letter_values(crib);
+
+    if crib.is_empty() || crib.len() > ciphertext.len() {
+        return Vec::new();
+    }
+
+    (0..=ciphertext.len() - crib.len())
+        .filter(|&offset| {
+            crib.iter()
+                .zip(ciphertext[offset..].iter())
+                .all(|(p, c)| p != c)
+        })
+        .collect()
+}
+
+/// Look up a reflector the search can use, i.e., one of the Enigma I, M3 or
+/// M4 reflectors.
+fn attack_reflector_id(id: &str) -> Result<String, String> {
+    let id = reflector_id(id)?;
+    match EnigmaModel::for_reflector(id) {
+        Some(EnigmaModel::I | EnigmaModel::M3 | EnigmaModel::M4) => Ok(id.to_string()),
+        _ => Err(format!(
+            "Invalid reflector: {}. Only the Enigma I, M3 and M4 reflectors can be searched",
+            id
+        )),
+    }
+}
+
+/// Look up a rotor the search can use, i.e., a stepping rotor with 26
+/// contacts.
+fn attack_rotor_id(id: &str) -> Result<String, String> {
+    let id = rotor_id(id)?;
+    if EnigmaModel::M4.greek_rotors().contains(&id) {
+        return Err(format!(
+            "Invalid rotor: {}. Greek rotors never step so can't be searched",
+            id
+        ));
+    }
+    if Rotor::new(id).size() != 26 {
+        return Err(format!(
+            "Invalid rotor: {}. Only rotors with 26 contacts can be searched",
+            id
+        ));
+    }
+    Ok(id.to_string())
+}
+
+fn letter_values(text: &str) -> Vec<u8> {
+    text.chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase() as u8 - 65)
+        .collect()
+}
+
+/// Scrambler permutations (rotors and reflector, no plugboard) for every
+/// combination of rotor core offsets, indexed by `(left * 26 + middle) * 26 + right`.
+fn scrambler_table(reflector_id: &str, wheel_order: &[String]) -> Vec<[u8; 26]> {
+    let reflector = Reflector::new(reflector_id);
+    let mut right = Rotor::new(&wheel_order[0]);
+    let mut middle = Rotor::new(&wheel_order[1]);
+    let mut left = Rotor::new(&wheel_order[2]);
+    let mut table = Vec::with_capacity(26 * 26 * 26);
+
+    for l in 0..26 {
+        left.set_rotor_pos(l);
+        for m in 0..26 {
+            middle.set_rotor_pos(m);
+            for r in 0..26 {
+                right.set_rotor_pos(r);
+
+                let mut perm = [0; 26];
+                for (input, output) in perm.iter_mut().enumerate() {
+                    let mut val = right.map_in(input as u8);
+                    val = middle.map_in(val);
+                    val = left.map_in(val);
+                    val = reflector.map(val);
+                    val = left.map_out(val);
+                    val = middle.map_out(val);
+                    *output = right.map_out(val);
+                }
+                table.push(perm);
+            }
+        }
+    }
+
+    table
+}
+
+/// The letter connections implied by a crib.
+///
+/// Each crib letter and its ciphertext letter are connected by the scrambler
+/// at that position in the message. `components` holds a starting letter for
+/// each connected group of letters that is solved: the group hypotheses are
+/// tested on first, followed by every other group that closes a loop.
+#[derive(Debug)]
+struct Menu {
+    links: Vec<Vec<(u8, usize)>>,
+    components: Vec<u8>,
+}
+
+impl Menu {
+    fn new(crib: &[u8], ciphertext: &[u8]) -> Menu {
+        let mut links = vec![Vec::new(); 26];
+        for (i, (&p, &c)) in crib.iter().zip(ciphertext.iter()).enumerate() {
+            links[p as usize].push((c, i));
+            links[c as usize].push((p, i));
+        }
+
+        // Start each connected group of letters from its most connected
+        // letter, noting its size and whether it closes a loop, i.e., has
+        // at least as many links as letters.
+        let mut seen = [false; 26];
+        let mut groups = Vec::new();
+        let mut order: Vec<u8> = (0..26).filter(|&l| !links[l as usize].is_empty()).collect();
+        order.sort_by_key(|&l| std::cmp::Reverse(links[l as usize].len()));
+
+        for start in order {
+            if seen[start as usize] {
+                continue;
+            }
+
+            let (mut letters, mut ends) = (0, 0);
+            let mut stack = vec![start];
+            while let Some(letter) = stack.pop() {
+                if seen[letter as usize] {
+                    continue;
+                }
+                seen[letter as usize] = true;
+                letters += 1;
+                ends += links[letter as usize].len();
+                stack.extend(links[letter as usize].iter().map(|&(l, _)| l));
+            }
+
+            let link_count = ends / 2;
+            groups.push((start, link_count >= letters, link_count));
+        }
+
+        // Test hypotheses on the group with a loop and the most links, and
+        // leave groups without a loop unknown as any stecker fits them
+        groups.sort_by_key(|&(_, has_loop, link_count)| std::cmp::Reverse((has_loop, link_count)));
+        let components = groups
+            .iter()
+            .enumerate()
+            .filter(|&(i, &(_, has_loop, _))| i == 0 || has_loop)
+            .map(|(_, &(start, _, _))| start)
+            .collect();
+
+        Menu { links, components }
+    }
+
+    /// All consistent plugboard assignments for the given scramblers.
+    fn solve(&self, perms: &[&[u8; 26]]) -> Vec<[u8; 26]> {
+        let mut results = Vec::new();
+        let mut stack = Vec::new();
+        self.solve_component(0, [UNKNOWN; 26], perms, &mut stack, &mut results);
+        results
+    }
+
+    fn solve_component(
+        &self,
+        component: usize,
+        values: [u8; 26],
+        perms: &[&[u8; 26]],
+        stack: &mut Vec<(u8, u8)>,
+        results: &mut Vec<[u8; 26]>,
+    ) {
+        if component == self.components.len() {
+            results.push(values);
+            return;
+        }
+
+        let start = self.components[component];
+        if values[start as usize] != UNKNOWN {
+            self.solve_component(component + 1, values, perms, stack, results);
+            return;
+        }
+
+        for hypothesis in 0..26 {
+            let mut trial = values;
+            if self.propagate(&mut trial, start, hypothesis, perms, stack) {
+                self.solve_component(component + 1, trial, perms, stack, results);
+            }
+        }
+    }
+
+    /// Propagate `letter <-> value` through the menu. Returns false when a
+    /// letter ends up steckered to two different letters.
+    fn propagate(
+        &self,
+        values: &mut [u8; 26],
+        letter: u8,
+        value: u8,
+        perms: &[&[u8; 26]],
+        stack: &mut Vec<(u8, u8)>,
+    ) -> bool {
+        stack.clear();
+        stack.push((letter, value));
+
+        while let Some((letter, value)) = stack.pop() {
+            let (l, v) = (letter as usize, value as usize);
+            if values[l] == value {
+                continue;
+            }
+            if values[l] != UNKNOWN || (values[v] != UNKNOWN && values[v] != letter) {
+                return false;
+            }
+
+            values[l] = value;
+            values[v] = letter;
+
+            for &(other, i) in &self.links[l] {
+                stack.push((other, perms[i][v]));
+            }
+            if l != v {
+                for &(other, i) in &self.links[v] {
+                    stack.push((other, perms[i][l]));
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(plaintext: &str) -> String {
+        let mut em = EnigmaMachine::builder()
+            .reflector("B")
+            .rotors(vec![
+                ("II".to_string(), 3, 7),
+                ("V".to_string(), 17, 2),
+                ("I".to_string(), 9, 21),
+            ])
+            .plugboard(vec![
+                ('A', 'Q'),
+                ('E', 'Z'),
+                ('R', 'T'),
+                ('O', 'M'),
+                ('N', 'Y'),
+                ('L', 'C'),
+            ])
+            .build()
+            .unwrap();
+        em.translate_text(plaintext.chars())
+    }
+
+    /// Every crib letter with a known stecker decrypts to itself.
+    fn assert_crib_letters_match(solution: &CribSolution, offset: usize, crib: &str) {
+        for (p, c) in solution.plaintext[offset..].chars().zip(crib.chars()) {
+            if !solution.unknown.contains(&c) {
+                assert_eq!(p, c, "{}", solution.config());
+            }
+        }
+    }
+
+    #[test]
+    fn test_crib_must_fit_ciphertext() {
+        assert!(CribAttack::new("ABC", "DEFG", 0).is_err());
+        assert!(CribAttack::new("ABCD", "EFG", 2).is_err());
+        assert!(CribAttack::new("ABCD", "", 0).is_err());
+        assert!(CribAttack::new("ABCD", "EFG", 1).is_ok());
+    }
+
+    #[test]
+    fn test_crib_rejects_self_encryption() {
+        let err = CribAttack::new("ABCDEF", "XCXX", 1);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_possible_offsets() {
+        assert_eq!(possible_offsets("ABCABC", "AB"), vec![1, 2, 4]);
+        assert_eq!(possible_offsets("AB", "ABC"), Vec::<usize>::new());
+        assert_eq!(possible_offsets("a.b c", "CC"), vec![0]);
+    }
+
+    #[test]
+    fn test_default_wheel_orders() {
+        let attack = CribAttack::new("ABCD", "EFG", 0).unwrap();
+        assert_eq!(attack.wheel_orders.len(), 60);
+        assert_eq!(attack.reflectors, vec!["B".to_string()]);
+    }
+
+    #[test]
+    fn test_rejects_unsearchable_settings() {
+        let attack = || CribAttack::new("ABCD", "EFG", 0).unwrap();
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        assert!(attack().reflectors(ids(&["X"])).is_err());
+        // The search assumes a straight through entry wheel
+        for reflector in ["UKWK", "UKWR", "UKWT", "UKWG"] {
+            assert!(
+                attack().reflectors(ids(&[reflector])).is_err(),
+                "{}",
+                reflector
+            );
+        }
+        assert_eq!(
+            attack()
+                .reflectors(ids(&["b", "C-thin"]))
+                .unwrap()
+                .reflectors,
+            ids(&["B", "C-thin"])
+        );
+
+        assert!(attack()
+            .wheel_orders(vec![ids(&["I", "II", "Foo"])])
+            .is_err());
+        assert!(attack().wheel_orders(vec![ids(&["I", "II"])]).is_err());
+        assert!(attack()
+            .wheel_orders(vec![ids(&["I", "II", "Beta"])])
+            .is_err());
+        assert!(attack()
+            .wheel_orders(vec![ids(&["I", "II", "iii"])])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_recovers_key_from_crib() {
+        let plaintext = "ATTACKATDAWNALONGTHEEASTERNRIDGELINEWEATHERISCLEARSTOP";
+        let ciphertext = encrypt(plaintext);
+        let crib = "WEATHERISCLEAR";
+        let offset = plaintext.find(crib).unwrap();
+
+        let solutions = CribAttack::new(&ciphertext, crib, offset)
+            .unwrap()
+            .wheel_orders(vec![vec![
+                "II".to_string(),
+                "V".to_string(),
+                "I".to_string(),
+            ]])
+            .unwrap()
+            .solve();
+
+        assert!(!solutions.is_empty());
+        for solution in &solutions {
+            assert_crib_letters_match(solution, offset, crib);
+
+            let mut em = solution.machine().unwrap();
+            assert_eq!(em.translate_text(ciphertext.chars()), solution.plaintext);
+        }
+
+        // The crib ties down the true steckers for the letters it touches
+        let true_pairs = [
+            ('A', 'Q'),
+            ('E', 'Z'),
+            ('R', 'T'),
+            ('M', 'O'),
+            ('N', 'Y'),
+            ('C', 'L'),
+        ];
+        assert!(solutions.iter().any(|s| {
+            true_pairs
+                .iter()
+                .filter(|(a, b)| crib.contains(*a) || crib.contains(*b))
+                .all(|p| s.plugboard.contains(p))
+        }));
+    }
+
+    #[test]
+    fn test_loopless_crib() {
+        let plaintext = "THEQUICKBROWNFOXJUMPSOVERLAZYDOGS";
+        let ciphertext = encrypt(plaintext);
+        // JUMPS sits over KZUFT, which links J-K, U-Z, M-U, P-F and S-T
+        // without closing a loop, so nearly every position stops
+        let crib = "JUMPS";
+        let offset = plaintext.find(crib).unwrap();
+
+        let start = std::time::Instant::now();
+        let solutions = CribAttack::new(&ciphertext, crib, offset)
+            .unwrap()
+            .wheel_orders(vec![vec![
+                "II".to_string(),
+                "V".to_string(),
+                "I".to_string(),
+            ]])
+            .unwrap()
+            .solve();
+
+        assert!(start.elapsed() < std::time::Duration::from_secs(30));
+        assert_eq!(solutions.len(), DEFAULT_MAX_SOLUTIONS);
+        for solution in &solutions {
+            assert_crib_letters_match(solution, offset, crib);
+            // The menu can't tie down every letter without a loop
+            assert!(!solution.unknown.contains(&'U'));
+            assert!(!solution.unknown.is_empty());
+        }
+    }
+}
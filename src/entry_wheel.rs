@@ -0,0 +1,291 @@
+//! Enigma entry wheel module.
+//!
+//! The [EntryWheel](EntryWheel) (Eintrittswalze, ETW) connects the keyboard
+//! to the contacts of the right rotor. The military machines wired it
+//! straight through, `A` to `A`. The commercial machines wired it in keyboard
+//! order, so the key `Q` reached contact `A`, `W` contact `B` and so on.
+//!
+//! A wiring lists the key wired to each contact, starting at contact `A`.
+//!
+//! ```
+//! use enigma::entry_wheel::EntryWheel;
+//!
+//! let etw = EntryWheel::qwertz();
+//! assert_eq!(etw.map_in(b'Q' - b'A'), 0);
+//! assert_eq!(etw.map_out(0), b'Q' - b'A');
+//!
+//! assert!(EntryWheel::new("ABC").is_err());
+//! ```
+//!
+//! With the `serde` feature an [EntryWheel](EntryWheel) is serialized as its
+//! wiring, e.g., `"QWERTZUIOASDFGHJKPYXCVBNML"`. Entry wheels for another
+//! alphabet are serialized with it, e.g.,
+//! `{"wiring": "1234567890", "alphabet": "1234567890"}`.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+
+/// Entry wheel wired straight through, `A` to `A`, as used by the military
+/// machines.
+pub const ETW_IDENTITY: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Entry wheel wired in keyboard order, as used by the commercial machines.
+pub const ETW_QWERTZ: &str = "QWERTZUIOASDFGHJKPYXCVBNML";
+
+/// Entry wheel of the Enigma T (Tirpitz).
+pub const ETW_T: &str = "KZROUQHYAIGBLWVSTDXFPNMCJE";
+
+/// Entry wheel of the Enigma Z, wired straight through its keyboard.
+pub const ETW_Z: &str = "1234567890";
+
+/// Enigma entry wheel (Eintrittswalze).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "EntryWheelState", into = "EntryWheelState")
+)]
+pub struct EntryWheel {
+    /// Contact reached by each key
+    forward: Vec<u8>,
+    /// Key reached from each contact
+    backward: Vec<u8>,
+    /// The keys, to write the wiring with
+    alphabet: Alphabet,
+}
+
+impl EntryWheel {
+    /// Create an entry wheel from its wiring.
+    ///
+    /// * `wiring` - The 26 keys wired to contacts `A` - `Z`, in order. Each
+    ///   letter must appear exactly once. Case is ignored.
+    pub fn new(wiring: &str) -> Result<EntryWheel, String> {
+        let keys: Vec<char> = wiring.trim().chars().collect();
+        if keys.len() != 26 {
+            return Err(format!(
+                "Invalid entry wheel wiring: {}. Expected 26 letters",
+                wiring
+            ));
+        }
+
+        let mut forward = vec![u8::MAX; 26];
+        let mut backward = vec![0; 26];
+        for (contact, key) in keys.into_iter().enumerate() {
+            if !key.is_ascii_alphabetic() {
+                return Err(format!(
+                    "Invalid entry wheel wiring: {}. Expected only letters A - Z",
+                    wiring
+                ));
+            }
+
+            let key_val = key.to_ascii_uppercase() as u8 - b'A';
+            if forward[key_val as usize] != u8::MAX {
+                return Err(format!(
+                    "Invalid entry wheel wiring: {}. Letter {} is wired more than once",
+                    wiring,
+                    key.to_ascii_uppercase()
+                ));
+            }
+
+            forward[key_val as usize] = contact as u8;
+            backward[contact] = key_val;
+        }
+
+        Ok(EntryWheel {
+            forward,
+            backward,
+            alphabet: Alphabet::latin(),
+        })
+    }
+
+    /// Create an entry wheel for an alphabet other than `A` - `Z`.
+    ///
+    /// * `wiring` - The keys wired to each contact, in order. Each key of the
+    ///   alphabet must appear exactly once.
+    /// * `alphabet` - The keys of the machine
+    pub fn new_with_alphabet(wiring: &str, alphabet: &Alphabet) -> Result<EntryWheel, String> {
+        let backward = alphabet.wiring(wiring)?;
+        let mut forward = vec![0; backward.len()];
+        for (contact, &key) in backward.iter().enumerate() {
+            forward[key as usize] = contact as u8;
+        }
+
+        Ok(EntryWheel {
+            forward,
+            backward,
+            alphabet: alphabet.clone(),
+        })
+    }
+
+    /// The straight through entry wheel of the military machines.
+    pub fn identity() -> EntryWheel {
+        EntryWheel::new(ETW_IDENTITY).unwrap()
+    }
+
+    /// A straight through entry wheel for any alphabet.
+    pub fn straight(alphabet: &Alphabet) -> EntryWheel {
+        let contacts: Vec<u8> = (0..alphabet.len() as u8).collect();
+        EntryWheel {
+            forward: contacts.clone(),
+            backward: contacts,
+            alphabet: alphabet.clone(),
+        }
+    }
+
+    /// The number of contacts, i.e., the size of the alphabet.
+    pub fn size(&self) -> usize {
+        self.backward.len()
+    }
+
+    /// The keyboard order entry wheel of the commercial machines.
+    pub fn qwertz() -> EntryWheel {
+        EntryWheel::new(ETW_QWERTZ).unwrap()
+    }
+
+    /// Whether the entry wheel is wired straight through.
+    pub fn is_identity(&self) -> bool {
+        self.backward.iter().enumerate().all(|(i, &v)| i as u8 == v)
+    }
+
+    /// The keys of the entry wheel.
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// The wiring, listing the key wired to each contact.
+    pub fn wiring(&self) -> String {
+        self.backward
+            .iter()
+            .map(|&key| self.alphabet.letter(key))
+            .collect()
+    }
+
+    /// Maps a key to the rotor contact it is wired to.
+    ///
+    /// * `input_val` - The key, `0` - `25`
+    pub fn map_in(&self, input_val: u8) -> u8 {
+        self.forward[input_val as usize]
+    }
+
+    /// Maps a rotor contact back to its key.
+    ///
+    /// * `input_val` - The contact, `0` - `25`
+    pub fn map_out(&self, input_val: u8) -> u8 {
+        self.backward[input_val as usize]
+    }
+}
+
+impl Default for EntryWheel {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl fmt::Display for EntryWheel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.wiring())
+    }
+}
+
+/// Serialized form of an [EntryWheel](EntryWheel).
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EntryWheelState {
+    Wiring(String),
+    WithAlphabet { wiring: String, alphabet: Alphabet },
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<EntryWheelState> for EntryWheel {
+    type Error = String;
+
+    fn try_from(state: EntryWheelState) -> Result<Self, Self::Error> {
+        match state {
+            EntryWheelState::Wiring(wiring) => EntryWheel::new(&wiring),
+            EntryWheelState::WithAlphabet { wiring, alphabet } => {
+                EntryWheel::new_with_alphabet(&wiring, &alphabet)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<EntryWheel> for EntryWheelState {
+    fn from(entry_wheel: EntryWheel) -> Self {
+        if entry_wheel.alphabet.is_latin() {
+            EntryWheelState::Wiring(entry_wheel.wiring())
+        } else {
+            EntryWheelState::WithAlphabet {
+                wiring: entry_wheel.wiring(),
+                alphabet: entry_wheel.alphabet,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity() {
+        let etw = EntryWheel::default();
+        assert!(etw.is_identity());
+        for val in 0..26 {
+            assert_eq!(etw.map_in(val), val);
+            assert_eq!(etw.map_out(val), val);
+        }
+    }
+
+    #[test]
+    fn test_wirings_invert() {
+        for wiring in [ETW_QWERTZ, ETW_T, "zyxwvutsrqponmlkjihgfedcba"] {
+            let etw = EntryWheel::new(wiring).unwrap();
+            assert!(!etw.is_identity());
+            assert_eq!(etw.wiring(), wiring.to_ascii_uppercase());
+            for val in 0..26 {
+                assert_eq!(etw.map_out(etw.map_in(val)), val);
+            }
+        }
+
+        // W is the second key of the keyboard and reaches contact B
+        assert_eq!(EntryWheel::qwertz().map_in(b'W' - b'A'), 1);
+    }
+
+    #[test]
+    fn test_invalid_wirings() {
+        assert!(EntryWheel::new("").is_err());
+        assert!(EntryWheel::new("QWERTZUIOASDFGHJKPYXCVBNM").is_err());
+        assert!(EntryWheel::new("QWERTZUIOASDFGHJKPYXCVBNMQ").is_err());
+        assert!(EntryWheel::new("QWERTZUIOASDFGHJKPYXCVBNM1").is_err());
+    }
+
+    #[test]
+    fn test_other_alphabets() {
+        let digits = Alphabet::digits();
+        let etw = EntryWheel::new_with_alphabet("9876543210", &digits).unwrap();
+        assert_eq!(etw.wiring(), "9876543210");
+        assert_eq!(etw.map_in(9), 0);
+        assert_eq!(EntryWheel::straight(&digits).wiring(), "0123456789");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let etw = EntryWheel::qwertz();
+        let json = serde_json::to_string(&etw).unwrap();
+        assert_eq!(json, format!("\"{}\"", ETW_QWERTZ));
+        assert_eq!(serde_json::from_str::<EntryWheel>(&json).unwrap(), etw);
+
+        let etw = EntryWheel::new_with_alphabet("9876543210", &Alphabet::digits()).unwrap();
+        let json = serde_json::to_string(&etw).unwrap();
+        assert_eq!(json, r#"{"wiring":"9876543210","alphabet":"0123456789"}"#);
+        assert_eq!(serde_json::from_str::<EntryWheel>(&json).unwrap(), etw);
+
+        assert!(serde_json::from_str::<EntryWheel>(r#""1234567890""#).is_err());
+    }
+}
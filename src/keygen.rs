@@ -0,0 +1,208 @@
+//! Enigma key generation module.
+//!
+//! [KeyGenerator](KeyGenerator) draws random day keys (wheel order, ring
+//! settings, start positions and plugboard) the way a key sheet would list
+//! them. Keys are returned as a [Config](Config) so that they can be printed
+//! as a configuration string or built into a machine directly.
+//!
+//! ```
+//! use enigma::keygen::KeyGenerator;
+//! use rand::SeedableRng;
+//!
+//! let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+//! let cfg = KeyGenerator::new().plug_count(6).generate(&mut rng).unwrap();
+//! let mut machine = cfg.build_machine();
+//! ```
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::config::{model_alphabet, Config};
+
+/// Random key generator.
+///
+/// Defaults to reflector `B`, three of the rotors `I` through `V` and ten
+/// plugboard cables, as used by the Wehrmacht and Luftwaffe.
+#[derive(Debug, Clone)]
+pub struct KeyGenerator {
+    reflector_ids: Vec<String>,
+    rotor_ids: Vec<String>,
+    rotor_count: usize,
+    plug_count: usize,
+}
+
+impl Default for KeyGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyGenerator {
+    pub fn new() -> KeyGenerator {
+        KeyGenerator {
+            reflector_ids: vec!["B".to_string()],
+            rotor_ids: ["I", "II", "III", "IV", "V"]
+                .iter()
+                .map(|id| id.to_string())
+                .collect(),
+            rotor_count: 3,
+            plug_count: 10,
+        }
+    }
+
+    /// Set the reflectors to choose from.
+    pub fn reflectors<T>(mut self, reflector_ids: T) -> KeyGenerator
+    where
+        T: IntoIterator<Item = String>,
+    {
+        self.reflector_ids = reflector_ids.into_iter().collect();
+        self
+    }
+
+    /// Set the rotors to choose the wheel order from.
+    pub fn rotors<T>(mut self, rotor_ids: T) -> KeyGenerator
+    where
+        T: IntoIterator<Item = String>,
+    {
+        self.rotor_ids = rotor_ids.into_iter().collect();
+        self
+    }
+
+    /// Set the number of rotors in the machine.
+    pub fn rotor_count(mut self, count: usize) -> KeyGenerator {
+        self.rotor_count = count;
+        self
+    }
+
+    /// Set the number of plugboard cables.
+    pub fn plug_count(mut self, count: usize) -> KeyGenerator {
+        self.plug_count = count;
+        self
+    }
+
+    /// Draw a random key.
+    pub fn generate<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<Config, String> {
+        let reflector_id = self
+            .reflector_ids
+            .choose(rng)
+            .ok_or_else(|| "At least one reflector is required".to_string())?;
+
+        if self.rotor_count == 0 || self.rotor_count > self.rotor_ids.len() {
+            return Err(format!(
+                "Can't choose {} rotors from {} available rotors",
+                self.rotor_count,
+                self.rotor_ids.len()
+            ));
+        }
+
+        if self.plug_count > 13 {
+            return Err(format!(
+                "The plugboard holds at most 13 cables. Received {}",
+                self.plug_count
+            ));
+        }
+
+        // The Enigma Z is set with its ten digits and has no plugboard
+        let alphabet = model_alphabet(None, reflector_id);
+        if !alphabet.is_latin() && self.plug_count > 0 {
+            return Err(format!(
+                "Reflector {} belongs to a machine without a plugboard. Set no plugs",
+                reflector_id
+            ));
+        }
+        let size = alphabet.len() as u8;
+
+        let rotor_ids = self
+            .rotor_ids
+            .choose_multiple(rng, self.rotor_count)
+            .map(|id| (id.clone(), rng.gen_range(0..size), rng.gen_range(0..size)))
+            .collect();
+
+        let mut letters: Vec<char> = ('A'..='Z').collect();
+        letters.shuffle(rng);
+        let plugs = letters
+            .chunks(2)
+            .take(self.plug_count)
+            .map(|pair| {
+                if pair[0] < pair[1] {
+                    (pair[0], pair[1])
+                } else {
+                    (pair[1], pair[0])
+                }
+            })
+            .collect();
+
+        Ok(Config::new(reflector_id, rotor_ids, plugs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generated_key_shape() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let cfg = KeyGenerator::new().generate(&mut rng).unwrap();
+
+        assert_eq!(cfg.reflector_config.reflector_id, "B");
+        assert_eq!(cfg.rotor_config.rotor_ids.len(), 3);
+
+        let plugs = cfg.plugboard_config.unwrap().plugboard_maps;
+        assert_eq!(plugs.len(), 10);
+        let mut letters: Vec<char> = plugs.iter().flat_map(|&(a, b)| [a, b]).collect();
+        letters.sort();
+        letters.dedup();
+        assert_eq!(letters.len(), 20);
+    }
+
+    #[test]
+    fn test_generated_key_is_reproducible() {
+        let keygen = KeyGenerator::new().rotor_count(3).plug_count(0);
+        let cfg1 = keygen.generate(&mut StdRng::seed_from_u64(1)).unwrap();
+        let cfg2 = keygen.generate(&mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(cfg1, cfg2);
+        assert!(cfg1.plugboard_config.is_none());
+
+        // Generated keys always parse back to the same configuration
+        assert_eq!(Config::parse(cfg1.to_string()), Some(cfg1));
+    }
+
+    #[test]
+    fn test_generated_enigma_z_key() {
+        let keygen = KeyGenerator::new()
+            .reflectors(vec!["UKWZ".to_string()])
+            .rotors(["IZ", "IIZ", "IIIZ"].iter().map(|id| id.to_string()))
+            .plug_count(0);
+        for seed in 0..20 {
+            let cfg = keygen.generate(&mut StdRng::seed_from_u64(seed)).unwrap();
+            assert!(cfg
+                .rotor_config
+                .rotor_ids
+                .iter()
+                .all(|&(_, pos, ring)| pos < 10 && ring < 10));
+            assert_eq!(Config::parse(cfg.to_string()), Some(cfg));
+        }
+
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(keygen.plug_count(1).generate(&mut rng).is_err());
+    }
+
+    #[test]
+    fn test_invalid_generator_settings() {
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(KeyGenerator::new()
+            .rotor_count(6)
+            .generate(&mut rng)
+            .is_err());
+        assert!(KeyGenerator::new()
+            .plug_count(14)
+            .generate(&mut rng)
+            .is_err());
+        assert!(KeyGenerator::new()
+            .reflectors(Vec::new())
+            .generate(&mut rng)
+            .is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Enigma keyspace module.
+//!
+//! A [Keyspace](Keyspace) counts the keys a [model](EnigmaModel) could be set
+//! to, exactly, as the product of the choices an operator had: the wheel
+//! order, the reflector, the ring settings, the start positions and the
+//! plugboard pairs. Counts quickly outgrow every primitive integer, so they
+//! are [BigUint](BigUint)s, along with their size in bits.
+//!
+//! Some settings encrypt exactly like others. Only the difference between a
+//! rotor's position and its ring setting changes the wiring, so the ring
+//! setting of a rotor whose notch never turns anything over adds nothing
+//! that the position doesn't. That is the left rotor of a lever stepping
+//! machine, the greek rotor of the M4 and the settable reflectors.
+//! [discount_equivalent](KeyspaceBuilder::discount_equivalent) leaves those
+//! ring settings out.
+//!
+//! ```
+//! use enigma::keyspace::{bits, Keyspace};
+//! use enigma::model::EnigmaModel;
+//!
+//! let keyspace = Keyspace::builder(EnigmaModel::M3)
+//!     .plug_pairs(10)
+//!     .discount_equivalent(true)
+//!     .try_build()
+//!     .unwrap();
+//!
+//! assert_eq!(keyspace.wheel_orders().to_string(), "336");
+//! assert_eq!(keyspace.plugboard().to_string(), "150738274937250");
+//! assert_eq!(bits(keyspace.plugboard()).round(), 47.0);
+//! ```
+pub use num_bigint::BigUint;
+
+use crate::model::EnigmaModel;
+use crate::reflector::Reflector;
+use crate::stepping::Stepping;
+
+/// The number of keys, and so plugboard contacts, on every model with a
+/// plugboard.
+const KEYS: usize = 26;
+
+/// The most pairs a plugboard can connect.
+pub const MAX_PLUG_PAIRS: usize = KEYS / 2;
+
+/// The plug pairs the Wehrmacht used from 1939.
+pub const DEFAULT_PLUG_PAIRS: usize = 10;
+
+/// The size of a count in bits, i.e., its base 2 logarithm. `0` counts as
+/// `0` bits.
+pub fn bits(count: &BigUint) -> f64 {
+    let len = count.bits();
+    if len == 0 {
+        return 0.0;
+    }
+
+    // Keep the top 64 bits, which is more than an f64 can hold anyway
+    let shift = len.saturating_sub(64);
+    let top = (count >> shift).iter_u64_digits().next().unwrap_or(0);
+    (top as f64).log2() + shift as f64
+}
+
+/// The ways to connect `pairs` plugboard cables between `keys` keys.
+fn plug_pairings(keys: usize, pairs: usize) -> BigUint {
+    // Choose the 2n plugged keys in order, then forget the order of the
+    // pairs and of the two ends of each cable
+    let ordered = (keys - 2 * pairs + 1..=keys).fold(BigUint::from(1u32), |acc, k| acc * k);
+    let unordered = (1..=pairs).fold(BigUint::from(1u32), |acc, k| acc * k * 2u32);
+    ordered / unordered
+}
+
+/// Builds a [Keyspace](Keyspace) for a model.
+#[derive(Debug, Clone)]
+pub struct KeyspaceBuilder {
+    model: EnigmaModel,
+    plug_pairs: Option<usize>,
+    ring_settings: bool,
+    discount_equivalent: bool,
+}
+
+impl KeyspaceBuilder {
+    pub fn new(model: EnigmaModel) -> KeyspaceBuilder {
+        KeyspaceBuilder {
+            model,
+            plug_pairs: None,
+            ring_settings: true,
+            discount_equivalent: false,
+        }
+    }
+
+    /// The number of plugboard cables. Defaults to
+    /// [DEFAULT_PLUG_PAIRS](DEFAULT_PLUG_PAIRS) for models with a plugboard
+    /// and `0` for the rest.
+    pub fn plug_pairs(mut self, pairs: usize) -> KeyspaceBuilder {
+        self.plug_pairs = Some(pairs);
+        self
+    }
+
+    /// Whether the ring settings are part of the key. Leave them out when
+    /// they are already known, as they often were from the key sheets.
+    /// Defaults to `true`.
+    pub fn ring_settings(mut self, ring_settings: bool) -> KeyspaceBuilder {
+        self.ring_settings = ring_settings;
+        self
+    }
+
+    /// Whether to leave out ring settings that encrypt exactly like another
+    /// ring setting at another position. Defaults to `false`.
+    pub fn discount_equivalent(mut self, discount_equivalent: bool) -> KeyspaceBuilder {
+        self.discount_equivalent = discount_equivalent;
+        self
+    }
+
+    pub fn try_build(self) -> Result<Keyspace, String> {
+        let model = self.model;
+        let plug_pairs = match self.plug_pairs {
+            Some(pairs) if pairs > 0 && !model.has_plugboard() => {
+                return Err(format!(
+                    "Invalid plug pairs: the Enigma {} has no plugboard",
+                    model
+                ))
+            }
+            Some(pairs) if pairs > MAX_PLUG_PAIRS => {
+                return Err(format!(
+                    "Invalid plug pairs: {}. Expected at most {}",
+                    pairs, MAX_PLUG_PAIRS
+                ))
+            }
+            Some(pairs) => pairs,
+            None if model.has_plugboard() => DEFAULT_PLUG_PAIRS,
+            None => 0,
+        };
+
+        let rotors = model.rotor_count();
+        let stepping_rotors = model.rotor_count() - model.greek_rotors().len().min(1);
+        let wheel_orders = (0..stepping_rotors).fold(BigUint::from(1u32), |acc, i| {
+            acc * (model.rotors().len() - i)
+        }) * model.greek_rotors().len().max(1);
+
+        let settable_reflector = Reflector::new(model.reflectors()[0]).is_settable();
+        let dials = rotors + usize::from(settable_reflector);
+
+        // The ring of the left rotor only matters when it turns the reflector
+        // over. The greek rotor and the reflector have no notch at all
+        let mut rings = dials;
+        if self.discount_equivalent {
+            rings = match model.stepping() {
+                Stepping::Gear => stepping_rotors,
+                _ => stepping_rotors - 1,
+            };
+        }
+        if !self.ring_settings {
+            rings = 0;
+        }
+
+        let keys = BigUint::from(model.alphabet().len());
+        Ok(Keyspace {
+            model,
+            plug_pairs,
+            wheel_orders,
+            reflectors: BigUint::from(model.reflectors().len()),
+            ring_settings: keys.pow(rings as u32),
+            positions: keys.pow(dials as u32),
+            plugboard: plug_pairings(KEYS, plug_pairs),
+        })
+    }
+}
+
+/// The number of keys of a model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyspace {
+    model: EnigmaModel,
+    plug_pairs: usize,
+    wheel_orders: BigUint,
+    reflectors: BigUint,
+    ring_settings: BigUint,
+    positions: BigUint,
+    plugboard: BigUint,
+}
+
+impl Keyspace {
+    pub fn builder(model: EnigmaModel) -> KeyspaceBuilder {
+        KeyspaceBuilder::new(model)
+    }
+
+    /// The model the keys are for.
+    pub fn model(&self) -> EnigmaModel {
+        self.model
+    }
+
+    /// The number of plugboard cables counted.
+    pub fn plug_pairs(&self) -> usize {
+        self.plug_pairs
+    }
+
+    /// The ways to pick and order the rotors, including the greek rotor of
+    /// the M4.
+    pub fn wheel_orders(&self) -> &BigUint {
+        &self.wheel_orders
+    }
+
+    /// The reflectors to choose from.
+    pub fn reflectors(&self) -> &BigUint {
+        &self.reflectors
+    }
+
+    /// The ring settings of the rotors and, where it has one, the reflector.
+    /// `1` when they aren't part of the key.
+    pub fn ring_settings(&self) -> &BigUint {
+        &self.ring_settings
+    }
+
+    /// The start positions of the rotors and any settable reflector.
+    pub fn positions(&self) -> &BigUint {
+        &self.positions
+    }
+
+    /// The ways to connect the plugboard cables.
+    pub fn plugboard(&self) -> &BigUint {
+        &self.plugboard
+    }
+
+    /// The number of keys, i.e., the product of every choice.
+    pub fn total(&self) -> BigUint {
+        &self.wheel_orders
+            * &self.reflectors
+            * &self.ring_settings
+            * &self.positions
+            * &self.plugboard
+    }
+
+    /// The size of the keyspace in bits.
+    pub fn bits(&self) -> f64 {
+        bits(&self.total())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyspace(model: EnigmaModel, discount_equivalent: bool) -> Keyspace {
+        Keyspace::builder(model)
+            .discount_equivalent(discount_equivalent)
+            .try_build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_plugboard() {
+        for (pairs, pairings) in [
+            (0, "1"),
+            (1, "325"),
+            (10, "150738274937250"),
+            (11, "205552193096250"),
+            (13, "7905853580625"),
+        ] {
+            assert_eq!(plug_pairings(KEYS, pairs).to_string(), pairings);
+        }
+    }
+
+    #[test]
+    fn test_models() {
+        for (model, discount_equivalent, total) in [
+            (EnigmaModel::I, false, "8381777611525548310080000"),
+            (EnigmaModel::I, true, "322376061981751858080000"),
+            (EnigmaModel::M4, false, "42306743101588154243518464000"),
+            (EnigmaModel::M4, true, "62583939499390760715264000"),
+            (EnigmaModel::G, false, "1252962387456"),
+            (EnigmaModel::G, true, "48190861056"),
+            (EnigmaModel::K, true, "1853494656"),
+            // Ten positions and ring settings for each rotor and the reflector
+            (EnigmaModel::Z, false, "600000000"),
+        ] {
+            let keyspace = keyspace(model, discount_equivalent);
+            assert_eq!(keyspace.total().to_string(), total, "{}", model);
+        }
+
+        let i = keyspace(EnigmaModel::I, false);
+        assert_eq!(i.wheel_orders().to_string(), "60");
+        assert_eq!(i.reflectors().to_string(), "3");
+        assert_eq!(i.plug_pairs(), 10);
+        assert!((i.bits() - 82.7935).abs() < 1e-3);
+
+        let m4 = keyspace(EnigmaModel::M4, false);
+        assert_eq!(m4.wheel_orders().to_string(), "672");
+    }
+
+    #[test]
+    fn test_constraints() {
+        let keyspace = Keyspace::builder(EnigmaModel::I)
+            .plug_pairs(0)
+            .ring_settings(false)
+            .try_build()
+            .unwrap();
+        assert_eq!(keyspace.total().to_string(), (60 * 3 * 17576).to_string());
+
+        assert!(Keyspace::builder(EnigmaModel::I)
+            .plug_pairs(14)
+            .try_build()
+            .is_err());
+        assert!(Keyspace::builder(EnigmaModel::K)
+            .plug_pairs(1)
+            .try_build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_bits() {
+        assert_eq!(bits(&BigUint::from(0u32)), 0.0);
+        assert_eq!(bits(&BigUint::from(1u32)), 0.0);
+        assert_eq!(bits(&BigUint::from(1024u32)), 10.0);
+        assert_eq!(bits(&(BigUint::from(1u32) << 200u32)), 200.0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Enigma keystream module.
+//!
+//! A [Keystream](Keystream) is the sequence of substitutions a machine will
+//! apply to the next key presses, one [Permutation](Permutation) per key
+//! press, independent of any message. See
+//! [EnigmaMachine::keystream](crate::machine::EnigmaMachine::keystream).
+//!
+//! Keystreams are exported in one of two [formats](KeystreamFormat):
+//!
+//! * `Text` - One line per key press holding, for each key in alphabet
+//!   order, the key it encrypts to.
+//! * `Binary` - One record per key press of one byte per key, holding the
+//!   contact (`0` for `A`) the key encrypts to. There is no header, so a
+//!   machine with the `A` - `Z` alphabet writes 26 bytes per key press.
+//!
+//! ```
+//! use enigma::keystream::KeystreamFormat;
+//! use enigma::machine::EnigmaMachine;
+//!
+//! let em = EnigmaMachine::builder()
+//!     .reflector("B")
+//!     .rotors(vec![
+//!         ("III".to_string(), 0, 0),
+//!         ("II".to_string(), 0, 0),
+//!         ("I".to_string(), 0, 0),
+//!     ])
+//!     .try_build()
+//!     .unwrap();
+//!
+//! let keystream = em.keystream(2);
+//! let mut text = Vec::new();
+//! keystream.write(KeystreamFormat::Text, &mut text).unwrap();
+//! assert_eq!(text.len(), 2 * 27);
+//!
+//! // The machine itself hasn't moved
+//! assert_eq!(em.position_letters(), vec!['A', 'A', 'A']);
+//! ```
+use std::io::{self, Write};
+
+use crate::alphabet::Alphabet;
+use crate::permutation::Permutation;
+
+/// How a [Keystream](Keystream) is written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeystreamFormat {
+    /// One line of keys per key press
+    Text,
+    /// One byte per key, one record per key press
+    Binary,
+}
+
+/// The substitutions for a run of key presses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystream {
+    alphabet: Alphabet,
+    permutations: Vec<Permutation>,
+}
+
+impl Keystream {
+    /// Create a keystream.
+    ///
+    /// * `alphabet` - The keys of the machine
+    /// * `permutations` - One permutation of the alphabet per key press
+    pub fn new(alphabet: Alphabet, permutations: Vec<Permutation>) -> Result<Keystream, String> {
+        if let Some(p) = permutations.iter().find(|p| p.len() != alphabet.len()) {
+            return Err(format!(
+                "Invalid keystream: a permutation of {} keys for an alphabet of {}",
+                p.len(),
+                alphabet.len()
+            ));
+        }
+
+        Ok(Keystream {
+            alphabet,
+            permutations,
+        })
+    }
+
+    /// The permutations, first key press first.
+    pub fn permutations(&self) -> &[Permutation] {
+        &self.permutations
+    }
+
+    /// The keys of the machine.
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// The number of key presses.
+    pub fn len(&self) -> usize {
+        self.permutations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.permutations.is_empty()
+    }
+
+    /// Write the keystream in the given format.
+    pub fn write<W: Write>(&self, format: KeystreamFormat, mut writer: W) -> io::Result<()> {
+        for p in self.permutations.iter() {
+            match format {
+                KeystreamFormat::Text => {
+                    let keys: String = p
+                        .as_slice()
+                        .iter()
+                        .map(|&val| self.alphabet.letter(val))
+                        .collect();
+                    writeln!(writer, "{}", keys)?;
+                }
+                KeystreamFormat::Binary => writer.write_all(p.as_slice())?,
+            }
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        let shift = Permutation::from_letters("BCDEFGHIJKLMNOPQRSTUVWXYZA").unwrap();
+        let keystream =
+            Keystream::new(Alphabet::latin(), vec![Permutation::identity(26), shift]).unwrap();
+
+        let mut text = Vec::new();
+        keystream.write(KeystreamFormat::Text, &mut text).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "ABCDEFGHIJKLMNOPQRSTUVWXYZ\nBCDEFGHIJKLMNOPQRSTUVWXYZA\n"
+        );
+
+        let mut binary = Vec::new();
+        keystream
+            .write(KeystreamFormat::Binary, &mut binary)
+            .unwrap();
+        assert_eq!(binary.len(), 52);
+        assert_eq!(&binary[24..28], &[24, 25, 1, 2]);
+    }
+
+    #[test]
+    fn test_alphabet_mismatch() {
+        assert!(Keystream::new(Alphabet::digits(), vec![Permutation::identity(26)]).is_err());
+    }
+}
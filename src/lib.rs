@@ -22,6 +22,85 @@
 //! enigma -i "LZFAD, AMT GPJ FND IFMJY" -c "A;III-A-A,II-A-A,I-A-A;a-b"
 //! HELLO, HOW ARE YOU DOING
 //! ```
+//!
+//! Each capability also has its own subcommand with its own `--help`. Running
+//! without a subcommand is the same as `encrypt`.
+//!
+//! ```sh
+//! enigma encrypt -i "hello, how are you doing" -c "A;III-A-A,II-A-A,I-A-A;a-b" --group 5
+//! LZFAD AMTGP JFNDI FMJY
+//! enigma decrypt -i "LZFAD AMTGP JFNDI FMJY" -c "A;III-A-A,II-A-A,I-A-A;a-b"
+//! HELLO HOWAR EYOUD OING
+//! enigma check -c "A;III-A-A,II-A-A,I-A-A;A-B"
+//! A;III-A-A,II-A-A,I-A-A;a-b
+//! enigma convert --to keysheet -c "A;III-A-A,II-A-A,I-A-A;a-b"
+//! UKW A | Walzenlage III II I | Ringstellung 01 01 01 | Grundstellung A A A | Stecker AB
+//! enigma keygen --plugs 10 --count 3
+//! enigma crack -i "<ciphertext>" --crib WETTERVORHERSAGE --wheel-order "II IV I"
+//! ```
+//!
+//! The machine can also be set up with discrete flags instead of, or on top
+//! of, a configuration string. Rotor settings are listed left rotor first and
+//! flags override the matching part of `--config`.
+//!
+//! ```sh
+//! enigma --reflector A --rotors "III II I" --rings "01 01 01" --positions AAA --plugs "AB" -i "hello"
+//! LZFAD
+//! enigma check -c "A;III-A-A,II-A-A,I-A-A;a-b" --positions XYZ --plugs "AB CD"
+//! A;III-X-A,II-Y-A,I-Z-A;a-b,c-d
+//! ```
+//!
+//! The `trace` subcommand prints the path every key press takes through the
+//! machine, including the rotor windows and how each rotor stepped. Use
+//! `--format csv` or `--format json` for output that other tools can read.
+//!
+//! ```sh
+//! enigma trace -i "aaa" -c "B;I-A-A,II-D-A,III-U-A"
+//!    #  Key  Before  After   Plug  ETW   III   II    I     UKW   I     II    III   ETW   Lamp
+//!    1  A    ADU     ADV     A     A     R     M     O     M     C     T     E     E     E
+//!    2  A    ADV     AEW     A     A     Y     Z     J     X     Q     D     Q     Q     Q
+//!    3  A    AEW     BFX     A     A     V     V     A     Y     I     O     I     I     I
+//! ```
+//!
+//! The `schedule` subcommand lists when the middle and left rotors turn over,
+//! marking double steps, and how many key presses it takes until the rotor
+//! positions repeat. Pass `--around` to list the positions around a key press.
+//! The same is available in the API as
+//! [StepSchedule](schedule::StepSchedule).
+//!
+//! ```sh
+//! enigma schedule -n 30 -c "B;I-A-A,II-D-A,III-U-A"
+//! Period: 16900 key presses
+//! Lead-in: 0 key presses
+//!
+//! Turnovers in the first 30 key presses:
+//!        2  II      D -> E
+//!        3  II      E -> F  double step
+//!        3  I       A -> B
+//!       28  II      F -> G
+//! ```
+//!
+//! The `repl` subcommand (the `repl` feature, enabled by default) keeps one
+//! machine alive between lines. Plain text is translated and commands such as
+//! `:pos ABC`, `:ring 01 02 03`, `:plug add AB`, `:undo`, `:reset` and `:state`
+//! change or show the settings. See `:help` for the full list.
+//!
+//! ```sh
+//! enigma repl -c "B;III-A-A,II-A-A,I-A-A;a-b"
+//! enigma> aaaa
+//! WUPG
+//! enigma> :state
+//! B;III-A-A,II-A-A,I-E-A;a-b
+//! ```
+//!
+//! The **enigma-tui** binary is a full-screen simulator with a lampboard,
+//! keyboard, rotor windows and a live plugboard. It is built with the `tui`
+//! feature.
+//!
+//! ```sh
+//! cargo run --features tui --bin enigma-tui -- -c "B;I-A-A,II-A-A,III-A-A"
+//! ```
+//!
 //! ## Configuration
 //! The **enigma** configuration string specifies all details for the machine.
 //!
@@ -40,7 +119,10 @@
 //! ```
 //!
 //! The Reflector Id is one of the Wehrmacht and Luftwaffe reflectors. See the
-//! [Reflector](reflector::Reflector) page for valid options.
+//! [Reflector](reflector::Reflector) page for valid options. The settable
+//! reflectors of the commercial, Railway and Enigma G machines take a position
+//! and ring setting like a rotor, e.g., `UKWK-B-A`. The Enigma Z is set with its
+//! digit keys instead of letters, e.g., `UKWZ-1-1;IIIZ-1-1,IIZ-1-1,IZ-0-9`.
 //!
 //! A rotor configuration specifies a rotor id and the position and ring location
 //! settings. See the [rotor](rotor) module page for valid rotor ids.
@@ -58,6 +140,81 @@
 //! <Reflector Id>;<Rotor Configuration>;a-b,d-c
 //! ```
 //!
+//! The Luftwaffe's [Uhr](uhr::Uhr) replaced ten plug cables with a 40 position
+//! switch that makes the steckering non-reciprocal. An optional fourth component
+//! plugs it in at a position `00` - `39`. The plugboard mappings are then the
+//! ten Uhr cables `1` - `10` in order, each listing the letter of its `a` plug
+//! first. Profiles take the position in their `uhr` key.
+//!
+//! ```sh
+//! "B;I-A-A,II-A-A,III-A-A;a-q,w-e,r-t,z-u,i-o,s-d,f-g,h-j,k-p,y-x;UHR-27"
+//! ```
+//!
+//! ### Profile files
+//!
+//! Standard machines can be kept in a TOML or JSON file of named profiles
+//! (the `profiles` feature, enabled by default, which turns on `serde`) and
+//! selected with `--profile`.
+//! Files ending in `.json` are read as JSON. See the [profile](profile) module
+//! for the full format.
+//!
+//! ```toml
+//! [profiles.training]
+//! reflector = "B"
+//! rotors = [
+//!     { id = "I", position = "A", ring = 1 },
+//!     { id = "II", position = "B", ring = 1 },
+//!     { id = "III", position = "C", ring = 1 },
+//! ]
+//! plugs = ["AB", "CD"]
+//! ```
+//!
+//! ```sh
+//! enigma --config-file machines.toml --profile training -i "hello"
+//! ```
+//!
+//! ### Models
+//!
+//! The Beta and Gamma rotors and the thin `B-thin` and `C-thin` reflectors of
+//! the Kriegsmarine M4 are listed like any other component. The fourth (left)
+//! rotor never steps.
+//!
+//! ```sh
+//! "B-thin;Beta-A-A,II-A-A,IV-A-A,I-A-A"
+//! ```
+//!
+//! The commercial Enigma D and K use rotors `IK`, `IIK` and `IIIK` with the
+//! settable `UKWK` reflector. The Swiss-K uses rotors `ISK`, `IISK` and
+//! `IIISK` with the same reflector and the Railway (Rocket) Enigma rotors
+//! `IR`, `IIR` and `IIIR` with the settable `UKWR` reflector.
+//!
+//! The Abwehr Enigma G-31 (`IG`, `IIG`, `IIIG` and `UKWG`) and G-312
+//! (`IG312`, `IIG312`, `IIIG312` and `UKWG312`) rotors have 17, 15 and 11
+//! notches. They are driven by cog wheels like an odometer, without a double
+//! step, and the left rotor steps the reflector.
+//!
+//! The Enigma T (Tirpitz) rotors `IT` through `VIIIT` have five notches each
+//! and run with the settable `UKWT` reflector and the T's own entry wheel.
+//!
+//! ```sh
+//! "UKWK-B-A;IIIK-A-A,IIK-A-A,IK-A-A"
+//! ```
+//!
+//! `--model` checks the settings against an
+//! [EnigmaModel](model::EnigmaModel) (`I`, `M3`, `M4`, `K`, `G`, `G-312`,
+//! `D`, `T`, `Swiss-K` or `Railway`) and rejects rotors, reflectors or plugs the machine never had. Profiles
+//! accept the same names in their `model` key and the API takes them with
+//! [EnigmaMachineBuilder::model](machine::EnigmaMachineBuilder::model). The
+//! model, or otherwise the reflector, also selects the
+//! [EntryWheel](entry_wheel::EntryWheel): straight through on the military
+//! machines and in keyboard order (`QWERTZ...`) on the commercial ones. A
+//! custom wiring can be given with
+//! [EnigmaMachineBuilder::entry_wheel](machine::EnigmaMachineBuilder::entry_wheel).
+//!
+//! ```sh
+//! enigma --model M4 -c "B-thin;Beta-A-A,II-A-A,IV-A-A,I-A-A" -i "hello"
+//! ```
+//!
 //! ## API
 //!
 //! [EnigmaMachine](machine::EnigmaMachine) and
@@ -81,15 +238,141 @@
 //! let trans = machine.translate_text("Hello, how are you".chars());
 //! ```
 //!
-//! # Future Improvements
+//! The rotors are moved by a
+//! [SteppingMechanism](stepping::SteppingMechanism) picked with
+//! [Stepping](stepping::Stepping): lever stepping with the double step, the
+//! M4's lever stepping with a fixed greek rotor, odometer stepping or the gear
+//! stepping of the Enigma G. The model picks one by default and
+//! [EnigmaMachineBuilder::stepping](machine::EnigmaMachineBuilder::stepping)
+//! overrides it, including with a custom mechanism from
+//! [Stepping::custom](stepping::Stepping::custom).
+//!
+//! Machines aren't limited to the letters `A` - `Z`. The Enigma Z had ten digit
+//! keys and its rotors `IZ`, `IIZ` and `IIIZ` and reflector `UKWZ` are part of the
+//! catalogue. Any other [Alphabet](alphabet::Alphabet) is set with
+//! [EnigmaMachineBuilder::alphabet](machine::EnigmaMachineBuilder::alphabet)
+//! and the rotors and reflector are wired for it with
+//! [Rotor::new_custom](rotor::Rotor::new_custom) and
+//! [Reflector::new_custom](reflector::Reflector::new_custom). Such machines have
+//! no plugboard.
+//!
+//! ```
+//! use enigma::machine::EnigmaMachine;
+//! use enigma::model::EnigmaModel;
+//!
+//! let builder = EnigmaMachine::builder()
+//!     .model(EnigmaModel::Z)
+//!     .reflector("UKWZ")
+//!     .rotors(vec![
+//!         ("IIIZ".to_string(), 0, 0),
+//!         ("IIZ".to_string(), 0, 0),
+//!         ("IZ".to_string(), 0, 0),
+//!     ]);
+//!
+//! let cipher = builder.clone().try_build().unwrap().translate_text("0815".chars());
+//! let plain = builder.try_build().unwrap().translate_text(cipher.chars());
+//! assert_eq!(plain, "0815");
+//! ```
+//!
+//! Rotors can also be inserted back to front, or have their wiring core turned
+//! within the alphabet ring, with
+//! [EnigmaMachineBuilder::rotor_with_core](machine::EnigmaMachineBuilder::rotor_with_core).
+//! A core offset works exactly like the same increase of the ring setting.
+//!
+//! [TypexMachine](typex::TypexMachine) emulates the British Typex with the same
+//! rotors: five of them, the two on the right being stators, each with several
+//! notches and optionally inserted back to front. Without a reflector the Typex
+//! isn't reciprocal and deciphers in
+//! [TypexMode::Decipher](typex::TypexMode::Decipher). The service wirings were
+//! never published, so the rotors `A` - `H` are examples.
+//!
+//! Every component implements the [Scrambler](scrambler::Scrambler) trait, so
+//! a [Pipeline](scrambler::Pipeline) can chain them in any order. Starting from
+//! [Pipeline::from_machine](scrambler::Pipeline::from_machine), extra stages
+//! such as a second plugboard or a stator can be inserted anywhere.
 //!
-//! Only a portion of the available rotors and reflectors are currently
-//! implemented. Specifically, the Beta and Gamma rotors and the thin
-//! Kriegsmarine M4 reflectors are not implemented. The implementation
-//! of the machine will handle all of these without issue, including
-//! the fourth rotor of the Kriegsmarine M4.
+//! [EnigmaMachine::translate_traced](machine::EnigmaMachine::translate_traced)
+//! returns a [SignalTrace](trace::SignalTrace) with the rotor stepping and the
+//! letter after every component for a single key press.
+//!
+//! The machine's current settings can be read back with accessors such as
+//! [rotor_ids](machine::EnigmaMachine::rotor_ids),
+//! [position_letters](machine::EnigmaMachine::position_letters),
+//! [rings](machine::EnigmaMachine::rings),
+//! [reflector_id](machine::EnigmaMachine::reflector_id) and
+//! [plug_pairs](machine::EnigmaMachine::plug_pairs), or all at once with
+//! [config](machine::EnigmaMachine::config).
+//!
+//! With the `serde` feature, [EnigmaMachine](machine::EnigmaMachine), its
+//! components, [Config](config::Config) and [Session](session::Session)
+//! implement `Serialize` and `Deserialize`. Machines are stored with their
+//! current rotor positions, so a restored machine continues encrypting exactly
+//! where the original stopped.
+//!
+//! ```json
+//! {"reflector":"B","rotors":[{"id":"I","position":3,"ring":1}, ...],"plugboard":[["A","B"]]}
+//! ```
+//!
+//! ## Cryptanalysis
+//!
+//! [CribAttack](crib::CribAttack) recovers the wheel order, ring settings,
+//! start positions and plugboard from a ciphertext and a crib (a known
+//! fragment of the plaintext at a known offset) in the same way the bombe
+//! did.
+//!
+//! ```no_run
+//! use enigma::crib::CribAttack;
+//!
+//! let solutions = CribAttack::new("ILFDFBRUADONVISRUKNZQM", "IPSUMDOLORSITAMET", 5)
+//!     .unwrap()
+//!     .wheel_orders(vec![vec!["I".to_string(), "II".to_string(), "III".to_string()]])
+//!     .unwrap()
+//!     .solve();
+//! ```
+//!
+//! [EnigmaMachine::permutation](machine::EnigmaMachine::permutation) returns
+//! the substitution at the current rotor positions as a
+//! [Permutation](permutation::Permutation), which can be composed, inverted and
+//! split into cycles for Rejewski-style analysis.
+//!
+//! [EnigmaMachine::keystream](machine::EnigmaMachine::keystream) returns the
+//! permutations of the next key presses without moving the machine, and the
+//! `keystream` subcommand exports them for other tools, either as one line of
+//! letters per key press or, with `--format binary`, as 26 raw bytes per key
+//! press (`0` for `A`).
+//!
+//! ```sh
+//! enigma keystream -n 2 -c "B;I-A-A,II-A-A,III-A-A"
+//! BAQMFEXIHSWPDYTLCVJOZRKGNU
+//! DJRALKWPOBFEYQIHNCXZVUGSMT
+//! ```
+//!
+//! [Banburismus](banburismus::Banburismus) scores messages sent under the
+//! same day key for depth and narrows down the right rotor (and so the
+//! wheel orders a crib attack needs to test) from the turnover evidence.
+//!
+//! [Keyspace](keyspace::Keyspace) counts the keys of a model exactly: the
+//! wheel orders, reflectors, ring settings, start positions and plugboard
+//! pairings. The `keyspace` subcommand lists every model in the catalogue, or
+//! breaks a single model down. Use `--plug-pairs` to change the number of
+//! cables, `--known-rings` to leave the ring settings out and
+//! `--discount-equivalent` to leave out ring settings that encrypt exactly
+//! like another setting.
+//!
+//! ```sh
+//! enigma keyspace --model I
+//! Wheel orders                               60    5.91 bits
+//! Reflectors                                  3    1.58 bits
+//! Ring settings                           17576   14.10 bits
+//! Positions                               17576   14.10 bits
+//! Plugboard (10)                150738274937250   47.10 bits
+//! Total               8381777611525548310080000   82.79 bits
+//! ```
+//!
+//! # Future Improvements
 //!
-//! Some cryptanalysis tooling would be fun to implement. See the
+//! Ciphertext-only attacks (e.g., hill climbing on quadgram fitness) would
+//! be fun to implement. See the
 //! Practical Cryptography and Computerphile links for some motivation.
 //!
 //! # Additional Details
@@ -104,7 +387,26 @@
 //! - [Quadram Statistics as a Fitness Measure, Practical Cryptography](http://practicalcryptography.com/cryptanalysis/text-characterisation/quadgrams/)
 //! - [Cracking Enigma in 2021, Computerphile](https://www.youtube.com/watch?v=RzWB5jL5RX0)
 
+pub mod alphabet;
+pub mod banburismus;
+pub mod config;
+pub mod crib;
+pub mod entry_wheel;
+pub mod keygen;
+pub mod keyspace;
+pub mod keystream;
 pub mod machine;
+pub mod model;
+pub mod permutation;
 pub mod plugboard;
+#[cfg(feature = "profiles")]
+pub mod profile;
 pub mod reflector;
 pub mod rotor;
+pub mod schedule;
+pub mod scrambler;
+pub mod session;
+pub mod stepping;
+pub mod trace;
+pub mod typex;
+pub mod uhr;
@@ -1,36 +1,136 @@
-use lazy_static::lazy_static;
-use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+use crate::alphabet::Alphabet;
+use crate::config::Config;
+use crate::entry_wheel::EntryWheel;
+use crate::keystream::Keystream;
+use crate::model::EnigmaModel;
+use crate::permutation::Permutation;
 use crate::plugboard::PlugBoard;
+#[cfg(feature = "profiles")]
+use crate::profile::Profile;
 use crate::reflector::Reflector;
 use crate::rotor::Rotor;
-
-lazy_static! {
-    static ref VALID_CHAR: Regex = Regex::new("^[a-zA-Z]$").unwrap();
-}
+use crate::stepping::{Stepping, SteppingMechanism};
+use crate::trace::{RotorStep, SignalTrace};
+use crate::uhr::Uhr;
 
 #[derive(Debug, Default, Clone)]
 pub struct EnigmaMachineBuilder {
+    model: Option<EnigmaModel>,
+    alphabet: Option<Alphabet>,
+    entry_wheel: Option<EntryWheel>,
     reflector: Option<Reflector>,
     rotors: Option<Vec<Rotor>>,
     plugboard: Option<PlugBoard>,
+    uhr: Option<Uhr>,
+    stepping: Option<Stepping>,
 }
 
 impl EnigmaMachineBuilder {
     pub fn new() -> EnigmaMachineBuilder {
         EnigmaMachineBuilder {
+            model: None,
+            alphabet: None,
+            entry_wheel: None,
             reflector: None,
             rotors: None,
             plugboard: None,
+            uhr: None,
+            stepping: None,
         }
     }
 
+    /// Restrict the machine to the components of an [EnigmaModel](EnigmaModel)
+    /// and use its stepping mechanism.
+    ///
+    /// Settings that couldn't have existed on the model are rejected by
+    /// [try_build](EnigmaMachineBuilder::try_build).
+    pub fn model(mut self, model: EnigmaModel) -> EnigmaMachineBuilder {
+        self.model = Some(model);
+        self
+    }
+
+    /// Create a builder set up as described by a [Profile](Profile).
+    ///
+    /// The profile is validated first, so that `build` can't fail or panic
+    /// on an invalid rotor or reflector id.
+    #[cfg(feature = "profiles")]
+    pub fn from_profile(profile: &Profile) -> Result<EnigmaMachineBuilder, String> {
+        let cfg = profile.config()?;
+        let uhr = cfg.uhr()?;
+        let mut builder = EnigmaMachineBuilder::new();
+        if let Some(model) = &profile.model {
+            builder = builder.model(model.parse()?);
+        }
+        let builder = builder
+            .reflector_with_state(
+                &cfg.reflector_config.reflector_id,
+                cfg.reflector_config.position,
+                cfg.reflector_config.ring,
+            )
+            .rotors(cfg.rotor_config.rotor_ids);
+
+        Ok(match (uhr, cfg.plugboard_config) {
+            (Some(uhr), _) => builder.uhr(uhr),
+            (None, Some(pbcfg)) => builder.plugboard(pbcfg.plugboard_maps),
+            (None, None) => builder,
+        })
+    }
+
+    /// Set the entry wheel.
+    ///
+    /// Defaults to the entry wheel of the [model](EnigmaMachineBuilder::model)
+    /// if one is given, or of the model the reflector shipped with otherwise.
+    pub fn entry_wheel(mut self, entry_wheel: EntryWheel) -> EnigmaMachineBuilder {
+        self.entry_wheel = Some(entry_wheel);
+        self
+    }
+
+    /// Set how the rotors are stepped.
+    ///
+    /// Defaults to the stepping of the [model](EnigmaMachineBuilder::model)
+    /// if one is given, or of the model the reflector shipped with otherwise.
+    pub fn stepping(mut self, stepping: Stepping) -> EnigmaMachineBuilder {
+        self.stepping = Some(stepping);
+        self
+    }
+
+    /// Set the keys of the machine. Defaults to the keys of the model, which
+    /// are `A` - `Z` for every model but the Enigma Z.
+    ///
+    /// Machines with another alphabet are built from the Enigma Z components
+    /// or from [custom rotors](EnigmaMachineBuilder::custom_rotor) and a
+    /// [custom reflector](EnigmaMachineBuilder::custom_reflector) of the same
+    /// size, and have no plugboard.
+    pub fn alphabet(mut self, alphabet: Alphabet) -> EnigmaMachineBuilder {
+        self.alphabet = Some(alphabet);
+        self
+    }
+
     pub fn reflector(mut self, id: &str) -> EnigmaMachineBuilder {
         let refl = Reflector::new(id);
         self.reflector = Some(refl);
         self
     }
 
+    /// Set a settable reflector, e.g., `UKWK`, to a position and ring
+    /// setting.
+    ///
+    /// [try_build](EnigmaMachineBuilder::try_build) rejects settings on a
+    /// reflector that can't be set.
+    pub fn reflector_with_state(mut self, id: &str, pos: u8, ring_loc: u8) -> EnigmaMachineBuilder {
+        self.reflector = Some(Reflector::new_with_state(id, pos, ring_loc));
+        self
+    }
+
+    /// Use a reflector built with [Reflector::new_custom](Reflector::new_custom).
+    pub fn custom_reflector(mut self, reflector: Reflector) -> EnigmaMachineBuilder {
+        self.reflector = Some(reflector);
+        self
+    }
+
     pub fn plugboard<T>(mut self, mappings: T) -> EnigmaMachineBuilder
     where
         T: IntoIterator<Item = (char, char)>,
@@ -44,6 +144,15 @@ impl EnigmaMachineBuilder {
         self
     }
 
+    /// Plug an [Uhr](Uhr) in place of the plugboard cables.
+    ///
+    /// [try_build](EnigmaMachineBuilder::try_build) rejects an Uhr together
+    /// with plugboard pairs.
+    pub fn uhr(mut self, uhr: Uhr) -> EnigmaMachineBuilder {
+        self.uhr = Some(uhr);
+        self
+    }
+
     pub fn rotors<T>(mut self, rotor_ids: T) -> EnigmaMachineBuilder
     where
         T: IntoIterator<Item = (String, u8, u8)>,
@@ -73,32 +182,180 @@ impl EnigmaMachineBuilder {
         self
     }
 
+    /// Add a rotor with its wiring core turned within the alphabet ring, or
+    /// inserted back to front. See
+    /// [Rotor::set_core_offset](Rotor::set_core_offset) and
+    /// [Rotor::set_reversed](Rotor::set_reversed).
+    ///
+    /// Configuration strings can't describe either variant, so they are lost
+    /// in [EnigmaMachine::config](EnigmaMachine::config).
+    pub fn rotor_with_core(
+        self,
+        rotor_id: &str,
+        pos: u8,
+        ring_loc: u8,
+        core_offset: u8,
+        reversed: bool,
+    ) -> EnigmaMachineBuilder {
+        let mut rotor = Rotor::new_with_state(rotor_id, pos, ring_loc);
+        rotor.set_core_offset(core_offset);
+        rotor.set_reversed(reversed);
+        self.custom_rotor(rotor)
+    }
+
+    /// Add a rotor built with [Rotor::new_custom](Rotor::new_custom), to the
+    /// left of the rotors added so far.
+    pub fn custom_rotor(mut self, rotor: Rotor) -> EnigmaMachineBuilder {
+        self.rotors.get_or_insert_with(Vec::new).push(rotor);
+        self
+    }
+
     pub fn build(self) -> Option<EnigmaMachine> {
-        if self.reflector.is_none() || self.rotors.is_none() {
-            return None;
+        self.try_build().map_err(|err| eprintln!("{}", err)).ok()
+    }
+
+    /// Build the machine, reporting why the settings are invalid if they
+    /// are.
+    pub fn try_build(self) -> Result<EnigmaMachine, String> {
+        let reflector = self.reflector.ok_or("No reflector provided")?;
+        let rotors = self.rotors.ok_or("No rotors provided")?;
+        let plugboard = self.plugboard.unwrap_or_default();
+
+        if self.uhr.is_some() && !plugboard.pairs().is_empty() {
+            return Err(
+                "The Uhr replaces the plugboard cables. Remove the plugboard pairs".to_string(),
+            );
+        }
+
+        if !reflector.is_settable() && (reflector.position() != 0 || reflector.ring() != 0) {
+            return Err(format!("Reflector {} can't be set", reflector.id()));
+        }
+
+        let model = self
+            .model
+            .or_else(|| EnigmaModel::for_reflector(reflector.id()));
+        let alphabet = self
+            .alphabet
+            .or_else(|| model.map(|model| model.alphabet()))
+            .unwrap_or_default();
+        if !alphabet.is_latin() && (!plugboard.pairs().is_empty() || self.uhr.is_some()) {
+            return Err("Only machines with the A - Z alphabet have a plugboard".to_string());
+        }
+        let sizes = rotors
+            .iter()
+            .map(|rotor| (rotor.id(), rotor.size()))
+            .chain([(reflector.id(), reflector.size())]);
+        for (id, size) in sizes {
+            if size != alphabet.len() {
+                return Err(format!(
+                    "{} has {} contacts but the alphabet has {} keys",
+                    id,
+                    size,
+                    alphabet.len()
+                ));
+            }
+        }
+
+        if let Some(model) = self.model {
+            let rotor_ids: Vec<&str> = rotors.iter().map(|rotor| rotor.id()).collect();
+            let has_plugs = !plugboard.pairs().is_empty() || self.uhr.is_some();
+            model.validate(reflector.id(), &rotor_ids, has_plugs)?;
+        }
+
+        let entry_wheel = match self.entry_wheel {
+            Some(entry_wheel) => entry_wheel,
+            None => default_entry_wheel(model, &alphabet)?,
+        };
+        if entry_wheel.size() != alphabet.len() {
+            return Err(format!(
+                "The entry wheel has {} contacts but the alphabet has {} keys",
+                entry_wheel.size(),
+                alphabet.len()
+            ));
         }
+        let stepping = self
+            .stepping
+            .or_else(|| model.map(|model| model.stepping()))
+            .unwrap_or_default();
 
-        Some(EnigmaMachine {
-            reflector: self.reflector.unwrap(),
-            rotors: self.rotors.unwrap(),
-            plugboard: self.plugboard.unwrap_or_default(),
+        Ok(EnigmaMachine {
+            alphabet,
+            entry_wheel,
+            reflector,
+            rotors,
+            plugboard,
+            uhr: self.uhr,
+            stepping,
         })
     }
 }
 
-#[derive(Debug, Default)]
+/// The entry wheel of the model, or a straight through one for machines
+/// without a model or with another alphabet.
+fn default_entry_wheel(
+    model: Option<EnigmaModel>,
+    alphabet: &Alphabet,
+) -> Result<EntryWheel, String> {
+    match model {
+        Some(model) if model.alphabet() == *alphabet => {
+            EntryWheel::new_with_alphabet(model.entry_wheel(), alphabet)
+        }
+        _ => Ok(EntryWheel::straight(alphabet)),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn lever_stepping() -> Stepping {
+    Stepping::Lever
+}
+
+#[cfg(feature = "serde")]
+fn is_default_entry_wheel(entry_wheel: &EntryWheel) -> bool {
+    *entry_wheel == EntryWheel::default()
+}
+
+/// Enigma machine.
+///
+/// With the `serde` feature the machine is serialized with its current rotor
+/// positions so that a deserialized machine carries on exactly where the
+/// original left off.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct EnigmaMachine {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Alphabet::is_latin")
+    )]
+    alphabet: Alphabet,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "is_default_entry_wheel")
+    )]
+    entry_wheel: EntryWheel,
     reflector: Reflector,
     rotors: Vec<Rotor>,
     plugboard: PlugBoard,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    uhr: Option<Uhr>,
+    /// Machines serialized before the stepping was recorded used lever
+    /// stepping
+    #[cfg_attr(feature = "serde", serde(default = "lever_stepping"))]
+    stepping: Stepping,
 }
 
 impl EnigmaMachine {
     pub fn new() -> EnigmaMachine {
         EnigmaMachine {
+            alphabet: Alphabet::latin(),
             reflector: Reflector::new("A"),
+            entry_wheel: EntryWheel::identity(),
             rotors: vec![Rotor::new("I"), Rotor::new("II"), Rotor::new("III")],
             plugboard: PlugBoard::new(),
+            uhr: None,
+            stepping: Stepping::Lever,
         }
     }
 
@@ -107,34 +364,270 @@ impl EnigmaMachine {
     }
 
     pub fn translate(&mut self, input: char) -> char {
-        let mut conv_buf = [0; 4];
+        let trans_input = match self.alphabet.index_of(input) {
+            Some(trans_input) => trans_input,
+            None => return input,
+        };
+
+        self.step_rotors();
+
+        self.alphabet.letter(self.encipher(trans_input))
+    }
+
+    /// Send a key through the machine at the current rotor positions.
+    fn encipher(&self, mut trans_input: u8) -> u8 {
+        trans_input = self.stecker_in(trans_input);
+        trans_input = self.entry_wheel.map_in(trans_input);
 
-        if !(VALID_CHAR.is_match(input.encode_utf8(&mut conv_buf))) {
-            return input;
+        for rotor in self.rotors.iter() {
+            trans_input = rotor.map_in(trans_input);
         }
 
-        let mut trans_input = input.to_ascii_uppercase() as u8 - 65;
-        trans_input = self.plugboard.map(trans_input);
-        let mut rotation_triggered = false;
+        trans_input = self.reflector.map(trans_input);
 
-        for (i, rotor) in self.rotors.iter_mut().enumerate() {
-            if i == 0 || rotor.will_step_next_rotor() || rotation_triggered {
-                rotation_triggered = rotor.will_step_next_rotor();
-                rotor.rotate();
-            }
+        for rotor in self.rotors.iter().rev() {
+            trans_input = rotor.map_out(trans_input);
+        }
 
+        trans_input = self.entry_wheel.map_out(trans_input);
+        self.stecker_out(trans_input)
+    }
+
+    /// The substitution the machine applies at its current rotor positions,
+    /// without stepping.
+    ///
+    /// A key press steps the rotors before the signal goes through, so the
+    /// next key is encrypted with the permutation of the positions after
+    /// stepping.
+    pub fn permutation(&self) -> Permutation {
+        Permutation::new(
+            (0..self.alphabet.len())
+                .map(|val| self.encipher(val as u8))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    /// The permutations the next `count` key presses will apply, without
+    /// moving the machine.
+    pub fn keystream(&self, count: usize) -> Keystream {
+        let mut machine = self.clone();
+        let permutations = (0..count)
+            .map(|_| {
+                machine.step_rotors();
+                machine.permutation()
+            })
+            .collect();
+
+        Keystream::new(self.alphabet.clone(), permutations).unwrap()
+    }
+
+    /// Translate a character while recording the signal path through the
+    /// machine.
+    ///
+    /// The machine is stepped exactly as it would be by
+    /// [translate](EnigmaMachine::translate). Returns `None` (without
+    /// stepping) for characters that the machine can't translate.
+    pub fn translate_traced(&mut self, input: char) -> Option<SignalTrace> {
+        let input_val = self.alphabet.index_of(input)?;
+
+        let positions_before = self.position_letters();
+        let steps = self.step_rotors_traced();
+        let positions_after = self.position_letters();
+
+        let to_char = |val: u8| -> char { self.alphabet.letter(val) };
+
+        let mut trans_input = self.stecker_in(input_val);
+        let plugboard_in = to_char(trans_input);
+
+        trans_input = self.entry_wheel.map_in(trans_input);
+        let entry_in = to_char(trans_input);
+
+        let mut forward = Vec::with_capacity(self.rotors.len());
+        for rotor in self.rotors.iter() {
             trans_input = rotor.map_in(trans_input);
+            forward.push(to_char(trans_input));
         }
 
         trans_input = self.reflector.map(trans_input);
+        let reflector = to_char(trans_input);
 
+        let mut backward = Vec::with_capacity(self.rotors.len());
         for rotor in self.rotors.iter().rev() {
             trans_input = rotor.map_out(trans_input);
+            backward.push(to_char(trans_input));
+        }
+
+        trans_input = self.entry_wheel.map_out(trans_input);
+        let entry_out = to_char(trans_input);
+
+        trans_input = self.stecker_out(trans_input);
+
+        Some(SignalTrace {
+            input: to_char(input_val),
+            positions_before,
+            positions_after,
+            steps,
+            plugboard_in,
+            entry_in,
+            forward,
+            reflector,
+            backward,
+            entry_out,
+            output: to_char(trans_input),
+        })
+    }
+
+    /// Stecker a key on its way in, through the Uhr if one is plugged in.
+    fn stecker_in(&self, input_val: u8) -> u8 {
+        match &self.uhr {
+            Some(uhr) => uhr.map_in(input_val),
+            None => self.plugboard.map(input_val),
         }
+    }
+
+    /// Stecker a letter on its way out to the lamps.
+    fn stecker_out(&self, input_val: u8) -> u8 {
+        match &self.uhr {
+            Some(uhr) => uhr.map_out(input_val),
+            None => self.plugboard.map(input_val),
+        }
+    }
+
+    /// Step the rotors as a key press would, using the machine's
+    /// [stepping mechanism](crate::stepping).
+    fn step_rotors(&mut self) {
+        self.stepping.step(&mut self.rotors, &mut self.reflector);
+    }
+
+    /// Step the rotors as a key press would, returning how each rotor moved.
+    pub(crate) fn step_rotors_traced(&mut self) -> Vec<RotorStep> {
+        let positions_before = self.positions();
+        let at_notch: Vec<bool> = self
+            .rotors
+            .iter()
+            .map(|rotor| rotor.will_step_next_rotor())
+            .collect();
+
+        self.step_rotors();
+
+        self.rotors
+            .iter()
+            .enumerate()
+            .map(|(i, rotor)| {
+                if positions_before[i] == rotor.position() {
+                    RotorStep::Stationary
+                } else if i == 0 || at_notch[i - 1] {
+                    RotorStep::Stepped
+                } else {
+                    RotorStep::DoubleStepped
+                }
+            })
+            .collect()
+    }
+
+    /// The reflector.
+    pub fn reflector(&self) -> &Reflector {
+        &self.reflector
+    }
+
+    /// The rotors, right (fast) rotor first.
+    pub fn rotors(&self) -> &[Rotor] {
+        &self.rotors
+    }
+
+    /// The plugboard.
+    pub fn plugboard(&self) -> &PlugBoard {
+        &self.plugboard
+    }
+
+    /// The Uhr, if one is plugged in place of the plugboard cables.
+    pub fn uhr(&self) -> Option<&Uhr> {
+        self.uhr.as_ref()
+    }
+
+    /// The keys of the machine.
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
 
-        trans_input = self.plugboard.map(trans_input);
+    /// The entry wheel.
+    pub fn entry_wheel(&self) -> &EntryWheel {
+        &self.entry_wheel
+    }
+
+    /// How the rotors are stepped.
+    pub fn stepping(&self) -> &Stepping {
+        &self.stepping
+    }
+
+    /// The reflector id, e.g., `B`.
+    pub fn reflector_id(&self) -> &str {
+        self.reflector.id()
+    }
+
+    /// The number of rotors, not counting the reflector.
+    pub fn rotor_count(&self) -> usize {
+        self.rotors.len()
+    }
+
+    /// Rotor ids, right (fast) rotor first.
+    pub fn rotor_ids(&self) -> Vec<&str> {
+        self.rotors.iter().map(|rotor| rotor.id()).collect()
+    }
+
+    /// Current rotor positions, right (fast) rotor first. `0` is `A`.
+    pub fn positions(&self) -> Vec<u8> {
+        self.rotors.iter().map(|rotor| rotor.position()).collect()
+    }
+
+    /// Letters shown in the rotor windows, right (fast) rotor first.
+    pub fn position_letters(&self) -> Vec<char> {
+        self.rotors
+            .iter()
+            .map(|rotor| self.alphabet.letter(rotor.position()))
+            .collect()
+    }
 
-        (trans_input + 65).into()
+    /// Ring settings, right (fast) rotor first. `0` is `A` (or `01`).
+    pub fn rings(&self) -> Vec<u8> {
+        self.rotors.iter().map(|rotor| rotor.ring()).collect()
+    }
+
+    /// Plugboard pairs in alphabetical order. Empty when an
+    /// [Uhr](EnigmaMachine::uhr) is plugged in.
+    pub fn plug_pairs(&self) -> Vec<(char, char)> {
+        self.plugboard.pairs()
+    }
+
+    /// The current settings, including the rotor positions the machine has
+    /// stepped to, as a [Config](Config).
+    ///
+    /// An entry wheel or stepping other than that of the reflector's model
+    /// is kept in the [Config](Config) too.
+    pub fn config(&self) -> Config {
+        let mut cfg = Config::new(
+            self.reflector_id(),
+            self.rotors
+                .iter()
+                .map(|rotor| (rotor.id().to_string(), rotor.position(), rotor.ring()))
+                .collect(),
+            self.plug_pairs(),
+        )
+        .with_reflector_state(self.reflector.position(), self.reflector.ring());
+
+        let model = EnigmaModel::for_reflector(self.reflector.id());
+        if default_entry_wheel(model, &self.alphabet).as_ref() != Ok(&self.entry_wheel) {
+            cfg = cfg.with_entry_wheel(self.entry_wheel.clone());
+        }
+        if model.map(|model| model.stepping()).unwrap_or_default() != self.stepping {
+            cfg = cfg.with_stepping(self.stepping.clone());
+        }
+
+        match &self.uhr {
+            Some(uhr) => cfg.with_uhr(uhr),
+            None => cfg,
+        }
     }
 
     pub fn translate_text(&mut self, chars: impl Iterator<Item = char>) -> String {
@@ -149,7 +642,7 @@ mod builder_tests {
     #[test]
     fn test_basic_builder() {
         let builder = EnigmaMachine::builder();
-        let em = builder
+        let _em = builder
             .reflector("A")
             .plugboard(vec![('A', 'B')])
             .rotors(vec![
@@ -198,11 +691,43 @@ mod builder_tests {
         ]);
         assert_eq!(em.rotors.unwrap().len(), 4);
     }
+
+    #[test]
+    fn test_builder_model() {
+        let rotors = vec![
+            ("III".to_string(), 0, 0),
+            ("II".to_string(), 0, 0),
+            ("I".to_string(), 0, 0),
+        ];
+
+        let em = EnigmaMachine::builder()
+            .model(EnigmaModel::M3)
+            .reflector("B")
+            .plugboard(vec![('A', 'B')])
+            .rotors(rotors.clone())
+            .try_build();
+        assert_eq!(em.unwrap().stepping(), &Stepping::Lever);
+
+        let em = EnigmaMachine::builder()
+            .model(EnigmaModel::M4)
+            .reflector("B")
+            .rotors(rotors.clone())
+            .try_build();
+        assert!(em.is_err());
+
+        let em = EnigmaMachine::builder()
+            .model(EnigmaModel::M3)
+            .rotors(rotors)
+            .try_build();
+        assert_eq!(em.unwrap_err(), "No reflector provided");
+    }
 }
 
 #[cfg(test)]
 mod machine_tests {
     use super::*;
+    use crate::entry_wheel::{ETW_T, ETW_Z};
+    use crate::rotor::RotorTyre;
 
     #[test]
     fn simple_translation_test() {
@@ -363,9 +888,9 @@ mod machine_tests {
             .reflector("B")
             .plugboard(vec![('B', 'C')])
             .rotors(vec![
-                ("I".to_string(), 'W' as u8 - 65, 12),
-                ("II".to_string(), 'J' as u8 - 65, 17),
-                ("III".to_string(), 'E' as u8 - 65, 6),
+                ("I".to_string(), b'W' - 65, 12),
+                ("II".to_string(), b'J' - 65, 17),
+                ("III".to_string(), b'E' - 65, 6),
             ])
             .build()
             .unwrap();
@@ -410,4 +935,947 @@ mod machine_tests {
 
         assert_eq!(em.translate_text(input.chars()), expected);
     }
+
+    #[test]
+    fn test_traced_translation_matches_translate() {
+        let build = || {
+            EnigmaMachine::builder()
+                .reflector("B")
+                .plugboard(vec![('B', 'C'), ('Q', 'X')])
+                .rotors(vec![
+                    ("I".to_string(), 3, 12),
+                    ("II".to_string(), 4, 17),
+                    ("III".to_string(), 21, 6),
+                ])
+                .build()
+                .unwrap()
+        };
+        let mut em = build();
+        let mut traced = build();
+
+        for c in "Loremipsumdolorsitametconsecteturadipiscingelit".chars() {
+            let trace = traced.translate_traced(c).unwrap();
+            assert_eq!(trace.output, em.translate(c));
+            assert_eq!(trace.input, c.to_ascii_uppercase());
+            assert_eq!(trace.forward.len(), 3);
+            assert_eq!(trace.backward.len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_traced_signal_path() {
+        let mut em = EnigmaMachine::builder()
+            .reflector("B")
+            .plugboard(vec![('A', 'B')])
+            .rotors(vec![
+                ("I".to_string(), 0, 0),
+                ("II".to_string(), 0, 0),
+                ("III".to_string(), 0, 0),
+            ])
+            .build()
+            .unwrap();
+
+        let trace = em.translate_traced('a').unwrap();
+        assert_eq!(trace.positions_before, vec!['A', 'A', 'A']);
+        assert_eq!(trace.positions_after, vec!['B', 'A', 'A']);
+        assert_eq!(
+            trace.steps,
+            vec![
+                RotorStep::Stepped,
+                RotorStep::Stationary,
+                RotorStep::Stationary
+            ]
+        );
+        // B (plugged from A) enters rotor I at position B
+        assert_eq!(trace.plugboard_in, 'B');
+        assert_eq!(trace.entry_in, 'B');
+        assert_eq!(trace.forward, vec!['L', 'H', 'P']);
+        assert_eq!(trace.reflector, 'I');
+        assert_eq!(trace.backward, vec!['Q', 'Q', 'W']);
+        assert_eq!(trace.entry_out, 'W');
+        assert_eq!(trace.output, 'W');
+    }
+
+    #[test]
+    fn test_traced_double_step() {
+        // Classic double step sequence ADU -> ADV -> AEW -> BFX
+        let mut em = EnigmaMachine::builder()
+            .reflector("B")
+            .rotors(vec![
+                ("III".to_string(), 20, 0),
+                ("II".to_string(), 3, 0),
+                ("I".to_string(), 0, 0),
+            ])
+            .build()
+            .unwrap();
+
+        let trace = em.translate_traced('A').unwrap();
+        assert_eq!(trace.positions_after, vec!['V', 'D', 'A']);
+
+        let trace = em.translate_traced('A').unwrap();
+        assert_eq!(trace.positions_after, vec!['W', 'E', 'A']);
+        assert_eq!(
+            trace.steps,
+            vec![
+                RotorStep::Stepped,
+                RotorStep::Stepped,
+                RotorStep::Stationary
+            ]
+        );
+
+        let trace = em.translate_traced('A').unwrap();
+        assert_eq!(trace.positions_after, vec!['X', 'F', 'B']);
+        assert_eq!(
+            trace.steps,
+            vec![
+                RotorStep::Stepped,
+                RotorStep::DoubleStepped,
+                RotorStep::Stepped
+            ]
+        );
+    }
+
+    #[test]
+    fn test_traced_invalid_chars_do_not_step() {
+        let mut em = EnigmaMachine::builder()
+            .reflector("B")
+            .rotors(vec![
+                ("I".to_string(), 0, 0),
+                ("II".to_string(), 0, 0),
+                ("III".to_string(), 0, 0),
+            ])
+            .build()
+            .unwrap();
+
+        assert!(em.translate_traced('.').is_none());
+        assert_eq!(
+            em.translate_traced('A').unwrap().positions_before,
+            vec!['A', 'A', 'A']
+        );
+    }
+
+    #[test]
+    fn test_introspection() {
+        let mut em = EnigmaMachine::builder()
+            .reflector("B")
+            .plugboard(vec![('Q', 'C'), ('A', 'B')])
+            .rotors(vec![
+                ("I".to_string(), 21, 1),
+                ("VI".to_string(), 12, 0),
+                ("III".to_string(), 25, 7),
+            ])
+            .build()
+            .unwrap();
+
+        assert_eq!(em.reflector_id(), "B");
+        assert_eq!(em.rotor_count(), 3);
+        assert_eq!(em.rotor_ids(), vec!["I", "VI", "III"]);
+        assert_eq!(em.positions(), vec![21, 12, 25]);
+        assert_eq!(em.position_letters(), vec!['V', 'M', 'Z']);
+        assert_eq!(em.rings(), vec![1, 0, 7]);
+        assert_eq!(em.plug_pairs(), vec![('A', 'B'), ('C', 'Q')]);
+        assert_eq!(em.config().to_string(), "B;III-Z-H,VI-M-A,I-V-B;a-b,c-q");
+
+        em.translate('A');
+        // I turns over VI, which is sitting on its notch and turns over III
+        assert_eq!(em.position_letters(), vec!['W', 'N', 'A']);
+        assert_eq!(em.rotors()[0].position(), 22);
+    }
+
+    #[test]
+    fn test_entry_wheel_wraps_the_scrambler() {
+        let build = |entry_wheel: EntryWheel| {
+            EnigmaMachine::builder()
+                .entry_wheel(entry_wheel)
+                .reflector("B")
+                .rotors(vec![
+                    ("I".to_string(), 3, 12),
+                    ("II".to_string(), 4, 17),
+                    ("III".to_string(), 21, 6),
+                ])
+                .try_build()
+                .unwrap()
+        };
+        let etw = EntryWheel::qwertz();
+        let mut em = build(etw.clone());
+        let mut straight = build(EntryWheel::default());
+        assert!(straight.entry_wheel().is_identity());
+
+        // Keys are rewired on the way in and the lamps on the way out
+        for c in "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(20).chars() {
+            let contact = (etw.map_in(c as u8 - b'A') + b'A') as char;
+            let lamp = straight.translate(contact) as u8 - b'A';
+            assert_eq!(em.translate(c), (etw.map_out(lamp) + b'A') as char);
+        }
+
+        let trace = em.translate_traced('Q').unwrap();
+        assert_eq!(trace.entry_in, 'A');
+    }
+
+    #[test]
+    fn test_config_keeps_entry_wheel_and_stepping() {
+        let mut em = EnigmaMachine::builder()
+            .entry_wheel(EntryWheel::qwertz())
+            .stepping(Stepping::Odometer)
+            .reflector("B")
+            .rotors(vec![
+                ("I".to_string(), 3, 12),
+                ("II".to_string(), 4, 17),
+                ("III".to_string(), 21, 6),
+            ])
+            .try_build()
+            .unwrap();
+        em.translate_text("ENIGMA".chars());
+
+        let cfg = em.config();
+        assert_eq!(cfg.entry_wheel, Some(EntryWheel::qwertz()));
+        assert_eq!(cfg.stepping, Some(Stepping::Odometer));
+        let mut rebuilt = cfg.build_machine();
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(30);
+        assert_eq!(
+            rebuilt.translate_text(text.chars()),
+            em.translate_text(text.chars())
+        );
+
+        // The entry wheel and stepping of the reflector's model are implied
+        let cfg = Config::parse("UKWG-A-A;IG-A-A,IIG-A-A,IIIG-A-A".to_string())
+            .unwrap()
+            .build_machine()
+            .config();
+        assert_eq!(cfg.entry_wheel, None);
+        assert_eq!(cfg.stepping, None);
+    }
+
+    #[test]
+    fn test_commercial_regression_vectors() {
+        // The expected ciphertexts weren't produced by this crate. They come
+        // from a separate reference model of each machine (its rotor and
+        // reflector wirings, the QWERTZ entry wheel and lever stepping),
+        // written apart from this crate. They haven't been checked against a
+        // historical simulator.
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOGXAAAAAAAAAAAAAAAAAAAAAAAAAA";
+        for (cfg, model, expected) in [
+            (
+                // Reference model, Enigma K with every setting at A
+                "UKWK;IK-A-A,IIK-A-A,IIIK-A-A",
+                EnigmaModel::K,
+                "WGMMKBMGSAJLKDXQMSKWOXQDKPGYQYQAGPRMFVCMZTHMSCVVSPCSCPNCTIDKKC",
+            ),
+            (
+                // Reference model, Enigma D with a set reflector
+                "UKWK-Q-B;IIIK-D-F,IK-Z-A,IIK-X-M",
+                EnigmaModel::D,
+                "SSFVEGTJUDRRLADGTIWNXHJWEYLXRLSHAKYGHZLNYCOHQKGZUYCIWUSIQEMCLU",
+            ),
+            (
+                // Reference model, Swiss-K rotors with a set reflector
+                "UKWK-C-A;IISK-M-G,IIISK-Q-B,ISK-E-Z",
+                EnigmaModel::SwissK,
+                "VGVZFDXDAJGTPCLMENCYBBNPMQGWIPINWHZJEKGGOXTDDVJFXQODZURDKFEERW",
+            ),
+            (
+                // Reference model, Railway with every setting at A
+                "UKWR;IR-A-A,IIR-A-A,IIIR-A-A",
+                EnigmaModel::Railway,
+                "NLNCXWBYRUWZPORKWQUBVWZIDQQBPNTCTKXZVDQLZSYRBTBWLWLXPGUJBHURBI",
+            ),
+            (
+                // Reference model, Railway with a set reflector
+                "UKWR-H-R;IIIR-W-C,IIR-N-O,IR-Y-K",
+                EnigmaModel::Railway,
+                "MGXXHQXRLYGJGAVLYQNDOUPUPQMQHVMUZKHEWJEIJDSOEPKIYDMUMTTXRYIXWY",
+            ),
+        ] {
+            let cfg = Config::parse(cfg.to_string()).unwrap();
+            assert!(cfg.validate_model(model).is_ok());
+
+            // The QWERTZ entry wheel is picked from the reflector
+            let mut em = cfg.clone().build_machine();
+            assert_eq!(em.entry_wheel(), &EntryWheel::qwertz());
+            assert_eq!(em.translate_text(text.chars()), expected);
+
+            let mut em = cfg.build_machine();
+            assert_eq!(em.translate_text(expected.chars()), text);
+        }
+    }
+
+    #[test]
+    fn test_enigma_g_regression_vectors() {
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOGXAAAAAAAAAAAAAAAAAAAAAAAAAA";
+        for (cfg, model, expected, config_after) in [
+            (
+                "UKWG-M-C;IIIG-Z-B,IG-Q-X,IIG-W-D",
+                EnigmaModel::G,
+                "FEHDVEICTDXRHOCZHVZUHWCITSDKTIAFGCIMFHGVVTMZPJFDVWXJNJMBKYBIQV",
+                "UKWG-V-C;IIIG-W-B,IG-A-X,IIG-G-D",
+            ),
+            (
+                "UKWG312-K-U;IIG312-B-O,IIIG312-S-A,IG312-F-R",
+                EnigmaModel::G312,
+                "ZJUDDVUJYEUXEZDLPOBYBTRCNOIPBSVCYIKQRINBSZVHMXBHVRXGPRORJTLQWB",
+                "UKWG312-T-U;IIG312-S-O,IIIG312-G-A,IG312-P-R",
+            ),
+        ] {
+            let cfg = Config::parse(cfg.to_string()).unwrap();
+            assert!(cfg.validate_model(model).is_ok());
+
+            let mut em = cfg.clone().build_machine();
+            assert_eq!(em.stepping(), &Stepping::Gear);
+            assert_eq!(em.translate_text(text.chars()), expected);
+            // The reflector has been stepped along with the rotors
+            assert_eq!(em.config().to_string(), config_after);
+
+            let mut em = cfg.build_machine();
+            assert_eq!(em.translate_text(expected.chars()), text);
+        }
+    }
+
+    #[test]
+    fn test_enigma_t_regression_vectors() {
+        for (cfg, text, expected, config_after) in [
+            (
+                "UKWT;IT-A-A,IIT-A-A,IIIT-A-A",
+                "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+                "FWRYLQCQGZRGSVNOZEPVLYXPXQNMLT",
+                "UKWT;IT-B-A,IIT-G-A,IIIT-E-A",
+            ),
+            (
+                "UKWT-F-C;VIIIT-A-Q,IVT-J-A,VT-V-K",
+                "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG",
+                "RTOWTCIPQBLGHBMHWWBVUWHLOJBMCGPFLVC",
+                "UKWT-F-C;VIIIT-C-Q,IVT-S-A,VT-E-K",
+            ),
+        ] {
+            let cfg = Config::parse(cfg.to_string()).unwrap();
+            assert!(cfg.validate_model(EnigmaModel::T).is_ok());
+
+            let mut em = cfg.clone().build_machine();
+            assert_eq!(em.entry_wheel().wiring(), ETW_T);
+            assert_eq!(em.translate_text(text.chars()), expected);
+            assert_eq!(em.config().to_string(), config_after);
+
+            let mut em = cfg.build_machine();
+            assert_eq!(em.translate_text(expected.chars()), text);
+        }
+    }
+
+    #[test]
+    fn test_gear_stepping_has_no_double_step() {
+        let mut em = EnigmaMachine::builder()
+            .model(EnigmaModel::G)
+            .reflector_with_state("UKWG", 25, 0)
+            .rotors(vec![
+                ("IIG".to_string(), 1, 0),
+                ("IIIG".to_string(), 0, 0),
+                ("IG".to_string(), 25, 0),
+            ])
+            .try_build()
+            .unwrap();
+
+        // B isn't a notch of IIG so nothing but the right rotor moves, even
+        // though the middle rotor sits on one of its notches
+        em.translate('A');
+        assert_eq!(em.position_letters(), vec!['C', 'A', 'Z']);
+        assert_eq!(em.reflector().position(), 25);
+
+        // C is a notch of IIG, A of IIIG and Z of IG, so the carry runs all
+        // the way into the reflector
+        em.translate('A');
+        assert_eq!(em.position_letters(), vec!['D', 'B', 'A']);
+        assert_eq!(em.reflector().position(), 0);
+    }
+
+    #[test]
+    fn test_fixed_reflector_cannot_be_set() {
+        let build = |id: &str| {
+            EnigmaMachine::builder()
+                .reflector_with_state(id, 3, 0)
+                .rotors(vec![
+                    ("IK".to_string(), 0, 0),
+                    ("IIK".to_string(), 0, 0),
+                    ("IIIK".to_string(), 0, 0),
+                ])
+                .try_build()
+        };
+        assert!(build("UKWK").is_ok());
+        assert_eq!(build("B").unwrap_err(), "Reflector B can't be set");
+
+        let em = build("UKWK").unwrap();
+        assert_eq!(em.config().to_string(), "UKWK-D-A;IIIK-A-A,IIK-A-A,IK-A-A");
+    }
+
+    #[test]
+    fn test_m4_greek_rotor_does_not_step() {
+        let mut em = EnigmaMachine::builder()
+            .model(EnigmaModel::M4)
+            .reflector("B-thin")
+            .rotors(vec![
+                ("I".to_string(), 16, 0),
+                ("II".to_string(), 4, 0),
+                ("III".to_string(), 21, 0),
+                ("Gamma".to_string(), 7, 0),
+            ])
+            .try_build()
+            .unwrap();
+
+        em.translate('A');
+        // III steps off its notch, but there is no pawl for the greek rotor
+        assert_eq!(em.position_letters(), vec!['R', 'F', 'W', 'H']);
+    }
+
+    fn uhr_plugs() -> Vec<(char, char)> {
+        vec![
+            ('A', 'Q'),
+            ('W', 'E'),
+            ('R', 'T'),
+            ('Z', 'U'),
+            ('I', 'O'),
+            ('S', 'D'),
+            ('F', 'G'),
+            ('H', 'J'),
+            ('K', 'P'),
+            ('Y', 'X'),
+        ]
+    }
+
+    fn uhr_machine(uhr: Uhr) -> EnigmaMachine {
+        EnigmaMachine::builder()
+            .uhr(uhr)
+            .reflector("B")
+            .rotors(vec![
+                ("III".to_string(), 4, 1),
+                ("II".to_string(), 20, 5),
+                ("I".to_string(), 11, 0),
+            ])
+            .try_build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_uhr_is_reciprocal_machine() {
+        for position in [0, 13, 27, 39] {
+            let uhr = Uhr::new(uhr_plugs(), position).unwrap();
+            let mut em1 = uhr_machine(uhr.clone());
+            let mut em2 = uhr_machine(uhr);
+
+            let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(10);
+            let cipher = em1.translate_text(text.chars());
+            assert_eq!(em2.translate_text(cipher.chars()), text);
+        }
+    }
+
+    #[test]
+    fn test_uhr_at_00_matches_plug_cables() {
+        let plugs = uhr_plugs();
+        // At 00 cable n's a plug is wired to the b plug of these cables
+        let pairs: Vec<(char, char)> = [1, 4, 7, 9, 6, 3, 0, 2, 5, 8]
+            .iter()
+            .zip(&plugs)
+            .map(|(&b_plug, &(a, _))| (a, plugs[b_plug].1))
+            .collect();
+
+        let mut uhr = uhr_machine(Uhr::new(plugs, 0).unwrap());
+        let mut cables = EnigmaMachine::builder()
+            .reflector("B")
+            .plugboard(pairs)
+            .rotors(vec![
+                ("III".to_string(), 4, 1),
+                ("II".to_string(), 20, 5),
+                ("I".to_string(), 11, 0),
+            ])
+            .try_build()
+            .unwrap();
+
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(10);
+        assert_eq!(
+            uhr.translate_text(text.chars()),
+            cables.translate_text(text.chars())
+        );
+        assert_eq!(uhr.config().to_string().rsplit(';').next(), Some("UHR-00"));
+        assert!(uhr.plug_pairs().is_empty());
+    }
+
+    #[test]
+    fn test_uhr_replaces_plugboard() {
+        let em = EnigmaMachine::builder()
+            .uhr(Uhr::new(uhr_plugs(), 0).unwrap())
+            .plugboard(vec![('B', 'C')])
+            .reflector("B")
+            .rotors(vec![
+                ("III".to_string(), 0, 0),
+                ("II".to_string(), 0, 0),
+                ("I".to_string(), 0, 0),
+            ])
+            .try_build();
+        assert!(em.is_err());
+
+        // The commercial machines had no plugboard to plug it into
+        let em = EnigmaMachine::builder()
+            .model(EnigmaModel::K)
+            .uhr(Uhr::new(uhr_plugs(), 0).unwrap())
+            .reflector("UKWK")
+            .rotors(vec![
+                ("IIIK".to_string(), 0, 0),
+                ("IIK".to_string(), 0, 0),
+                ("IK".to_string(), 0, 0),
+            ])
+            .try_build();
+        assert!(em.is_err());
+    }
+
+    #[test]
+    fn test_stepping_overrides_model() {
+        let mut em = EnigmaMachine::builder()
+            .model(EnigmaModel::M4)
+            .stepping(Stepping::Lever)
+            .reflector("B-thin")
+            .rotors(vec![
+                ("I".to_string(), 16, 0),
+                ("II".to_string(), 4, 0),
+                ("III".to_string(), 21, 0),
+                ("Gamma".to_string(), 7, 0),
+            ])
+            .try_build()
+            .unwrap();
+
+        em.translate('A');
+        // With a fourth pawl III turns over the greek rotor
+        assert_eq!(em.position_letters(), vec!['R', 'F', 'W', 'I']);
+    }
+
+    #[test]
+    fn test_custom_stepping() {
+        /// Only ever steps the middle rotor.
+        #[derive(Debug)]
+        struct MiddleOnly;
+
+        impl SteppingMechanism for MiddleOnly {
+            fn step(&self, rotors: &mut [Rotor], _reflector: &mut Reflector) {
+                rotors[1].rotate();
+            }
+        }
+
+        let mut em = EnigmaMachine::builder()
+            .stepping(Stepping::custom(MiddleOnly))
+            .reflector("B")
+            .rotors(vec![
+                ("I".to_string(), 0, 0),
+                ("II".to_string(), 0, 0),
+                ("III".to_string(), 0, 0),
+            ])
+            .try_build()
+            .unwrap();
+
+        em.translate_text("AAAA".chars());
+        assert_eq!(em.position_letters(), vec!['A', 'E', 'A']);
+    }
+
+    #[test]
+    fn test_core_offset_matches_ring_setting() {
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(20);
+        let mut offset = EnigmaMachine::builder()
+            .reflector("B")
+            .rotor_with_core("III", 21, 0, 3, false)
+            .rotor_with_core("II", 4, 10, 17, false)
+            .rotor_with_core("I", 16, 25, 2, false)
+            .try_build()
+            .unwrap();
+        let mut rings = EnigmaMachine::builder()
+            .reflector("B")
+            .rotor("III", 21, 3)
+            .rotor("II", 4, 1)
+            .rotor("I", 16, 1)
+            .try_build()
+            .unwrap();
+        assert_eq!(
+            offset.translate_text(text.chars()),
+            rings.translate_text(text.chars())
+        );
+    }
+
+    #[test]
+    fn test_reversed_rotor() {
+        let build = |reversed| {
+            EnigmaMachine::builder()
+                .reflector("B")
+                .rotor("III", 0, 0)
+                .rotor_with_core("II", 0, 0, 0, reversed)
+                .rotor("I", 0, 0)
+                .try_build()
+                .unwrap()
+        };
+
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG";
+        let mut em = build(true);
+        let cipher = em.translate_text(text.chars());
+        assert_ne!(cipher, build(false).translate_text(text.chars()));
+        assert_eq!(build(true).translate_text(cipher.chars()), text);
+
+        // III turns over at V, stepping the reversed middle rotor from A
+        // down to Z
+        assert_eq!(em.position_letters(), vec!['J', 'Z', 'A']);
+        assert!(em.rotors()[1].is_reversed());
+    }
+
+    #[test]
+    fn test_permutation_analysis() {
+        let permutations = |cfg: &str| {
+            let mut em = Config::parse(cfg.to_string()).unwrap().build_machine();
+            (0..6)
+                .map(|_| {
+                    em.step_rotors();
+                    em.permutation()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let cfg = "B;I-A-Q,II-C-B,III-X-E;A-B,C-D,E-F";
+        let plugged = permutations(cfg);
+        let mut em = Config::parse(cfg.to_string()).unwrap().build_machine();
+        for (p, c) in plugged
+            .iter()
+            .zip(em.translate_text("AAAAAA".chars()).chars())
+        {
+            assert_eq!(p.apply(0), c as u8 - b'A');
+            assert!(p.is_involution());
+            assert!(!p.has_fixed_points());
+        }
+
+        // Rejewski's characteristic: the products of the permutations three
+        // key presses apart have their cycles in pairs of equal length, and
+        // the plugboard only relabels them
+        let plain = permutations("B;I-A-Q,II-C-B,III-X-E");
+        for i in 0..3 {
+            let product = plugged[i].compose(&plugged[i + 3]);
+            let lengths = product.cycle_type();
+            assert!(lengths.chunks(2).all(|pair| pair[0] == pair[1]));
+            assert!(product.is_conjugate(&plain[i].compose(&plain[i + 3])));
+        }
+    }
+
+    #[test]
+    fn test_keystream_matches_translation() {
+        let mut em = Config::parse("C;VI-Q-C,VIII-E-X,VII-U-D;Q-Z,W-E".to_string())
+            .unwrap()
+            .build_machine();
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG";
+
+        let keystream = em.keystream(text.len());
+        assert_eq!(keystream.len(), text.len());
+        assert_eq!(em.position_letters(), vec!['U', 'E', 'Q']);
+
+        let expected: String = keystream
+            .permutations()
+            .iter()
+            .zip(text.chars())
+            .map(|(p, c)| (p.apply(c as u8 - b'A') + b'A') as char)
+            .collect();
+        assert_eq!(em.translate_text(text.chars()), expected);
+    }
+
+    fn digit_machine() -> EnigmaMachineBuilder {
+        let digits = Alphabet::digits();
+        let rotor = |id, wiring, notch| {
+            Rotor::new_custom(id, RotorTyre::new(wiring, notch, &digits).unwrap(), 0, 0)
+        };
+        EnigmaMachine::builder()
+            .alphabet(digits.clone())
+            .custom_rotor(rotor("3", "6418270359", "9"))
+            .custom_rotor(rotor("2", "5841097632", "9"))
+            .custom_rotor(rotor("1", "3581620794", "9"))
+            .custom_reflector(Reflector::new_custom("UKW", "5678901234", &digits).unwrap())
+    }
+
+    #[test]
+    fn test_enigma_z() {
+        let builder = EnigmaMachine::builder()
+            .reflector_with_state("UKWZ", 3, 5)
+            .rotors(vec![
+                ("IIIZ".to_string(), 6, 1),
+                ("IIZ".to_string(), 7, 0),
+                ("IZ".to_string(), 2, 4),
+            ]);
+        let text = "31415926535897932384626433832795";
+        // Computed with an independent reference implementation
+        let expected = "87959313124433489771293851773414";
+
+        // The alphabet and entry wheel are picked from the reflector
+        let mut em = builder.clone().try_build().unwrap();
+        assert_eq!(em.alphabet(), &Alphabet::z());
+        assert_eq!(em.entry_wheel().wiring(), ETW_Z);
+        assert_eq!(em.translate_text(text.chars()), expected);
+        // The middle rotor double steps from 9 to 0 and on to 1
+        assert_eq!(em.position_letters(), vec!['9', '2', '4']);
+
+        let mut em = builder.model(EnigmaModel::Z).try_build().unwrap();
+        assert_eq!(em.translate_text(expected.chars()), text);
+    }
+
+    #[test]
+    fn test_digit_alphabet() {
+        let mut em = digit_machine().try_build().unwrap();
+        assert_eq!(em.entry_wheel().size(), 10);
+
+        let text = "31415926535897932384626433832795";
+        let cipher = em.translate_text(text.chars());
+        assert_eq!(cipher.len(), text.len());
+        assert!(cipher.chars().all(|c| c.is_ascii_digit()));
+        // Like any Enigma no digit encrypts to itself
+        assert!(cipher.chars().zip(text.chars()).all(|(c, p)| c != p));
+        // Letters aren't keys of this machine
+        assert_eq!(em.translate('A'), 'A');
+
+        // The right rotor turns over the middle rotor on 9
+        assert_eq!(em.position_letters(), vec!['2', '3', '0']);
+
+        let mut em = digit_machine().try_build().unwrap();
+        assert_eq!(em.translate_text(cipher.chars()), text);
+    }
+
+    #[test]
+    fn test_alphabet_mismatch() {
+        let latin_rotors = vec![
+            ("I".to_string(), 0, 0),
+            ("II".to_string(), 0, 0),
+            ("III".to_string(), 0, 0),
+        ];
+        let err = EnigmaMachine::builder()
+            .alphabet(Alphabet::digits())
+            .reflector("B")
+            .rotors(latin_rotors)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(err, "I has 26 contacts but the alphabet has 10 keys");
+
+        assert!(digit_machine()
+            .plugboard(vec![('A', 'B')])
+            .try_build()
+            .is_err());
+        assert!(digit_machine()
+            .entry_wheel(EntryWheel::identity())
+            .try_build()
+            .is_err());
+        assert!(Reflector::new_custom("UKWZ", "1234567890", &Alphabet::digits()).is_err());
+        assert!(Reflector::new_custom("UKWZ", "9876543210", &Alphabet::digits()).is_ok());
+    }
+
+    #[test]
+    fn test_m4_with_beta_at_a_matches_m3() {
+        let plugs = vec![('A', 'T'), ('B', 'L'), ('D', 'F')];
+        let rotors = vec![
+            ("I".to_string(), 3, 5),
+            ("IV".to_string(), 17, 0),
+            ("II".to_string(), 9, 21),
+        ];
+
+        let mut m3 = EnigmaMachine::builder()
+            .model(EnigmaModel::M3)
+            .reflector("B")
+            .plugboard(plugs.clone())
+            .rotors(rotors.clone())
+            .try_build()
+            .unwrap();
+        let mut m4 = EnigmaMachine::builder()
+            .model(EnigmaModel::M4)
+            .reflector("B-thin")
+            .plugboard(plugs)
+            .rotors(rotors)
+            .rotor("Beta", 0, 0)
+            .try_build()
+            .unwrap();
+
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(30);
+        assert_eq!(
+            m4.translate_text(text.chars()),
+            m3.translate_text(text.chars())
+        );
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    fn machine() -> EnigmaMachine {
+        EnigmaMachine::builder()
+            .reflector("B")
+            .plugboard(vec![('A', 'B'), ('Q', 'C')])
+            .rotors(vec![
+                ("I".to_string(), 3, 1),
+                ("VI".to_string(), 12, 0),
+                ("III".to_string(), 25, 7),
+            ])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_component_formats() {
+        let json = serde_json::to_string(&machine()).unwrap();
+        assert_eq!(
+            json,
+            "{\"reflector\":\"B\",\"rotors\":[\
+             {\"id\":\"I\",\"position\":3,\"ring\":1},\
+             {\"id\":\"VI\",\"position\":12,\"ring\":0},\
+             {\"id\":\"III\",\"position\":25,\"ring\":7}],\
+             \"plugboard\":[[\"A\",\"B\"],[\"C\",\"Q\"]],\
+             \"stepping\":\"Lever\"}"
+        );
+    }
+
+    #[test]
+    fn test_missing_stepping_is_lever() {
+        let json = serde_json::to_string(&machine())
+            .unwrap()
+            .replace(",\"stepping\":\"Lever\"", "");
+        assert!(!json.contains("stepping"));
+
+        let restored: EnigmaMachine = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.stepping(), &Stepping::Lever);
+    }
+
+    #[test]
+    fn test_round_trip_continues_encryption() {
+        let mut em = machine();
+        em.translate_text("ENIGMA".chars());
+
+        let json = serde_json::to_string(&em).unwrap();
+        let mut restored: EnigmaMachine = serde_json::from_str(&json).unwrap();
+
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(30);
+        assert_eq!(
+            restored.translate_text(text.chars()),
+            em.translate_text(text.chars())
+        );
+    }
+
+    #[test]
+    fn test_reversed_rotor_round_trip() {
+        let mut em = EnigmaMachine::builder()
+            .reflector("B")
+            .rotor("III", 0, 0)
+            .rotor_with_core("II", 4, 0, 9, true)
+            .rotor("I", 0, 0)
+            .try_build()
+            .unwrap();
+        em.translate_text("ENIGMA".chars());
+
+        let json = serde_json::to_string(&em).unwrap();
+        assert!(json.contains(r#""core_offset":9,"reversed":true"#));
+        let mut restored: EnigmaMachine = serde_json::from_str(&json).unwrap();
+
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG";
+        assert_eq!(
+            restored.translate_text(text.chars()),
+            em.translate_text(text.chars())
+        );
+    }
+
+    #[test]
+    fn test_entry_wheel_round_trip() {
+        let mut em = EnigmaMachine::builder()
+            .entry_wheel(EntryWheel::qwertz())
+            .reflector("B")
+            .rotors(vec![
+                ("I".to_string(), 3, 1),
+                ("VI".to_string(), 12, 0),
+                ("III".to_string(), 25, 7),
+            ])
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&em).unwrap();
+        assert!(json.starts_with("{\"entry_wheel\":\"QWERTZUIOASDFGHJKPYXCVBNML\","));
+
+        let mut restored: EnigmaMachine = serde_json::from_str(&json).unwrap();
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG";
+        assert_eq!(
+            restored.translate_text(text.chars()),
+            em.translate_text(text.chars())
+        );
+    }
+
+    #[test]
+    fn test_other_alphabet_round_trip() {
+        let mut em = EnigmaMachine::builder()
+            .model(EnigmaModel::Z)
+            .reflector("UKWZ")
+            .rotors(vec![
+                ("IIIZ".to_string(), 6, 1),
+                ("IIZ".to_string(), 7, 0),
+                ("IZ".to_string(), 2, 4),
+            ])
+            .try_build()
+            .unwrap();
+        em.translate_text("0815".chars());
+
+        let json = serde_json::to_string(&em).unwrap();
+        assert!(json.starts_with(
+            "{\"alphabet\":\"1234567890\",\
+             \"entry_wheel\":{\"wiring\":\"1234567890\",\"alphabet\":\"1234567890\"},"
+        ));
+
+        let mut restored: EnigmaMachine = serde_json::from_str(&json).unwrap();
+        let text = "31415926535897932384626433832795";
+        assert_eq!(
+            restored.translate_text(text.chars()),
+            em.translate_text(text.chars())
+        );
+    }
+
+    #[test]
+    fn test_settable_reflector_round_trip() {
+        let em = Config::parse("UKWK-D-B;IIIK-A-A,IIK-A-A,IK-A-A".to_string())
+            .unwrap()
+            .build_machine();
+
+        let json = serde_json::to_string(&em).unwrap();
+        assert!(json.contains("\"reflector\":{\"id\":\"UKWK\",\"position\":3,\"ring\":1}"));
+
+        let restored: EnigmaMachine = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.config(), em.config());
+
+        let fixed = serde_json::to_string(&machine()).unwrap().replace(
+            "\"reflector\":\"B\"",
+            "\"reflector\":{\"id\":\"B\",\"position\":3,\"ring\":1}",
+        );
+        assert!(serde_json::from_str::<EnigmaMachine>(&fixed).is_err());
+    }
+
+    #[test]
+    fn test_uhr_round_trip() {
+        let plugs = "a-q,w-e,r-t,z-u,i-o,s-d,f-g,h-j,k-p,y-x";
+        let mut em = Config::parse(format!("B;I-A-A,II-A-A,III-A-A;{};UHR-27", plugs))
+            .unwrap()
+            .build_machine();
+        em.translate_text("HELLO".chars());
+
+        let json = serde_json::to_string(&em).unwrap();
+        assert!(json.contains("\"uhr\":{\"position\":27,\"plugs\":[[\"A\",\"Q\"],"));
+        assert!(json.contains("\"plugboard\":[]"));
+
+        let mut restored: EnigmaMachine = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.config(), em.config());
+        assert_eq!(
+            restored.translate_text("WORLD".chars()),
+            em.translate_text("WORLD".chars())
+        );
+    }
+
+    #[test]
+    fn test_invalid_components() {
+        let json = serde_json::to_string(&machine()).unwrap();
+        for (from, to) in [
+            ("\"reflector\":\"B\"", "\"reflector\":\"Z\""),
+            ("\"id\":\"VI\"", "\"id\":\"IX\""),
+            ("[\"C\",\"Q\"]", "[\"B\",\"Q\"]"),
+        ] {
+            let invalid = json.replace(from, to);
+            assert!(
+                serde_json::from_str::<EnigmaMachine>(&invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
 }
@@ -1,16 +1,104 @@
 use std::io::{self, BufRead, BufReader};
 
-use enigma::machine::EnigmaMachine;
+#[cfg(feature = "repl")]
+use enigma::config::{parse_plug, parse_positions, parse_rings};
+use enigma::config::{Config, ConfigOverrides};
+use enigma::crib::{possible_offsets, CribAttack, DEFAULT_MAX_SOLUTIONS};
+use enigma::keygen::KeyGenerator;
+use enigma::keyspace::{self, BigUint, Keyspace};
+use enigma::keystream::KeystreamFormat;
+use enigma::model::EnigmaModel;
+#[cfg(feature = "profiles")]
+use enigma::profile::ProfileFile;
+use enigma::reflector::Reflector;
+use enigma::schedule::StepSchedule;
+use enigma::session::Session;
+use enigma::trace::{RotorStep, SignalTrace};
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+#[cfg(feature = "repl")]
+use rustyline::error::ReadlineError;
+#[cfg(feature = "repl")]
+use rustyline::DefaultEditor;
 
 #[derive(Parser)]
 #[clap(author, version, about)]
+#[clap(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
 /// Enigma machine encoding and decoding CLI. See --help for more details.
 ///
 /// enigma provides a CLI for encoding / decoding text via an Enigma Machine.
 /// See the `enigma` library for details on exactly what is supported.
+///
+/// Running without a subcommand translates the input, e.g.,
+/// `enigma -c <config> -i <input>`, the same as `enigma encrypt`.
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    machine: MachineArgs,
+
+    /// Input string for encoding / decoding.
+    ///
+    /// Defaults to stdin if this option is not provided.
+    #[clap(short, long)]
+    input: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encrypt the input
+    Encrypt(TranslateArgs),
+    /// Decrypt the input
+    ///
+    /// Enigma is reciprocal so this is the same as `encrypt`. Grouping
+    /// spaces in the input are kept as is.
+    Decrypt(TranslateArgs),
+    /// Generate random keys
+    Keygen(KeygenArgs),
+    /// Recover a key from a ciphertext and a known plaintext fragment (crib)
+    Crack(CrackArgs),
+    /// Validate a configuration string and print its normalized form
+    Check(MachineArgs),
+    /// Convert a configuration string to another format
+    Convert(ConvertArgs),
+    /// Print the signal path through the machine for every key press
+    Trace(TraceArgs),
+    /// Export the substitution of each of the next key presses
+    ///
+    /// The text format prints one line per key press holding the letter
+    /// each of A - Z encrypts to. The binary format writes 26 bytes per key
+    /// press, 0 for A.
+    Keystream(KeystreamArgs),
+    /// Show when the rotors turn over and how often their positions repeat
+    ///
+    /// Lists every time a rotor other than the right rotor moves, marking
+    /// double steps, and the number of key presses until the rotor
+    /// positions repeat. The lead-in counts the key presses before the
+    /// machine reaches positions it will come back to.
+    Schedule(ScheduleArgs),
+    /// Count the keys of the Enigma models
+    ///
+    /// Without --model every model in the catalogue is listed, and the plug
+    /// pairs only apply to models with a plugboard.
+    Keyspace(KeyspaceArgs),
+    /// Interactive session that keeps one machine alive between lines
+    ///
+    /// Plain text is translated. Lines starting with ':' are commands, see
+    /// `:help` inside the session.
+    #[cfg(feature = "repl")]
+    Repl(ReplArgs),
+}
+
+/// Machine settings shared by every subcommand that needs a machine.
+///
+/// The machine is described by a configuration string, by the discrete
+/// flags, or by both, in which case the flags override the matching part of
+/// the configuration string.
+#[derive(Args)]
+struct MachineArgs {
     /// Enigma machine configuration string
     ///
     /// This must be of the form:
@@ -18,11 +106,17 @@ struct Cli {
     ///     <reflector id>;<rotor ids>;<plugboard mappings>
     ///
     /// <reflector id> must be a valid reflector id. See enigma::reflector for
-    /// specifics. Currently one of 'A', 'B', or 'C' is supported.
+    /// specifics. Currently one of 'A', 'B', 'C', 'B-thin', 'C-thin', 'UKWK',
+    /// 'UKWR', 'UKWG', 'UKWG312', 'UKWT' or 'UKWZ' is supported. The 'UKW...'
+    /// reflectors may be followed by a position and ring location, e.g.,
+    /// 'UKWK-B-A'.
     ///
-    /// <rotor ids> must contain three rotor ids in the below form. See
-    /// enigma::rotor for specifics on rotor specifics. Currently one of
-    /// 'I', 'II', 'III', 'IV', 'V', 'VI', 'VII', 'VIII' is supported.
+    /// <rotor ids> must contain three or four rotor ids in the below form.
+    /// See enigma::rotor for specifics on rotor specifics. Currently one of
+    /// 'I', 'II', 'III', 'IV', 'V', 'VI', 'VII', 'VIII', 'Beta', 'Gamma',
+    /// 'IK', 'IIK', 'IIIK', 'ISK', 'IISK', 'IIISK', 'IR', 'IIR', 'IIIR',
+    /// 'IG', 'IIG', 'IIIG', 'IG312', 'IIG312', 'IIIG312', 'IT', 'IIT', 'IIIT',
+    /// 'IVT', 'VT', 'VIT', 'VIIT', 'VIIIT', 'IZ', 'IIZ' or 'IIIZ' is supported.
     ///
     ///     <rotor spec1>,<rotor spec2>,<rotor spec3>
     ///
@@ -33,6 +127,8 @@ struct Cli {
     /// Rotor position should be an alphabetic character and defines the given
     /// rotors starting position. Likewise, ring position should also be an
     /// alphabetic character and defines the rotors ring position setting.
+    /// The Enigma Z ('UKWZ') is set with its digit keys instead, e.g.,
+    /// 'UKWZ;IIIZ-1-1,IIZ-1-1,IZ-1-1'.
     ///
     /// The rotor id values and reflector id define the rotor structure as if
     /// you were looking at an Enigma Machine from the front. For example:
@@ -46,202 +142,913 @@ struct Cli {
     ///
     /// This would map char1 to char2 (and vice versa) and char3 to char4 (and
     /// vice versa) on the plugboard.
+    ///
+    /// An optional fourth part plugs in an Uhr at a position 00 - 39, e.g.,
+    /// 'UHR-27'. The plugboard mappings are then its ten cables in order,
+    /// each listing the letter of the a plug first.
     #[clap(short, long, value_parser)]
-    config: String,
+    config: Option<String>,
+
+    /// TOML or JSON file with named machine profiles.
+    ///
+    /// Files ending in .json are read as JSON, anything else as TOML. See
+    /// the `enigma::profile` module for the file format.
+    #[cfg(feature = "profiles")]
+    #[clap(long, conflicts_with = "config")]
+    config_file: Option<String>,
+
+    /// Profile to use from --config-file. May be left out if the file holds
+    /// only one profile
+    #[cfg(feature = "profiles")]
+    #[clap(long, requires = "config-file")]
+    profile: Option<String>,
+
+    /// Enigma model the settings must be valid for, e.g., M4
+    #[clap(long)]
+    model: Option<EnigmaModel>,
+
+    /// Reflector id, e.g., B, or a settable reflector with its position and
+    /// ring location, e.g., UKWK-B-A
+    #[clap(long)]
+    reflector: Option<String>,
+
+    /// Wheel order, left rotor first, e.g., "I II III"
+    #[clap(long)]
+    rotors: Option<String>,
+
+    /// Ring settings, left rotor first, as numbers or letters, e.g., "01 01 01"
+    #[clap(long)]
+    rings: Option<String>,
+
+    /// Rotor start positions, left rotor first, e.g., AAA
+    #[clap(long)]
+    positions: Option<String>,
+
+    /// Plugboard pairs, e.g., "AB CD". An empty string removes all plugs
+    #[clap(long)]
+    plugs: Option<String>,
+}
+
+impl MachineArgs {
+    /// Load the selected profile, exiting with a message if it can't be
+    /// used.
+    #[cfg(feature = "profiles")]
+    fn profile_config(&self) -> Option<Config> {
+        let path = self.config_file.as_ref()?;
+        let cfg = ProfileFile::load(path)
+            .and_then(|file| file.select(self.profile.as_deref())?.config())
+            .unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            });
+
+        Some(cfg)
+    }
+
+    #[cfg(not(feature = "profiles"))]
+    fn profile_config(&self) -> Option<Config> {
+        None
+    }
+
+    /// Parse and validate the configuration, exiting with a message if it
+    /// can't be used to build a machine.
+    fn session(&self) -> Session {
+        let base = match self.profile_config() {
+            Some(cfg) => Some(cfg),
+            None => self.config.as_ref().map(|config| {
+                Config::parse(config.clone()).unwrap_or_else(|| {
+                    eprintln!("Invalid enigma config provided: {}", config);
+                    std::process::exit(1);
+                })
+            }),
+        };
+
+        let overrides = ConfigOverrides {
+            reflector: self.reflector.clone(),
+            rotors: self.rotors.clone(),
+            rings: self.rings.clone(),
+            positions: self.positions.clone(),
+            plugs: self.plugs.clone(),
+        };
+        let mut cfg = overrides.apply(base).unwrap_or_else(|err| {
+            eprintln!("Invalid machine settings: {}", err);
+            std::process::exit(1);
+        });
+
+        if let Some(model) = self.model {
+            if let Err(err) = cfg.validate_model(model) {
+                eprintln!("Invalid machine settings: {}", err);
+                std::process::exit(1);
+            }
+            cfg = cfg.with_model(model);
+        }
+
+        Session::from_config(cfg).unwrap_or_else(|err| {
+            eprintln!("Invalid enigma config provided: {}", err);
+            std::process::exit(1);
+        })
+    }
+}
+
+#[derive(Args)]
+struct TranslateArgs {
+    #[clap(flatten)]
+    machine: MachineArgs,
 
     /// Input string for encoding / decoding.
     ///
     /// Defaults to stdin if this option is not provided.
     #[clap(short, long)]
     input: Option<String>,
+
+    /// Print only letters, split into groups of this many letters
+    #[clap(short, long)]
+    group: Option<usize>,
+}
+
+#[derive(Args)]
+struct KeygenArgs {
+    /// Reflectors to choose from
+    #[clap(long, multiple_values = true, default_values = &["B"])]
+    reflectors: Vec<String>,
+
+    /// Rotors to choose the wheel order from
+    #[clap(long, multiple_values = true, default_values = &["I", "II", "III", "IV", "V"])]
+    rotors: Vec<String>,
+
+    /// Number of rotors in the machine
+    #[clap(long, default_value_t = 3)]
+    rotor_count: usize,
+
+    /// Number of plugboard cables
+    #[clap(long, default_value_t = 10)]
+    plugs: usize,
+
+    /// Number of keys to generate
+    #[clap(short = 'n', long, default_value_t = 1)]
+    count: usize,
+
+    /// Seed for reproducible keys
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Output format
+    #[clap(short, long, value_enum, default_value_t = KeyFormat::Config)]
+    format: KeyFormat,
+}
+
+#[derive(Args)]
+struct CrackArgs {
+    /// Ciphertext to attack.
+    ///
+    /// Defaults to stdin if this option is not provided.
+    #[clap(short, long)]
+    input: Option<String>,
+
+    /// Known plaintext fragment
+    #[clap(long)]
+    crib: String,
+
+    /// Position of the crib within the ciphertext (letters only).
+    ///
+    /// Every offset where the crib can be placed is tried if not provided.
+    #[clap(long)]
+    offset: Option<usize>,
+
+    /// Reflectors to test
+    #[clap(long, multiple_values = true, default_values = &["B"])]
+    reflectors: Vec<String>,
+
+    /// Rotors to build wheel orders from
+    #[clap(long, multiple_values = true, default_values = &["I", "II", "III", "IV", "V"])]
+    rotors: Vec<String>,
+
+    /// Test a single wheel order instead, left rotor first, e.g., "I II III"
+    #[clap(long, conflicts_with = "rotors")]
+    wheel_order: Option<String>,
+
+    /// Most keys to report for each crib offset
+    #[clap(long, default_value_t = DEFAULT_MAX_SOLUTIONS)]
+    max_solutions: usize,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    #[clap(flatten)]
+    machine: MachineArgs,
+
+    /// Output format
+    #[clap(short, long, value_enum)]
+    to: KeyFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum KeyFormat {
+    /// Configuration string as accepted by `--config`
+    Config,
+    /// Key sheet line listing reflector, wheel order, rings, positions and plugs
+    Keysheet,
+}
+
+#[cfg(feature = "repl")]
+#[derive(Args)]
+struct ReplArgs {
+    #[clap(flatten)]
+    machine: MachineArgs,
+}
+
+#[derive(Args)]
+struct TraceArgs {
+    #[clap(flatten)]
+    machine: MachineArgs,
+
+    /// Input string to trace.
+    ///
+    /// Defaults to stdin if this option is not provided. Characters that the
+    /// machine can't translate are skipped.
+    #[clap(short, long)]
+    input: Option<String>,
+
+    /// Output format
+    #[clap(short, long, value_enum, default_value_t = TraceFormat::Table)]
+    format: TraceFormat,
+
+    /// Highlight rotor steps and lamps with terminal colours (table only)
+    #[clap(long)]
+    color: bool,
+}
+
+#[derive(Args)]
+struct KeystreamArgs {
+    #[clap(flatten)]
+    machine: MachineArgs,
+
+    /// Number of key presses
+    #[clap(short = 'n', long, default_value_t = 26)]
+    count: usize,
+
+    /// Output format
+    #[clap(short, long, value_enum, default_value_t = StreamFormat::Text)]
+    format: StreamFormat,
 }
 
-#[derive(Debug)]
-struct Config {
-    reflector_config: ReflectorConfig,
-    rotor_config: RotorConfig,
-    plugboard_config: Option<PlugBoardConfig>,
+#[derive(Args)]
+struct ScheduleArgs {
+    #[clap(flatten)]
+    machine: MachineArgs,
+
+    /// Number of key presses to list turnovers for
+    #[clap(short = 'n', long, default_value_t = 676)]
+    count: usize,
+
+    /// Also list the rotor positions around this key press
+    #[clap(short, long)]
+    around: Option<usize>,
+
+    /// Number of key presses either side of --around to list
+    #[clap(short, long, default_value_t = 5)]
+    radius: usize,
 }
 
-impl Config {
-    fn parse(config: String) -> Option<Config> {
-        let mut cfg_parts: Vec<String> = config.split(';').map(|s| s.to_string()).collect();
+#[derive(Args)]
+struct KeyspaceArgs {
+    /// Enigma model to break the count down for, e.g., M4
+    #[clap(short, long)]
+    model: Option<EnigmaModel>,
+
+    /// Number of plugboard cables [default: 10 with a plugboard]
+    #[clap(short, long)]
+    plug_pairs: Option<usize>,
 
-        if cfg_parts.len() != 2 && cfg_parts.len() != 3 {
-            eprintln!(
-                "Invalid number of config components encountered: {} {:?}",
-                cfg_parts.len(),
-                config
+    /// Leave the ring settings out, as when they are already known
+    #[clap(long)]
+    known_rings: bool,
+
+    /// Leave out ring settings that encrypt like another setting
+    #[clap(short, long)]
+    discount_equivalent: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StreamFormat {
+    /// One line of letters per key press
+    Text,
+    /// Raw bytes, one record per key press
+    Binary,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TraceFormat {
+    /// Aligned table, one row per key press
+    Table,
+    /// Comma separated values with a header row
+    Csv,
+    /// One JSON object per key press, one per line
+    Json,
+}
+
+fn input_reader(input: Option<String>) -> Box<dyn BufRead> {
+    match input {
+        Some(input) => Box::new(io::Cursor::new(input)),
+        None => Box::new(BufReader::new(io::stdin().lock())),
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD_YELLOW: &str = "\x1b[1;33m";
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_RED: &str = "\x1b[31m";
+
+/// Rotor window letters as seen from the front of the machine (left rotor
+/// first).
+fn windows(positions: &[char]) -> String {
+    positions.iter().rev().collect()
+}
+
+/// Stepping summary as seen from the front of the machine. `.` for a rotor
+/// that didn't move, `S` for a step and `D` for a double step.
+fn step_codes(steps: &[RotorStep]) -> String {
+    steps
+        .iter()
+        .rev()
+        .map(|step| match step {
+            RotorStep::Stationary => '.',
+            RotorStep::Stepped => 'S',
+            RotorStep::DoubleStepped => 'D',
+        })
+        .collect()
+}
+
+fn colored_windows(trace: &SignalTrace) -> String {
+    trace
+        .positions_after
+        .iter()
+        .zip(trace.steps.iter())
+        .rev()
+        .map(|(pos, step)| match step {
+            RotorStep::Stationary => pos.to_string(),
+            RotorStep::Stepped => format!("{}{}{}", ANSI_GREEN, pos, ANSI_RESET),
+            RotorStep::DoubleStepped => format!("{}{}{}", ANSI_RED, pos, ANSI_RESET),
+        })
+        .collect()
+}
+
+fn trace_header(format: TraceFormat, rotor_ids: &[String]) -> Option<String> {
+    let forward: Vec<String> = rotor_ids.to_vec();
+    let backward: Vec<String> = rotor_ids.iter().rev().cloned().collect();
+
+    match format {
+        TraceFormat::Table => {
+            let mut header = format!(
+                "{:>4}  {:<3}  {:<w$}  {:<w$}  {:<4}  {:<4}",
+                "#",
+                "Key",
+                "Before",
+                "After",
+                "Plug",
+                "ETW",
+                w = rotor_ids.len().max(6)
             );
-            return None;
+            for id in forward
+                .iter()
+                .chain(["UKW".to_string()].iter())
+                .chain(backward.iter())
+                .chain(["ETW".to_string()].iter())
+            {
+                header.push_str(&format!("  {:<4}", id));
+            }
+            header.push_str("  Lamp");
+            Some(header)
         }
+        TraceFormat::Csv => {
+            let mut header =
+                "index,input,positions_before,positions_after,steps,plugboard_in,entry_in"
+                    .to_string();
+            for id in &forward {
+                header.push_str(&format!(",forward_{}", id));
+            }
+            header.push_str(",reflector");
+            for id in &backward {
+                header.push_str(&format!(",backward_{}", id));
+            }
+            header.push_str(",entry_out,output");
+            Some(header)
+        }
+        TraceFormat::Json => None,
+    }
+}
 
-        let reflector_config = ReflectorConfig::parse(&mut cfg_parts[0]);
-        let rotor_config = RotorConfig::parse(&mut cfg_parts[1]);
-        let plugboard_config = if cfg_parts.len() == 3 {
-            PlugBoardConfig::parse(&mut cfg_parts[2])
-        } else {
-            None
-        };
+fn trace_row(format: TraceFormat, index: usize, trace: &SignalTrace, color: bool) -> String {
+    let before = windows(&trace.positions_before);
+    let after = windows(&trace.positions_after);
 
-        if reflector_config.is_none() || rotor_config.is_none() {
-            eprintln!(
-                "Missing required config component. Refl: {:?} Rotor: {:?}",
-                reflector_config, rotor_config
+    match format {
+        TraceFormat::Table => {
+            let width = trace.positions_after.len().max(6);
+            let after = if color {
+                // Pad manually as the escape codes throw off the formatter.
+                format!(
+                    "{}{}",
+                    colored_windows(trace),
+                    " ".repeat(width - after.len())
+                )
+            } else {
+                format!("{:<w$}", after, w = width)
+            };
+            let mut row = format!(
+                "{:>4}  {:<3}  {:<w$}  {}  {:<4}  {:<4}",
+                index,
+                trace.input,
+                before,
+                after,
+                trace.plugboard_in,
+                trace.entry_in,
+                w = width
             );
-            return None;
+            for c in trace
+                .forward
+                .iter()
+                .chain([trace.reflector].iter())
+                .chain(trace.backward.iter())
+                .chain([trace.entry_out].iter())
+            {
+                row.push_str(&format!("  {:<4}", c));
+            }
+            if color {
+                row.push_str(&format!(
+                    "  {}{}{}",
+                    ANSI_BOLD_YELLOW, trace.output, ANSI_RESET
+                ));
+            } else {
+                row.push_str(&format!("  {}", trace.output));
+            }
+            row
+        }
+        TraceFormat::Csv => {
+            let mut row = format!(
+                "{},{},{},{},{},{},{}",
+                index,
+                trace.input,
+                before,
+                after,
+                step_codes(&trace.steps),
+                trace.plugboard_in,
+                trace.entry_in
+            );
+            for c in trace
+                .forward
+                .iter()
+                .chain([trace.reflector].iter())
+                .chain(trace.backward.iter())
+                .chain([trace.entry_out].iter())
+            {
+                row.push_str(&format!(",{}", c));
+            }
+            row.push_str(&format!(",{}", trace.output));
+            row
+        }
+        TraceFormat::Json => {
+            let letters = |chars: &[char]| -> String {
+                let quoted: Vec<String> = chars.iter().map(|c| format!("\"{}\"", c)).collect();
+                format!("[{}]", quoted.join(","))
+            };
+            format!(
+                "{{\"index\":{},\"input\":\"{}\",\"positions_before\":\"{}\",\"positions_after\":\"{}\",\"steps\":\"{}\",\"plugboard_in\":\"{}\",\"entry_in\":\"{}\",\"forward\":{},\"reflector\":\"{}\",\"backward\":{},\"entry_out\":\"{}\",\"output\":\"{}\"}}",
+                index,
+                trace.input,
+                before,
+                after,
+                step_codes(&trace.steps),
+                trace.plugboard_in,
+                trace.entry_in,
+                letters(&trace.forward),
+                trace.reflector,
+                letters(&trace.backward),
+                trace.entry_out,
+                trace.output
+            )
         }
-
-        Some(Config {
-            reflector_config: reflector_config.unwrap(),
-            rotor_config: rotor_config.unwrap(),
-            plugboard_config,
-        })
     }
 }
 
-#[derive(Debug)]
-struct ReflectorConfig {
-    reflector_id: String,
-}
+fn run_trace(args: TraceArgs) {
+    let cfg = args.machine.session().config();
+    let rotor_ids: Vec<String> = cfg
+        .rotor_config
+        .rotor_ids
+        .iter()
+        .map(|(id, _, _)| id.clone())
+        .collect();
+    let mut em = cfg.build_machine();
 
-impl ReflectorConfig {
-    fn parse(config: &mut String) -> Option<ReflectorConfig> {
-        config.retain(|c| !c.is_whitespace());
+    if let Some(header) = trace_header(args.format, &rotor_ids) {
+        println!("{}", header);
+    }
+
+    let mut index = 0;
+    for l in input_reader(args.input).lines() {
+        let in_line = match l {
+            Ok(in_line) => in_line,
+            Err(_) => break,
+        };
 
-        // TODO: This should be something supported in the library
-        if config == "A" || config == "B" || config == "C" {
-            Some(ReflectorConfig {
-                reflector_id: config.clone(),
-            })
-        } else {
-            eprintln!("Invalid reflector id: {}", config);
-            None
+        for c in in_line.chars() {
+            if let Some(trace) = em.translate_traced(c) {
+                index += 1;
+                println!("{}", trace_row(args.format, index, &trace, args.color));
+            }
         }
     }
 }
 
-#[derive(Debug)]
-struct RotorConfig {
-    rotor_ids: Vec<(String, u8, u8)>,
+fn run_keystream(args: KeystreamArgs) {
+    let em = args.machine.session().config().build_machine();
+    let format = match args.format {
+        StreamFormat::Text => KeystreamFormat::Text,
+        StreamFormat::Binary => KeystreamFormat::Binary,
+    };
+
+    if let Err(err) = em.keystream(args.count).write(format, io::stdout().lock()) {
+        eprintln!("Failed to write the keystream: {}", err);
+        std::process::exit(1);
+    }
 }
 
-impl RotorConfig {
-    fn parse(config: &mut String) -> Option<RotorConfig> {
-        config.retain(|c| !c.is_whitespace() && c != '(' && c != ')');
+fn run_schedule(args: ScheduleArgs) {
+    let em = args.machine.session().config().build_machine();
+    let schedule = StepSchedule::from_machine(&em);
 
-        //TODO: This should be something supported in the library
+    let period = schedule.period();
+    println!("Period: {} key presses", period.length);
+    println!("Lead-in: {} key presses", period.lead_in);
 
-        let rotor_cfgs: Vec<String> = config.split(',').map(|s| s.to_string()).rev().collect();
+    println!();
+    println!("Turnovers in the first {} key presses:", args.count);
+    for turnover in schedule.turnovers(args.count) {
+        let step = match turnover.step {
+            RotorStep::DoubleStepped => "  double step",
+            _ => "",
+        };
+        println!(
+            "{:>8}  {:<7} {} -> {}{}",
+            turnover.key_press, turnover.rotor_id, turnover.from, turnover.to, step
+        );
+    }
 
-        if rotor_cfgs.len() != 3 && rotor_cfgs.len() != 4 {
-            eprintln!("Invalid rotor config count: {:?}", rotor_cfgs);
-            return None;
+    if let Some(key_press) = args.around {
+        println!();
+        println!("Positions around key press {}:", key_press);
+        for entry in schedule.positions_around(key_press, args.radius) {
+            let marker = if entry.key_press == key_press {
+                "  <"
+            } else {
+                ""
+            };
+            println!(
+                "{:>8}  {}{}",
+                entry.key_press,
+                windows(&entry.positions),
+                marker
+            );
         }
+    }
+}
 
-        let mut rotor_ids: Vec<(String, u8, u8)> = Vec::new();
-        for rotor_cfg in rotor_cfgs {
-            let cfg_elems: Vec<&str> = rotor_cfg.split('-').collect();
-            if cfg_elems.len() != 3 {
-                eprintln!("Invalid rotor config: {}", rotor_cfg);
-                return None;
+fn run_keyspace(args: KeyspaceArgs) {
+    let keyspace = |model: EnigmaModel| {
+        let builder = Keyspace::builder(model)
+            .ring_settings(!args.known_rings)
+            .discount_equivalent(args.discount_equivalent);
+        let builder = match args.plug_pairs {
+            Some(pairs) if model.has_plugboard() || args.model.is_some() => {
+                builder.plug_pairs(pairs)
             }
+            _ => builder,
+        };
 
-            if cfg_elems[0] != "I"
-                && cfg_elems[0] != "II"
-                && cfg_elems[0] != "III"
-                && cfg_elems[0] != "IV"
-                && cfg_elems[0] != "V"
-                && cfg_elems[0] != "VI"
-                && cfg_elems[0] != "VII"
-                && cfg_elems[0] != "VIII"
-            {
-                eprintln!("Invalid rotor id: {}", cfg_elems[0]);
-                return None;
+        builder.try_build().unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+    };
+    let row = |name: &str, count: &BigUint| {
+        println!(
+            "{:<14} {:>30} {:>7.2} bits",
+            name,
+            count.to_string(),
+            keyspace::bits(count)
+        );
+    };
+
+    match args.model {
+        Some(model) => {
+            let keyspace = keyspace(model);
+            row("Wheel orders", keyspace.wheel_orders());
+            row("Reflectors", keyspace.reflectors());
+            row("Ring settings", keyspace.ring_settings());
+            row("Positions", keyspace.positions());
+            row(
+                &format!("Plugboard ({})", keyspace.plug_pairs()),
+                keyspace.plugboard(),
+            );
+            row("Total", &keyspace.total());
+        }
+        None => {
+            for model in EnigmaModel::ALL {
+                row(model.name(), &keyspace(model).total());
             }
+        }
+    }
+}
+
+#[cfg(feature = "repl")]
+const REPL_HELP: &str = "\
+Commands:
+  :pos ABC           Set the rotor positions (left rotor first)
+  :ring 01 02 03     Set the ring settings, as numbers or letters (left rotor first)
+  :plug add AB       Connect two letters on the plugboard
+  :plug remove AB    Remove a plugboard cable
+  :reset             Return to the starting settings
+  :undo              Undo the last translation or change
+  :state             Show the current settings as a configuration string
+  :help              Show this help
+  :quit              Leave the session
+Anything else is translated.";
 
-            let pos: char = cfg_elems[1].to_ascii_uppercase().chars().next().unwrap();
-            if !pos.is_ascii_alphabetic() {
-                eprintln!("Invalid rotor position: {}", pos);
-                return None;
+/// Run one REPL line. Returns `Ok(None)` when the session should end.
+#[cfg(feature = "repl")]
+fn repl_line(session: &mut Session, line: &str) -> Result<Option<String>, String> {
+    let line = line.trim();
+    if !line.starts_with(':') {
+        return Ok(Some(session.translate_text(line)));
+    }
+
+    let args: Vec<&str> = line[1..].split_whitespace().collect();
+    match args.as_slice() {
+        ["pos", positions] => {
+            let positions = parse_positions(positions, session.alphabet())?;
+            session.set_positions(&positions)?;
+        }
+        ["ring", rings @ ..] => {
+            let rings = parse_rings(&rings.join(" "), session.alphabet())?;
+            session.set_rings(&rings)?;
+        }
+        ["plug", "add", plug] => {
+            let (in1, in2) = parse_plug(plug)?;
+            session.add_plug(in1, in2)?;
+        }
+        ["plug", "remove", plug] => {
+            let (in1, in2) = parse_plug(plug)?;
+            session.remove_plug(in1, in2)?;
+        }
+        ["reset"] => session.reset(),
+        ["undo"] => {
+            if !session.undo() {
+                return Err("Nothing to undo".to_string());
             }
+        }
+        ["state"] => return Ok(Some(session.config_string())),
+        ["help"] => return Ok(Some(REPL_HELP.to_string())),
+        ["quit"] | ["q"] => return Ok(None),
+        _ => return Err(format!("Unknown command: {}. See :help", line)),
+    }
+
+    Ok(Some(String::new()))
+}
 
-            let ring_loc: char = cfg_elems[1].to_ascii_uppercase().chars().next().unwrap();
-            if !ring_loc.is_ascii_alphabetic() {
-                eprintln!("Invalid rotor ring location: {}", ring_loc);
-                return None;
+#[cfg(feature = "repl")]
+fn run_repl(args: ReplArgs) {
+    let mut session = args.machine.session();
+    let mut editor = DefaultEditor::new().expect("Unable to initialize the line editor");
+
+    loop {
+        let line = match editor.readline("enigma> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{}", err);
+                break;
             }
+        };
 
-            rotor_ids.push((
-                cfg_elems[0].to_string(),
-                pos as u8 - b'A',
-                ring_loc as u8 - b'A',
-            ))
+        if line.trim().is_empty() {
+            continue;
         }
+        let _ = editor.add_history_entry(line.as_str());
 
-        Some(RotorConfig { rotor_ids })
+        match repl_line(&mut session, &line) {
+            Ok(Some(output)) if output.is_empty() => {}
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => break,
+            Err(err) => eprintln!("{}", err),
+        }
     }
 }
 
-#[derive(Debug)]
-struct PlugBoardConfig {
-    plugboard_maps: Vec<(char, char)>,
+/// Split the letters of `text` into space separated groups of `size`.
+fn group_letters(text: &str, size: usize) -> String {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    letters
+        .chunks(size.max(1))
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
-impl PlugBoardConfig {
-    fn parse(config: &mut String) -> Option<PlugBoardConfig> {
-        config.retain(|c| !c.is_whitespace() && c != '(' && c != ')');
-
-        // TODO: This should be something supported in the library
-        let pb_maps: Vec<String> = config.split(',').map(|s| s.to_string()).collect();
+fn run_translate(args: TranslateArgs) {
+    let mut em = args.machine.session().config().build_machine();
 
-        let mut plugboard_maps: Vec<(char, char)> = Vec::new();
-        for map in pb_maps {
-            let chs: Vec<char> = map.to_ascii_uppercase().chars().collect();
-            if chs.len() != 3 {
-                eprintln!("Invalid plugboard config received: {}", map);
-                return None;
-            }
-
-            if !chs[0].is_ascii_alphabetic() || !chs[2].is_ascii_alphabetic() {
-                eprintln!("Plugboard config contains non alphabetic chars: {}", map);
-                return None;
-            }
+    for l in input_reader(args.input).lines() {
+        let in_line = match l {
+            Ok(in_line) => in_line,
+            Err(_) => break,
+        };
 
-            plugboard_maps.push((chs[0], chs[2]));
+        let out_line = em.translate_text(in_line.chars());
+        match args.group {
+            Some(size) => println!("{}", group_letters(&out_line, size)),
+            None => println!("{}", out_line),
         }
-
-        Some(PlugBoardConfig { plugboard_maps })
     }
 }
 
-fn main() {
-    let cli = Cli::parse();
-    let cfg = Config::parse(cli.config).expect("Invalid enigma config provided");
+/// Format a key as a key sheet line, rotors listed left to right.
+fn keysheet(cfg: &Config) -> String {
+    let alphabet = cfg.alphabet();
+    let rotors = cfg.rotor_config.rotor_ids.iter().rev();
+    let wheels: Vec<&str> = rotors.clone().map(|(id, _, _)| id.as_str()).collect();
+    let rings: Vec<String> = rotors
+        .clone()
+        .map(|(_, _, ring)| format!("{:02}", ring + 1))
+        .collect();
+    let positions: Vec<String> = rotors
+        .map(|(_, pos, _)| alphabet.letter(*pos).to_string())
+        .collect();
+    let plugs: Vec<String> = cfg
+        .plugboard_config
+        .iter()
+        .flat_map(|pb| pb.plugboard_maps.iter())
+        .map(|(a, b)| format!("{}{}", a.to_ascii_uppercase(), b.to_ascii_uppercase()))
+        .collect();
+    let plugs = if plugs.is_empty() {
+        "-".to_string()
+    } else {
+        plugs.join(" ")
+    };
 
-    let input: Box<dyn BufRead> = if cli.input.is_none() {
-        Box::new(BufReader::new(io::stdin().lock()))
+    // A settable reflector is set like a rotor
+    let reflector = &cfg.reflector_config;
+    let ukw = if Reflector::new(&reflector.reflector_id).is_settable() {
+        format!(
+            "{} {:02} {}",
+            reflector.reflector_id,
+            reflector.ring + 1,
+            alphabet.letter(reflector.position)
+        )
     } else {
-        Box::new(io::Cursor::new(cli.input.unwrap()))
+        reflector.reflector_id.clone()
     };
 
-    let builder = EnigmaMachine::builder();
-    let builder = builder
-        .reflector(&cfg.reflector_config.reflector_id)
-        .rotors(cfg.rotor_config.rotor_ids);
+    // The Uhr cables are listed in order, each with its `a` plug first
+    let stecker = match &cfg.uhr_config {
+        Some(uhr) => format!("Uhr {:02} | Uhrkabel {}", uhr.position, plugs),
+        None => format!("Stecker {}", plugs),
+    };
 
-    // TODO: This is a friggen hideous way to need to handle this ...
-    let builder = match cfg.plugboard_config {
-        Some(pbcfg) => builder.plugboard(pbcfg.plugboard_maps),
-        None => builder,
+    format!(
+        "UKW {} | Walzenlage {} | Ringstellung {} | Grundstellung {} | {}",
+        ukw,
+        wheels.join(" "),
+        rings.join(" "),
+        positions.join(" "),
+        stecker
+    )
+}
+
+fn format_key(cfg: &Config, format: KeyFormat) -> String {
+    match format {
+        KeyFormat::Config => cfg.to_string(),
+        KeyFormat::Keysheet => keysheet(cfg),
+    }
+}
+
+fn run_keygen(args: KeygenArgs) {
+    let keygen = KeyGenerator::new()
+        .reflectors(args.reflectors)
+        .rotors(args.rotors)
+        .rotor_count(args.rotor_count)
+        .plug_count(args.plugs);
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
     };
 
-    let mut em = builder.build().unwrap();
+    for _ in 0..args.count {
+        match keygen.generate(&mut rng) {
+            Ok(cfg) => println!("{}", format_key(&cfg, args.format)),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn run_crack(args: CrackArgs) {
+    let ciphertext: String = input_reader(args.input)
+        .lines()
+        .map_while(Result::ok)
+        .collect::<Vec<_>>()
+        .join("");
+
+    let wheel_orders: Vec<Vec<String>> = match args.wheel_order {
+        Some(order) => vec![order.split_whitespace().rev().map(String::from).collect()],
+        None => {
+            let mut orders = Vec::new();
+            for left in &args.rotors {
+                for middle in &args.rotors {
+                    for right in &args.rotors {
+                        if left != middle && middle != right && left != right {
+                            orders.push(vec![right.clone(), middle.clone(), left.clone()]);
+                        }
+                    }
+                }
+            }
+            orders
+        }
+    };
+
+    let offsets = match args.offset {
+        Some(offset) => vec![offset],
+        None => possible_offsets(&ciphertext, &args.crib),
+    };
+
+    // Check the crib and every reflector and rotor before searching
+    let attacks = offsets
+        .into_iter()
+        .map(|offset| {
+            CribAttack::new(&ciphertext, &args.crib, offset)
+                .and_then(|attack| attack.reflectors(args.reflectors.clone()))
+                .and_then(|attack| attack.wheel_orders(wheel_orders.clone()))
+                .map(|attack| (offset, attack.max_solutions(args.max_solutions)))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+    if attacks.is_empty() {
+        eprintln!("The crib can't be placed anywhere in the ciphertext");
+        std::process::exit(1);
+    }
 
-    for l in input.lines() {
-        match l {
-            Ok(in_line) => println!("{}", em.translate_text(in_line.chars())),
-            Err(_) => println!("Done"),
+    let mut found = false;
+    for (offset, attack) in attacks {
+        for solution in attack.solve() {
+            found = true;
+            println!("offset {}: {}", offset, solution.config());
+            if !solution.unknown.is_empty() {
+                let unknown: String = solution.unknown.iter().collect();
+                println!("unknown steckers: {}", unknown);
+            }
+            println!("{}", solution.plaintext);
         }
     }
+
+    if !found {
+        eprintln!("No key found");
+        std::process::exit(1);
+    }
+}
+
+fn run_check(args: MachineArgs) {
+    println!("{}", args.session().config_string());
+}
+
+fn run_convert(args: ConvertArgs) {
+    let cfg = args.machine.session().config();
+    println!("{}", format_key(&cfg, args.to));
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Encrypt(args)) | Some(Command::Decrypt(args)) => run_translate(args),
+        Some(Command::Keygen(args)) => run_keygen(args),
+        Some(Command::Crack(args)) => run_crack(args),
+        Some(Command::Check(args)) => run_check(args),
+        Some(Command::Convert(args)) => run_convert(args),
+        Some(Command::Trace(args)) => run_trace(args),
+        Some(Command::Keystream(args)) => run_keystream(args),
+        Some(Command::Schedule(args)) => run_schedule(args),
+        Some(Command::Keyspace(args)) => run_keyspace(args),
+        #[cfg(feature = "repl")]
+        Some(Command::Repl(args)) => run_repl(args),
+        None => run_translate(TranslateArgs {
+            machine: cli.machine,
+            input: cli.input,
+            group: None,
+        }),
+    }
 }
@@ -0,0 +1,384 @@
+//! Enigma model catalogue module.
+//!
+//! [EnigmaModel](EnigmaModel) describes the machines that were actually
+//! built: which rotors and reflectors they shipped with, how many rotors sit
+//! in the machine, whether there is a plugboard, how the entry wheel is wired
+//! and how the rotors are stepped. Passing a model to
+//! [EnigmaMachineBuilder::model](crate::machine::EnigmaMachineBuilder::model)
+//! rejects settings that could not have existed on that machine.
+//!
+//! ```
+//! use enigma::machine::EnigmaMachine;
+//! use enigma::model::EnigmaModel;
+//!
+//! let em = EnigmaMachine::builder()
+//!     .model(EnigmaModel::M4)
+//!     .reflector("B-thin")
+//!     .rotors(vec![
+//!         ("III".to_string(), 0, 0),
+//!         ("II".to_string(), 0, 0),
+//!         ("I".to_string(), 0, 0),
+//!         ("Beta".to_string(), 0, 0),
+//!     ])
+//!     .try_build();
+//! assert!(em.is_ok());
+//!
+//! // The Enigma I only held three rotors
+//! let em = EnigmaMachine::builder()
+//!     .model(EnigmaModel::I)
+//!     .reflector("B")
+//!     .rotors(vec![
+//!         ("III".to_string(), 0, 0),
+//!         ("II".to_string(), 0, 0),
+//!         ("I".to_string(), 0, 0),
+//!         ("Beta".to_string(), 0, 0),
+//!     ])
+//!     .try_build();
+//! assert!(em.is_err());
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+use crate::entry_wheel::{ETW_IDENTITY, ETW_QWERTZ, ETW_T, ETW_Z};
+use crate::stepping::Stepping;
+
+const M3_ROTORS: [&str; 8] = ["I", "II", "III", "IV", "V", "VI", "VII", "VIII"];
+
+/// Enigma models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EnigmaModel {
+    /// Enigma I, the Wehrmacht and Luftwaffe machine
+    I,
+    /// Enigma M3, the Kriegsmarine three rotor machine
+    M3,
+    /// Enigma M4, the Kriegsmarine four rotor machine with a thin reflector
+    M4,
+    /// Enigma K, the commercial machine of 1927
+    K,
+    /// Enigma G-31, the Abwehr cog-wheel machine
+    G,
+    /// Enigma G-312, the Abwehr machine captured by Bletchley Park
+    G312,
+    /// Enigma D, the commercial machine of 1926
+    D,
+    /// Enigma T (Tirpitz), built for the Japanese navy
+    T,
+    /// Swiss-K, the Enigma K rewired for the Swiss army
+    SwissK,
+    /// Railway (Rocket) Enigma, the Enigma K rewired for the Reichsbahn
+    Railway,
+    /// Enigma Z (Z30), the numeric machine with ten digit keys
+    Z,
+}
+
+impl EnigmaModel {
+    /// Every model in the catalogue.
+    pub const ALL: [EnigmaModel; 11] = [
+        EnigmaModel::I,
+        EnigmaModel::M3,
+        EnigmaModel::M4,
+        EnigmaModel::K,
+        EnigmaModel::G,
+        EnigmaModel::G312,
+        EnigmaModel::D,
+        EnigmaModel::T,
+        EnigmaModel::SwissK,
+        EnigmaModel::Railway,
+        EnigmaModel::Z,
+    ];
+
+    /// The model name, e.g., `M4`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnigmaModel::I => "I",
+            EnigmaModel::M3 => "M3",
+            EnigmaModel::M4 => "M4",
+            EnigmaModel::K => "K",
+            EnigmaModel::G => "G",
+            EnigmaModel::G312 => "G-312",
+            EnigmaModel::D => "D",
+            EnigmaModel::T => "T",
+            EnigmaModel::SwissK => "Swiss-K",
+            EnigmaModel::Railway => "Railway",
+            EnigmaModel::Z => "Z",
+        }
+    }
+
+    /// Rotors that can be placed in the stepping positions.
+    pub fn rotors(&self) -> &'static [&'static str] {
+        match self {
+            EnigmaModel::I => &["I", "II", "III", "IV", "V"],
+            EnigmaModel::M3 | EnigmaModel::M4 => &M3_ROTORS,
+            EnigmaModel::K | EnigmaModel::D => &["IK", "IIK", "IIIK"],
+            EnigmaModel::G => &["IG", "IIG", "IIIG"],
+            EnigmaModel::G312 => &["IG312", "IIG312", "IIIG312"],
+            EnigmaModel::T => &["IT", "IIT", "IIIT", "IVT", "VT", "VIT", "VIIT", "VIIIT"],
+            EnigmaModel::SwissK => &["ISK", "IISK", "IIISK"],
+            EnigmaModel::Railway => &["IR", "IIR", "IIIR"],
+            EnigmaModel::Z => &["IZ", "IIZ", "IIIZ"],
+        }
+    }
+
+    /// Rotors that can be placed in the fourth, non stepping, position.
+    pub fn greek_rotors(&self) -> &'static [&'static str] {
+        match self {
+            EnigmaModel::M4 => &["Beta", "Gamma"],
+            _ => &[],
+        }
+    }
+
+    /// Reflectors the model shipped with.
+    pub fn reflectors(&self) -> &'static [&'static str] {
+        match self {
+            EnigmaModel::I => &["A", "B", "C"],
+            EnigmaModel::M3 => &["B", "C"],
+            EnigmaModel::M4 => &["B-thin", "C-thin"],
+            EnigmaModel::K | EnigmaModel::D | EnigmaModel::SwissK => &["UKWK"],
+            EnigmaModel::G => &["UKWG"],
+            EnigmaModel::G312 => &["UKWG312"],
+            EnigmaModel::T => &["UKWT"],
+            EnigmaModel::Railway => &["UKWR"],
+            EnigmaModel::Z => &["UKWZ"],
+        }
+    }
+
+    /// Number of rotors in the machine, not counting the reflector.
+    pub fn rotor_count(&self) -> usize {
+        match self {
+            EnigmaModel::M4 => 4,
+            _ => 3,
+        }
+    }
+
+    /// Whether the model has a plugboard.
+    pub fn has_plugboard(&self) -> bool {
+        matches!(self, EnigmaModel::I | EnigmaModel::M3 | EnigmaModel::M4)
+    }
+
+    /// The entry wheel wiring, listing the key wired to each contact. See
+    /// [EntryWheel](crate::entry_wheel::EntryWheel).
+    pub fn entry_wheel(&self) -> &'static str {
+        match self {
+            EnigmaModel::I | EnigmaModel::M3 | EnigmaModel::M4 => ETW_IDENTITY,
+            EnigmaModel::K
+            | EnigmaModel::G
+            | EnigmaModel::G312
+            | EnigmaModel::D
+            | EnigmaModel::SwissK
+            | EnigmaModel::Railway => ETW_QWERTZ,
+            EnigmaModel::T => ETW_T,
+            EnigmaModel::Z => ETW_Z,
+        }
+    }
+
+    /// The keys of the machine, `A` - `Z` for every model but the Enigma Z.
+    pub fn alphabet(&self) -> Alphabet {
+        match self {
+            EnigmaModel::Z => Alphabet::z(),
+            _ => Alphabet::latin(),
+        }
+    }
+
+    /// The first model in the catalogue that shipped with the reflector.
+    ///
+    /// Used to pick the entry wheel when a machine is built without a
+    /// model.
+    pub fn for_reflector(reflector_id: &str) -> Option<EnigmaModel> {
+        EnigmaModel::ALL
+            .into_iter()
+            .find(|model| model.reflectors().contains(&reflector_id))
+    }
+
+    /// How the rotors are stepped.
+    pub fn stepping(&self) -> Stepping {
+        match self {
+            EnigmaModel::M4 => Stepping::FixedGreek,
+            EnigmaModel::G | EnigmaModel::G312 => Stepping::Gear,
+            _ => Stepping::Lever,
+        }
+    }
+
+    /// Check that a machine with the given components could have existed as
+    /// this model.
+    ///
+    /// * `reflector_id` - The reflector id
+    /// * `rotor_ids` - Rotor ids, right (fast) rotor first
+    /// * `has_plugs` - Whether any plugboard cables are connected
+    pub fn validate(
+        &self,
+        reflector_id: &str,
+        rotor_ids: &[&str],
+        has_plugs: bool,
+    ) -> Result<(), String> {
+        if !self.reflectors().contains(&reflector_id) {
+            return Err(format!(
+                "Reflector {} isn't available on the Enigma {}. Expected one of {}",
+                reflector_id,
+                self.name(),
+                self.reflectors().join(", ")
+            ));
+        }
+
+        if rotor_ids.len() != self.rotor_count() {
+            return Err(format!(
+                "The Enigma {} holds {} rotors. Received {}",
+                self.name(),
+                self.rotor_count(),
+                rotor_ids.len()
+            ));
+        }
+
+        for (i, id) in rotor_ids.iter().enumerate() {
+            let allowed = if i < 3 {
+                self.rotors()
+            } else {
+                self.greek_rotors()
+            };
+
+            if !allowed.contains(id) {
+                return Err(format!(
+                    "Rotor {} can't be placed in position {} of the Enigma {}. Expected one of {}",
+                    id,
+                    i + 1,
+                    self.name(),
+                    allowed.join(", ")
+                ));
+            }
+
+            if rotor_ids[..i].contains(id) {
+                return Err(format!(
+                    "Rotor {} is used more than once. Each Enigma {} rotor is unique",
+                    id,
+                    self.name()
+                ));
+            }
+        }
+
+        if has_plugs && !self.has_plugboard() {
+            return Err(format!("The Enigma {} has no plugboard", self.name()));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for EnigmaModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for EnigmaModel {
+    type Err = String;
+
+    /// Parse a model name, e.g., `M4` or `Enigma M4`, ignoring case.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let trimmed = name.trim();
+        let short = match trimmed.get(..7) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("enigma ") => &trimmed[7..],
+            _ => trimmed,
+        };
+
+        EnigmaModel::ALL
+            .iter()
+            .find(|model| model.name().eq_ignore_ascii_case(short.trim()))
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = EnigmaModel::ALL.iter().map(|m| m.name()).collect();
+                format!(
+                    "Unknown Enigma model: {}. Expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model_names() {
+        assert_eq!("M4".parse::<EnigmaModel>().unwrap(), EnigmaModel::M4);
+        assert_eq!("enigma i".parse::<EnigmaModel>().unwrap(), EnigmaModel::I);
+        assert_eq!(" Enigma G ".parse::<EnigmaModel>().unwrap(), EnigmaModel::G);
+        assert_eq!(
+            "swiss-k".parse::<EnigmaModel>().unwrap(),
+            EnigmaModel::SwissK
+        );
+        assert!("M5".parse::<EnigmaModel>().is_err());
+
+        for model in EnigmaModel::ALL {
+            assert_eq!(model.to_string().parse::<EnigmaModel>().unwrap(), model);
+        }
+    }
+
+    #[test]
+    fn test_validate_enigma_i() {
+        let model = EnigmaModel::I;
+        assert!(model.validate("B", &["III", "II", "I"], true).is_ok());
+
+        // Rotors VI - VIII were Kriegsmarine only
+        assert!(model.validate("B", &["VI", "II", "I"], true).is_err());
+        assert!(model.validate("B-thin", &["III", "II", "I"], true).is_err());
+        assert!(model.validate("B", &["III", "II", "II"], true).is_err());
+        assert!(model.validate("B", &["III", "II"], true).is_err());
+    }
+
+    #[test]
+    fn test_validate_m4() {
+        let model = EnigmaModel::M4;
+        assert!(model
+            .validate("C-thin", &["VIII", "VI", "I", "Gamma"], true)
+            .is_ok());
+
+        // The greek rotor only fits in the fourth position
+        assert!(model
+            .validate("B-thin", &["Beta", "VI", "I", "II"], true)
+            .is_err());
+        assert!(model
+            .validate("B-thin", &["III", "VI", "I", "IV"], true)
+            .is_err());
+        assert!(model
+            .validate("B", &["III", "VI", "I", "Beta"], true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_commercial_models_have_no_plugboard() {
+        for model in [
+            EnigmaModel::K,
+            EnigmaModel::D,
+            EnigmaModel::G,
+            EnigmaModel::G312,
+            EnigmaModel::SwissK,
+            EnigmaModel::Railway,
+        ] {
+            assert!(!model.has_plugboard());
+            assert_eq!(model.entry_wheel(), ETW_QWERTZ);
+            let rotors: Vec<&str> = model.rotors().to_vec();
+            let reflector = model.reflectors()[0];
+            assert!(model.validate(reflector, &rotors, false).is_ok());
+            assert!(model.validate(reflector, &rotors, true).is_err());
+        }
+        assert_eq!(EnigmaModel::G.stepping(), Stepping::Gear);
+        assert_eq!(EnigmaModel::M4.stepping(), Stepping::FixedGreek);
+    }
+
+    #[test]
+    fn test_enigma_z() {
+        let model = EnigmaModel::Z;
+        assert_eq!(model.alphabet().len(), 10);
+        assert_eq!(EnigmaModel::for_reflector("UKWZ"), Some(model));
+        assert!(model
+            .validate("UKWZ", &["IIIZ", "IIZ", "IZ"], false)
+            .is_ok());
+        assert!(model.validate("UKWZ", &["III", "II", "I"], false).is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Enigma permutation module.
+//!
+//! At any one rotor position an Enigma is just a substitution: a
+//! [Permutation](Permutation) of the alphabet. Looking at the machine this way
+//! is how Rejewski broke it. The permutations of two key presses compose into
+//! a product whose cycle structure doesn't depend on the plugboard, and the
+//! cycle structure of a permutation is exactly its conjugacy class.
+//!
+//! [EnigmaMachine::permutation](crate::machine::EnigmaMachine::permutation)
+//! returns the permutation of the machine at its current rotor positions.
+//!
+//! ```
+//! use enigma::alphabet::Alphabet;
+//! use enigma::permutation::Permutation;
+//!
+//! let p = Permutation::from_letters("BADCEFGHIJKLMNOPQRSTUVWXYZ").unwrap();
+//! assert!(p.is_involution());
+//! assert_eq!(p.fixed_points().len(), 22);
+//! assert_eq!(p.compose(&p), Permutation::identity(26));
+//! assert_eq!(p.cycle_notation(&Alphabet::latin()), "(AB)(CD)");
+//! ```
+use std::fmt;
+
+use crate::alphabet::Alphabet;
+
+/// A permutation of the contacts `0` up to its size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Permutation {
+    map: Vec<u8>,
+}
+
+impl Permutation {
+    /// Create a permutation from the image of each contact.
+    ///
+    /// * `map` - The contact each contact is sent to. Each contact must
+    ///   appear exactly once.
+    pub fn new(map: Vec<u8>) -> Result<Permutation, String> {
+        if map.len() > 256 {
+            return Err(format!(
+                "Invalid permutation: expected at most 256 contacts, received {}",
+                map.len()
+            ));
+        }
+
+        let mut seen = vec![false; map.len()];
+        for &val in map.iter() {
+            match seen.get_mut(val as usize) {
+                Some(seen) if !*seen => *seen = true,
+                Some(_) => {
+                    return Err(format!(
+                        "Invalid permutation: {} appears more than once",
+                        val
+                    ))
+                }
+                None => {
+                    return Err(format!(
+                        "Invalid permutation: {} is out of range for {} contacts",
+                        val,
+                        map.len()
+                    ))
+                }
+            }
+        }
+
+        Ok(Permutation { map })
+    }
+
+    /// Create a permutation of `A` - `Z` from the letter each letter is sent
+    /// to. Case is ignored.
+    pub fn from_letters(letters: &str) -> Result<Permutation, String> {
+        let mut map = Vec::with_capacity(26);
+        for letter in letters.trim().chars() {
+            if !letter.is_ascii_alphabetic() {
+                return Err(format!(
+                    "Invalid permutation: {}. Expected only letters A - Z",
+                    letters
+                ));
+            }
+            map.push(letter.to_ascii_uppercase() as u8 - b'A');
+        }
+        if map.len() != 26 {
+            return Err(format!(
+                "Invalid permutation: {}. Expected 26 letters",
+                letters
+            ));
+        }
+
+        Permutation::new(map)
+    }
+
+    /// The permutation that leaves every contact in place.
+    pub fn identity(size: usize) -> Permutation {
+        Permutation {
+            map: (0..size).map(|val| val as u8).collect(),
+        }
+    }
+
+    /// The number of contacts.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The contact a contact is sent to.
+    ///
+    /// Panics if the contact is out of range.
+    pub fn apply(&self, val: u8) -> u8 {
+        self.map[val as usize]
+    }
+
+    /// The image of each contact.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// The permutation applying `self` first and then `other`.
+    ///
+    /// Panics if the permutations have different sizes.
+    pub fn compose(&self, other: &Permutation) -> Permutation {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "Can't compose permutations of different sizes"
+        );
+        Permutation {
+            map: self.map.iter().map(|&val| other.apply(val)).collect(),
+        }
+    }
+
+    /// The permutation undoing this one.
+    pub fn inverse(&self) -> Permutation {
+        let mut map = vec![0; self.len()];
+        for (val, &image) in self.map.iter().enumerate() {
+            map[image as usize] = val as u8;
+        }
+        Permutation { map }
+    }
+
+    /// `other⁻¹ · self · other`, i.e., this permutation with its contacts
+    /// relabelled by `other`.
+    pub fn conjugate_by(&self, other: &Permutation) -> Permutation {
+        other.inverse().compose(self).compose(other)
+    }
+
+    /// The disjoint cycles, each starting at its smallest contact, ordered
+    /// by that contact. Fixed points are cycles of length one.
+    pub fn cycles(&self) -> Vec<Vec<u8>> {
+        let mut seen = vec![false; self.len()];
+        let mut cycles = Vec::new();
+        for start in 0..self.len() {
+            if seen[start] {
+                continue;
+            }
+
+            let mut cycle = Vec::new();
+            let mut val = start;
+            while !seen[val] {
+                seen[val] = true;
+                cycle.push(val as u8);
+                val = self.map[val] as usize;
+            }
+            cycles.push(cycle);
+        }
+        cycles
+    }
+
+    /// The cycle lengths, longest first. Two permutations are conjugate, i.e.,
+    /// in the same conjugacy class, exactly when their cycle types match.
+    pub fn cycle_type(&self) -> Vec<usize> {
+        let mut lengths: Vec<usize> = self.cycles().iter().map(Vec::len).collect();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        lengths
+    }
+
+    /// Whether `other` is in the same conjugacy class.
+    pub fn is_conjugate(&self, other: &Permutation) -> bool {
+        self.len() == other.len() && self.cycle_type() == other.cycle_type()
+    }
+
+    /// A permutation `p` that relabels `self` into `other`, i.e.,
+    /// `self.conjugate_by(&p) == *other`. `None` if they aren't conjugate.
+    pub fn conjugating(&self, other: &Permutation) -> Option<Permutation> {
+        if !self.is_conjugate(other) {
+            return None;
+        }
+
+        let by_length = |p: &Permutation| {
+            let mut cycles = p.cycles();
+            cycles.sort_by_key(|cycle| std::cmp::Reverse(cycle.len()));
+            cycles
+        };
+        let mut map = vec![0; self.len()];
+        for (from, to) in by_length(self).iter().zip(by_length(other).iter()) {
+            for (&a, &b) in from.iter().zip(to.iter()) {
+                map[a as usize] = b;
+            }
+        }
+        Some(Permutation { map })
+    }
+
+    /// Whether applying the permutation twice gives the identity, as for
+    /// every Enigma with a reflector.
+    pub fn is_involution(&self) -> bool {
+        self.map
+            .iter()
+            .enumerate()
+            .all(|(val, &image)| self.map[image as usize] as usize == val)
+    }
+
+    /// The contacts sent to themselves.
+    pub fn fixed_points(&self) -> Vec<u8> {
+        self.map
+            .iter()
+            .enumerate()
+            .filter(|&(val, &image)| val == image as usize)
+            .map(|(val, _)| val as u8)
+            .collect()
+    }
+
+    /// Whether any contact is sent to itself. An Enigma never encrypts a
+    /// letter to itself, so this is always false for its permutations.
+    pub fn has_fixed_points(&self) -> bool {
+        self.map
+            .iter()
+            .enumerate()
+            .any(|(val, &image)| val == image as usize)
+    }
+
+    /// The cycles as keys of an alphabet, e.g., `(AB)(CDE)`, or `(12)(345)`
+    /// for the Enigma Z. Fixed points are left out.
+    ///
+    /// Panics if the permutation has more contacts than the alphabet has
+    /// keys.
+    pub fn cycle_notation(&self, alphabet: &Alphabet) -> String {
+        self.cycles()
+            .iter()
+            .filter(|cycle| cycle.len() > 1)
+            .map(|cycle| {
+                let letters: String = cycle.iter().map(|&val| alphabet.letter(val)).collect();
+                format!("({})", letters)
+            })
+            .collect()
+    }
+}
+
+/// Formats a permutation of `A` - `Z` as the letter each letter is sent to,
+/// e.g., `BADCEFGHIJKLMNOPQRSTUVWXYZ`. Other sizes are formatted as the
+/// contact numbers.
+impl fmt::Display for Permutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.len() == 26 {
+            let letters: String = self.map.iter().map(|&val| (val + b'A') as char).collect();
+            write!(f, "{}", letters)
+        } else {
+            let vals: Vec<String> = self.map.iter().map(|val| val.to_string()).collect();
+            write!(f, "{}", vals.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_and_invert() {
+        let a = Permutation::from_letters("BCDEFGHIJKLMNOPQRSTUVWXYZA").unwrap();
+        let b = Permutation::from_letters("ZYXWVUTSRQPONMLKJIHGFEDCBA").unwrap();
+
+        // A -> B under a, then B -> Y under b
+        assert_eq!(a.compose(&b).apply(0), 24);
+        assert_eq!(b.compose(&a).apply(0), 0);
+        assert_eq!(a.compose(&a.inverse()), Permutation::identity(26));
+        assert_eq!(a.compose(&b).inverse(), b.inverse().compose(&a.inverse()));
+        assert_eq!(a.cycle_type(), vec![26]);
+        assert_eq!(b.cycle_type(), vec![2; 13]);
+        assert!(b.is_involution());
+        assert!(!b.has_fixed_points());
+    }
+
+    #[test]
+    fn test_conjugacy() {
+        let a = Permutation::from_letters("BCAEDFGHIJKLMNOPQRSTUVWXYZ").unwrap();
+        let b = Permutation::from_letters("AFCDEGBHIJKLMNOPQRSTUVWXYZ").unwrap();
+        assert_eq!(a.cycle_notation(&Alphabet::latin()), "(ABC)(DE)");
+        assert_eq!(b.cycle_notation(&Alphabet::latin()), "(BFG)");
+        assert!(!a.is_conjugate(&b));
+
+        let relabel = Permutation::from_letters("QWERTZUIOASDFGHJKPYXCVBNML").unwrap();
+        let c = a.conjugate_by(&relabel);
+        assert!(a.is_conjugate(&c));
+        let p = a.conjugating(&c).unwrap();
+        assert_eq!(a.conjugate_by(&p), c);
+        assert!(a.conjugating(&b).is_none());
+    }
+
+    #[test]
+    fn test_cycle_notation_of_digits() {
+        let p = Permutation::new(vec![1, 0, 3, 4, 2, 5, 6, 7, 8, 9]).unwrap();
+        assert_eq!(p.cycle_notation(&Alphabet::z()), "(12)(345)");
+        assert_eq!(p.cycle_notation(&Alphabet::digits()), "(01)(234)");
+    }
+
+    #[test]
+    fn test_invalid_permutations() {
+        assert!(Permutation::new(vec![0, 0]).is_err());
+        assert!(Permutation::new(vec![0, 2]).is_err());
+        assert!(Permutation::new(vec![1, 0]).is_ok());
+        assert!(Permutation::from_letters("ABC").is_err());
+        assert!(Permutation::from_letters("AACDEFGHIJKLMNOPQRSTUVWXYZ").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let p = Permutation::from_letters("badcefghijklmnopqrstuvwxyz").unwrap();
+        assert_eq!(p.to_string(), "BADCEFGHIJKLMNOPQRSTUVWXYZ");
+        assert_eq!(
+            Permutation::new(vec![1, 2, 0]).unwrap().to_string(),
+            "1 2 0"
+        );
+    }
+}
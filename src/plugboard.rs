@@ -1,8 +1,19 @@
 use std::collections::HashMap;
 
 use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+/// Enigma plugboard (Steckerbrett).
+///
+/// With the `serde` feature a [PlugBoard](PlugBoard) is serialized as its
+/// list of connected pairs.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "Vec<(char, char)>", into = "Vec<(char, char)>")
+)]
 pub struct PlugBoard {
     mapping: HashMap<u8, u8>,
 }
@@ -43,6 +54,13 @@ impl PlugBoard {
         let in1_val = in1.to_ascii_uppercase() as u8 - 65;
         let in2_val = in2.to_ascii_uppercase() as u8 - 65;
 
+        if in1_val == in2_val {
+            return Err(format!(
+                "Error in PlugBoard configuration. Can't plug {} into itself",
+                in1.to_ascii_uppercase()
+            ));
+        }
+
         if self.mapping.contains_key(&in1_val) {
             return Err(format!(
                 "Error in PlugBoard configuration. Duplicate mapping for {} encountered",
@@ -62,6 +80,18 @@ impl PlugBoard {
         Ok(())
     }
 
+    /// The connected pairs in alphabetical order.
+    pub fn pairs(&self) -> Vec<(char, char)> {
+        let mut pairs: Vec<(char, char)> = self
+            .mapping
+            .iter()
+            .filter(|(in1, in2)| in1 < in2)
+            .map(|(&in1, &in2)| ((in1 + b'A') as char, (in2 + b'A') as char))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
     pub fn map(&self, in_val: u8) -> u8 {
         if let Some(&v) = self.mapping.get(&in_val) {
             v
@@ -71,6 +101,22 @@ impl PlugBoard {
     }
 }
 
+#[cfg(feature = "serde")]
+impl TryFrom<Vec<(char, char)>> for PlugBoard {
+    type Error = String;
+
+    fn try_from(pairs: Vec<(char, char)>) -> Result<Self, Self::Error> {
+        PlugBoard::new_with_mapping(pairs)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<PlugBoard> for Vec<(char, char)> {
+    fn from(plugboard: PlugBoard) -> Self {
+        plugboard.pairs()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,8 +128,8 @@ mod tests {
         let res = pb.add_mapping('A', 'B');
 
         assert!(res.is_ok());
-        assert!(pb.mapping.contains_key(&('A' as u8 - 65)));
-        assert!(pb.mapping.contains_key(&('B' as u8 - 65)));
+        assert!(pb.mapping.contains_key(&(b'A' - 65)));
+        assert!(pb.mapping.contains_key(&(b'B' - 65)));
     }
 
     #[test]
@@ -93,8 +139,8 @@ mod tests {
         let res = pb.add_mapping('a', 'B');
 
         assert!(res.is_ok());
-        assert!(pb.mapping.contains_key(&('A' as u8 - 65)));
-        assert!(pb.mapping.contains_key(&('B' as u8 - 65)));
+        assert!(pb.mapping.contains_key(&(b'A' - 65)));
+        assert!(pb.mapping.contains_key(&(b'B' - 65)));
     }
 
     #[test]
@@ -106,17 +152,29 @@ mod tests {
 
         let err = pb.add_mapping('A', ',');
         assert!(err.is_err());
+
+        let err = pb.add_mapping('a', 'A');
+        assert!(err.is_err());
+        assert!(pb.mapping.is_empty());
     }
 
     #[test]
     fn plugboard_new_with_mappings() {
         let pb = PlugBoard::new_with_mapping(vec![('A', 'B'), ('C', 'D')]).unwrap();
-        assert!(pb.mapping.contains_key(&('A' as u8 - 65)));
-        assert!(pb.mapping.contains_key(&('B' as u8 - 65)));
-        assert!(pb.mapping.contains_key(&('C' as u8 - 65)));
-        assert!(pb.mapping.contains_key(&('D' as u8 - 65)));
+        assert!(pb.mapping.contains_key(&(b'A' - 65)));
+        assert!(pb.mapping.contains_key(&(b'B' - 65)));
+        assert!(pb.mapping.contains_key(&(b'C' - 65)));
+        assert!(pb.mapping.contains_key(&(b'D' - 65)));
     }
 
+    #[test]
+    fn plugboard_pairs() {
+        let pb = PlugBoard::new_with_mapping(vec![('Z', 'a'), ('D', 'C')]).unwrap();
+        assert_eq!(pb.pairs(), vec![('A', 'Z'), ('C', 'D')]);
+        assert!(PlugBoard::new().pairs().is_empty());
+    }
+
+    #[test]
     fn plugboard_new_with_mappings_percolates_errors() {
         let pb = PlugBoard::new_with_mapping(vec![('A', ','), ('C', 'D')]);
 
@@ -0,0 +1,420 @@
+//! Machine profile file module.
+//!
+//! A profile file holds any number of named machine setups in TOML or JSON.
+//! Rotors are listed left rotor first, as seen from the front of the
+//! machine, and ring settings are numbered `1` - `26` as on a key sheet. The
+//! Enigma Z is set with its digit keys and rings `1` - `10`, starting at its
+//! first key `1` when no position is given.
+//! Settable reflectors, e.g., `UKWK`, take `reflector_position` and
+//! `reflector_ring` keys in the same form. An `entry_wheel` wiring and a
+//! `stepping`, e.g., `"Odometer"`, replace the ones the reflector's model
+//! shipped with.
+//!
+//! ```toml
+//! [profiles.training]
+//! model = "I"
+//! reflector = "B"
+//! rotors = [
+//!     { id = "I", position = "A", ring = 1 },
+//!     { id = "II", position = "B", ring = 1 },
+//!     { id = "III", position = "C", ring = 1 },
+//! ]
+//! plugs = ["AB", "CD"]
+//! ```
+//!
+//! ```
+//! use enigma::machine::EnigmaMachineBuilder;
+//! use enigma::profile::ProfileFile;
+//!
+//! let file = ProfileFile::from_toml(r#"
+//!     [profiles.training]
+//!     reflector = "A"
+//!     rotors = [{ id = "III" }, { id = "II" }, { id = "I" }]
+//!     plugs = ["AB"]
+//! "#).unwrap();
+//!
+//! let profile = file.profile("training").unwrap();
+//! let mut em = EnigmaMachineBuilder::from_profile(profile).unwrap().build().unwrap();
+//! assert_eq!(em.translate_text("hello".chars()), "LZFAD");
+//! ```
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+use crate::config::{model_alphabet, parse_plugs, reflector_id, rotor_id, Config, UhrConfig};
+use crate::entry_wheel::EntryWheel;
+use crate::model::EnigmaModel;
+use crate::reflector::Reflector;
+use crate::stepping::Stepping;
+
+/// A collection of named machine profiles.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileFile {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ProfileFile {
+    /// Parse a profile file from TOML.
+    pub fn from_toml(input: &str) -> Result<ProfileFile, String> {
+        toml::from_str(input).map_err(|err| format!("Invalid profile file: {}", err))
+    }
+
+    /// Parse a profile file from JSON.
+    pub fn from_json(input: &str) -> Result<ProfileFile, String> {
+        serde_json::from_str(input).map_err(|err| format!("Invalid profile file: {}", err))
+    }
+
+    /// Load a profile file from disk.
+    ///
+    /// Files ending in `.json` are read as JSON, anything else as TOML.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ProfileFile, String> {
+        let path = path.as_ref();
+        let input = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ProfileFile::from_json(&input),
+            _ => ProfileFile::from_toml(&input),
+        }
+    }
+
+    /// Look up a profile by name.
+    pub fn profile(&self, name: &str) -> Result<&Profile, String> {
+        self.profiles.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.profiles.keys().map(|name| name.as_str()).collect();
+            format!(
+                "Unknown profile: {}. Available profiles: {}",
+                name,
+                names.join(", ")
+            )
+        })
+    }
+
+    /// Look up a profile by name, or the only profile in the file if no name
+    /// is given.
+    pub fn select(&self, name: Option<&str>) -> Result<&Profile, String> {
+        match name {
+            Some(name) => self.profile(name),
+            None if self.profiles.len() == 1 => Ok(self.profiles.values().next().unwrap()),
+            None => Err(format!(
+                "The profile file holds {} profiles. Select one with --profile",
+                self.profiles.len()
+            )),
+        }
+    }
+}
+
+/// A single machine setup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Machine model, e.g., `M3`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub reflector: String,
+    /// Position of a settable reflector, e.g., `UKWK`
+    #[serde(
+        default = "RotorProfile::default_position",
+        skip_serializing_if = "Profile::is_default_position"
+    )]
+    pub reflector_position: char,
+    /// Ring setting of a settable reflector, `1` - `26`, or `1` - `10` on the
+    /// Enigma Z
+    #[serde(
+        default = "RotorProfile::default_ring",
+        skip_serializing_if = "Profile::is_default_ring"
+    )]
+    pub reflector_ring: u8,
+    /// Rotors, left rotor first
+    pub rotors: Vec<RotorProfile>,
+    /// Plugboard pairs, e.g., `AB`
+    #[serde(default)]
+    pub plugs: Vec<String>,
+    /// Position of an Uhr, `0` - `39`, whose cables `1` - `10` are the
+    /// plugs, listing the `a` plug first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uhr: Option<u8>,
+    /// Entry wheel other than the one the reflector's model shipped with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_wheel: Option<EntryWheel>,
+    /// Stepping other than the one the reflector's model shipped with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stepping: Option<Stepping>,
+}
+
+/// A rotor within a [Profile](Profile).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotorProfile {
+    pub id: String,
+    /// Start position shown in the rotor window
+    #[serde(default = "RotorProfile::default_position")]
+    pub position: char,
+    /// Ring setting, `1` - `26`, or `1` - `10` on the Enigma Z
+    #[serde(default = "RotorProfile::default_ring")]
+    pub ring: u8,
+}
+
+impl RotorProfile {
+    fn default_position() -> char {
+        'A'
+    }
+
+    fn default_ring() -> u8 {
+        1
+    }
+}
+
+impl Profile {
+    fn is_default_position(position: &char) -> bool {
+        *position == RotorProfile::default_position()
+    }
+
+    fn is_default_ring(ring: &u8) -> bool {
+        *ring == RotorProfile::default_ring()
+    }
+
+    /// Validate the profile and convert it to a [Config](Config).
+    pub fn config(&self) -> Result<Config, String> {
+        let reflector_id = reflector_id(&self.reflector)?;
+        let model = match &self.model {
+            Some(model) => Some(model.parse::<EnigmaModel>()?),
+            None => None,
+        };
+        let alphabet = model_alphabet(model, reflector_id);
+        let (reflector_position, reflector_ring) =
+            parse_setting(self.reflector_position, self.reflector_ring, &alphabet)?;
+        if (reflector_position, reflector_ring) != (0, 0)
+            && !Reflector::new(reflector_id).is_settable()
+        {
+            return Err(format!("Reflector {} can't be set", reflector_id));
+        }
+
+        if self.rotors.len() != 3 && self.rotors.len() != 4 {
+            return Err(format!(
+                "Expected 3 or 4 rotors. Received {}",
+                self.rotors.len()
+            ));
+        }
+
+        let mut rotor_ids = Vec::new();
+        for rotor in self.rotors.iter().rev() {
+            let id = rotor_id(&rotor.id)?;
+            let (position, ring) = parse_setting(rotor.position, rotor.ring, &alphabet)?;
+            rotor_ids.push((id.to_string(), position, ring));
+        }
+
+        let plugs = parse_plugs(&self.plugs.join(" "))?;
+        let mut cfg = Config::new(reflector_id, rotor_ids, plugs)
+            .with_reflector_state(reflector_position, reflector_ring);
+        cfg.uhr_config = self.uhr.map(|position| UhrConfig { position });
+        cfg.uhr()?;
+        cfg.entry_wheel = self.entry_wheel.clone();
+        cfg.stepping = self.stepping.clone();
+
+        if let Some(model) = model {
+            cfg.validate_model(model)?;
+            cfg = cfg.with_model(model);
+        }
+
+        Ok(cfg)
+    }
+}
+
+/// Convert a window key and a ring setting numbered from `1` to `0` based
+/// values.
+fn parse_setting(position: char, ring: u8, alphabet: &Alphabet) -> Result<(u8, u8), String> {
+    let position = match alphabet.index_of(position) {
+        Some(position) => position,
+        // A missing position is the first key, also without an `A` key
+        None if position == RotorProfile::default_position() => 0,
+        None => {
+            return Err(format!(
+                "Invalid position: {}. Expected one of {}",
+                position, alphabet
+            ))
+        }
+    };
+
+    if !(1..=alphabet.len()).contains(&(ring as usize)) {
+        return Err(format!(
+            "Invalid ring setting: {}. Expected a number 1 - {}",
+            ring,
+            alphabet.len()
+        ));
+    }
+
+    Ok((position, ring - 1))
+}
+
+impl From<&Config> for Profile {
+    fn from(cfg: &Config) -> Profile {
+        let alphabet = cfg.alphabet();
+        Profile {
+            model: cfg.model.map(|model| model.to_string()),
+            reflector: cfg.reflector_config.reflector_id.clone(),
+            reflector_position: alphabet.letter(cfg.reflector_config.position),
+            reflector_ring: cfg.reflector_config.ring + 1,
+            rotors: cfg
+                .rotor_config
+                .rotor_ids
+                .iter()
+                .rev()
+                .map(|(id, pos, ring)| RotorProfile {
+                    id: id.clone(),
+                    position: alphabet.letter(*pos),
+                    ring: ring + 1,
+                })
+                .collect(),
+            plugs: cfg
+                .plugboard_config
+                .iter()
+                .flat_map(|pbcfg| pbcfg.plugboard_maps.iter())
+                .map(|(a, b)| format!("{}{}", a, b))
+                .collect(),
+            uhr: cfg.uhr_config.as_ref().map(|uhrcfg| uhrcfg.position),
+            entry_wheel: cfg.entry_wheel.clone(),
+            stepping: cfg.stepping.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES_TOML: &str = r#"
+        [profiles.training]
+        model = "I"
+        reflector = "B"
+        rotors = [
+            { id = "I", position = "X", ring = 1 },
+            { id = "II", position = "Y", ring = 2 },
+            { id = "III", position = "Z", ring = 3 },
+        ]
+        plugs = ["AB", "CD"]
+
+        [profiles.reference]
+        reflector = "A"
+        rotors = [{ id = "III" }, { id = "II" }, { id = "I" }]
+        plugs = ["AB"]
+    "#;
+
+    #[test]
+    fn test_load_toml_profiles() {
+        let file = ProfileFile::from_toml(PROFILES_TOML).unwrap();
+        assert_eq!(file.profiles.len(), 2);
+
+        let training = file.profile("training").unwrap();
+        assert_eq!(training.model.as_deref(), Some("I"));
+        assert_eq!(
+            training.config().unwrap().to_string(),
+            "B;I-X-A,II-Y-B,III-Z-C;a-b,c-d"
+        );
+        assert_eq!(training.config().unwrap().model, Some(EnigmaModel::I));
+
+        let reference = file.profile("reference").unwrap();
+        let mut em = reference.config().unwrap().build_machine();
+        assert_eq!(
+            em.translate_text("hello, how are you doing".chars()),
+            "LZFAD, AMT GPJ FND IFMJY"
+        );
+
+        assert!(file.profile("missing").is_err());
+        assert!(file.select(None).is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let file = ProfileFile::from_toml(PROFILES_TOML).unwrap();
+        let json = serde_json::to_string(&file).unwrap();
+        assert_eq!(ProfileFile::from_json(&json).unwrap(), file);
+
+        for cfg_str in [
+            "C;IV-Q-B,V-E-Z,I-A-M;q-w,e-r",
+            "UKWR-C-D;IR-A-A,IIIR-B-C,IIR-Z-Z",
+            "UKWZ-2-3;IIIZ-1-1,IIZ-5-0,IZ-0-9",
+        ] {
+            let cfg = Config::parse(cfg_str.to_string()).unwrap();
+            assert_eq!(Profile::from(&cfg).config().unwrap(), cfg);
+        }
+
+        let cfg = Config::parse("UKWK-C-D;IIISK-A-A,IISK-B-A,ISK-A-C".to_string())
+            .unwrap()
+            .with_model(EnigmaModel::SwissK)
+            .with_entry_wheel(EntryWheel::identity())
+            .with_stepping(Stepping::Odometer);
+        let mut file = ProfileFile::default();
+        file.profiles
+            .insert("swiss".to_string(), Profile::from(&cfg));
+        let json = serde_json::to_string(&file).unwrap();
+        let profile = ProfileFile::from_json(&json).unwrap().profiles["swiss"].clone();
+        assert_eq!(profile.model.as_deref(), Some("Swiss-K"));
+        assert_eq!(profile.config().unwrap(), cfg);
+    }
+
+    #[test]
+    fn test_enigma_z_profile() {
+        let file = ProfileFile::from_toml(
+            r#"
+            [profiles.z]
+            model = "Z"
+            reflector = "UKWZ"
+            reflector_ring = 10
+            rotors = [{ id = "IIIZ" }, { id = "IIZ", position = "0" }, { id = "IZ", ring = 2 }]
+            "#,
+        )
+        .unwrap();
+        let cfg = file.profile("z").unwrap().config().unwrap();
+        assert_eq!(cfg.to_string(), "UKWZ-1-0;IIIZ-1-1,IIZ-0-1,IZ-1-2");
+
+        for rotors in [
+            r#"[{ id = "IIIZ", ring = 11 }, { id = "IIZ" }, { id = "IZ" }]"#,
+            r#"[{ id = "IIIZ", position = "B" }, { id = "IIZ" }, { id = "IZ" }]"#,
+        ] {
+            let input = format!("[profiles.z]\nreflector = \"UKWZ\"\nrotors = {}", rotors);
+            let file = ProfileFile::from_toml(&input).unwrap();
+            assert!(file.profile("z").unwrap().config().is_err(), "{}", rotors);
+        }
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        let invalid = [
+            r#"[profiles.p]
+               reflector = "D"
+               rotors = [{ id = "I" }, { id = "II" }, { id = "III" }]"#,
+            r#"[profiles.p]
+               reflector = "B"
+               rotors = [{ id = "I" }, { id = "II" }]"#,
+            r#"[profiles.p]
+               reflector = "B"
+               rotors = [{ id = "I", ring = 27 }, { id = "II" }, { id = "III" }]"#,
+            r#"[profiles.p]
+               reflector = "B"
+               rotors = [{ id = "I" }, { id = "II" }, { id = "III" }]
+               plugs = ["AB", "BC"]"#,
+            r#"[profiles.p]
+               reflector = "B"
+               reflector_position = "C"
+               rotors = [{ id = "I" }, { id = "II" }, { id = "III" }]"#,
+            r#"[profiles.p]
+               model = "I"
+               reflector = "B"
+               rotors = [{ id = "VI" }, { id = "II" }, { id = "III" }]"#,
+            r#"[profiles.p]
+               model = "M5"
+               reflector = "B"
+               rotors = [{ id = "I" }, { id = "II" }, { id = "III" }]"#,
+        ];
+
+        for input in invalid {
+            let file = ProfileFile::from_toml(input).unwrap();
+            assert!(file.select(None).unwrap().config().is_err(), "{}", input);
+        }
+
+        assert!(ProfileFile::from_toml("[profiles.p]\nrotors = 3").is_err());
+    }
+}
@@ -1,7 +1,26 @@
 //! Enigma Reflector module.
 //!
 //! The [Reflector](Reflector) struct implements the Engima reflector
-//! rotors. Currently supports reflectors `A`, `B`, and `C`.
+//! rotors. Currently supports reflectors `A`, `B`, and `C`, the thin
+//! Kriegsmarine M4 reflectors `B-thin` and `C-thin`, the commercial (and
+//! Swiss-K) reflector `UKWK`, the Railway reflector `UKWR`, the Enigma G
+//! reflectors `UKWG` (G-31) and `UKWG312` (G-312) and the Enigma T reflector
+//! `UKWT`. Reflectors for other alphabets can be wired with
+//! [Reflector::new_custom](Reflector::new_custom).
+//!
+//! The commercial, Railway, Enigma G and Enigma T reflectors can be set to a
+//! position and ring setting like a rotor. Only the Enigma G reflector
+//! steps, driven by the left rotor.
+//!
+//! With the `serde` feature a [Reflector](Reflector) is serialized as its id,
+//! e.g., `"B"`, or as its id, position and ring setting if it has been set.
+use std::borrow::Cow;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::alphabet::Alphabet;
 
 // E, J, M, Z, A, L, Y, X, V, B, W, F, C, R, Q, U, O, N, T, S, P, I, K, H, G, D
 const REFLECTOR_A_ALPHABET: [u8; 26] = [
@@ -18,34 +37,228 @@ const REFLECTOR_C_ALPHABET: [u8; 26] = [
     5, 21, 15, 9, 8, 0, 14, 24, 4, 3, 17, 25, 23, 22, 6, 2, 19, 10, 20, 16, 18, 1, 13, 12, 7, 11,
 ];
 
+// E, N, K, Q, A, U, Y, W, J, I, C, O, P, B, L, M, D, X, Z, V, F, T, H, R, G, S
+const REFLECTOR_B_THIN_ALPHABET: [u8; 26] = [
+    4, 13, 10, 16, 0, 20, 24, 22, 9, 8, 2, 14, 15, 1, 11, 12, 3, 23, 25, 21, 5, 19, 7, 17, 6, 18,
+];
+
+// R, D, O, B, J, N, T, K, V, E, H, M, L, F, C, W, Z, A, X, G, Y, I, P, S, U, Q
+const REFLECTOR_C_THIN_ALPHABET: [u8; 26] = [
+    17, 3, 14, 1, 9, 13, 19, 10, 21, 4, 7, 12, 11, 5, 2, 22, 25, 0, 23, 6, 24, 8, 15, 18, 20, 16,
+];
+
+// I, M, E, T, C, G, F, R, A, Y, S, Q, B, Z, X, W, L, H, K, D, V, U, P, O, J, N
+const REFLECTOR_K_ALPHABET: [u8; 26] = [
+    8, 12, 4, 19, 2, 6, 5, 17, 0, 24, 18, 16, 1, 25, 23, 22, 11, 7, 10, 3, 21, 20, 15, 14, 9, 13,
+];
+
+// Q, Y, H, O, G, N, E, C, V, P, U, Z, T, F, D, J, A, X, W, M, K, I, S, R, B, L
+const REFLECTOR_R_ALPHABET: [u8; 26] = [
+    16, 24, 7, 14, 6, 13, 4, 2, 21, 15, 20, 25, 19, 5, 3, 9, 0, 23, 22, 12, 10, 8, 18, 17, 1, 11,
+];
+
+// R, U, L, Q, M, Z, J, S, Y, G, O, C, E, T, K, W, D, A, H, N, B, X, P, V, I, F
+const REFLECTOR_G312_ALPHABET: [u8; 26] = [
+    17, 20, 11, 16, 12, 25, 9, 18, 24, 6, 14, 2, 4, 19, 10, 22, 3, 0, 7, 13, 1, 23, 15, 21, 8, 5,
+];
+
+// [G, E, K, P, B, T, A, U, M, O, C, N, I, L, J, D, X, Z, Y, F, H, W, V, Q, S, R]
+const REFLECTOR_T_ALPHABET: [u8; 26] = [
+    6, 4, 10, 15, 1, 19, 0, 20, 12, 14, 2, 13, 8, 11, 9, 3, 23, 25, 24, 5, 7, 22, 21, 16, 18, 17,
+];
+
+// Over the contacts 1 - 9, 0 of the Enigma Z keyboard
+// [5, 0, 7, 9, 1, 8, 3, 6, 4, 2]
+const REFLECTOR_Z_ALPHABET: [u8; 10] = [4, 9, 6, 8, 0, 7, 2, 5, 3, 1];
+
 /// Enigma Reflector
 ///
 /// Implementation of the Enigma "reflector" rotor. Supports the
-/// `A`, `B`, and `C` reflectors.
+/// `A`, `B`, `C`, `B-thin`, `C-thin`, `UKWK`, `UKWR`, `UKWG`, `UKWG312`,
+/// `UKWT` and `UKWZ` reflectors.
 #[derive(Debug, Clone)]
 pub struct Reflector {
-    alphabet: &'static [u8; 26],
+    id: Cow<'static, str>,
+    alphabet: Cow<'static, [u8]>,
+    settable: bool,
+    pos: u8,
+    ring_loc: u8,
 }
 
 impl Reflector {
     /// Create a new Reflector from a given reflector Id.
     ///
     /// * `reflector_id` - The reflector Id dictating the mapping of this
-    ///     reflector. Valid options are `A`, `B`, and `C`.
+    ///   reflector. Valid options are `A`, `B`, `C`, `B-thin` and `C-thin`
+    ///   and the [settable](Reflector::is_settable) reflectors `UKWK`
+    ///   (Enigma D, K and Swiss-K), `UKWR` (Railway), `UKWG`, `UKWG312`,
+    ///   `UKWT` and `UKWZ`, which start at position and ring setting `0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reflector id is not one of the above.
     pub fn new(reflector_id: &str) -> Reflector {
+        Reflector::from_id(reflector_id)
+            .unwrap_or_else(|| panic!("Invalid Reflector id: {}", reflector_id))
+    }
+
+    /// Create a new Reflector set to a position and ring setting.
+    ///
+    /// Only [settable](Reflector::is_settable) reflectors can be turned.
+    /// Settings given to any other reflector are kept, but are rejected when
+    /// a machine is built with it.
+    pub fn new_with_state(reflector_id: &str, pos: u8, ring_loc: u8) -> Reflector {
+        let mut reflector = Reflector::new(reflector_id);
+        let size = reflector.size() as u8;
+        reflector.pos = pos.rem_euclid(size);
+        reflector.ring_loc = ring_loc.rem_euclid(size);
+        reflector
+    }
+
+    /// Create a fixed reflector from a custom wiring, e.g., for an alphabet
+    /// other than `A` - `Z`.
+    ///
+    /// * `reflector_id` - The id reported by [id](Reflector::id)
+    /// * `wiring` - The key each contact is wired to, in contact order. Every
+    ///   key must be wired to another key that is wired back to it.
+    /// * `alphabet` - The keys of the machine
+    pub fn new_custom(
+        reflector_id: &str,
+        wiring: &str,
+        alphabet: &Alphabet,
+    ) -> Result<Reflector, String> {
+        let contacts = alphabet.wiring(wiring)?;
+        for (contact, &key) in contacts.iter().enumerate() {
+            if key as usize == contact || contacts[key as usize] as usize != contact {
+                return Err(format!(
+                    "Invalid reflector wiring: {}. {} must be wired to another key in pairs",
+                    wiring,
+                    alphabet.letter(contact as u8)
+                ));
+            }
+        }
+
+        Ok(Reflector {
+            id: Cow::Owned(reflector_id.to_string()),
+            alphabet: Cow::Owned(contacts),
+            settable: false,
+            pos: 0,
+            ring_loc: 0,
+        })
+    }
+
+    fn from_id(reflector_id: &str) -> Option<Reflector> {
         match reflector_id {
-            "A" => REFLECTOR_A,
-            "B" => REFLECTOR_B,
-            "C" => REFLECTOR_C,
-            _ => panic!("Invalid Reflector id: {}", reflector_id),
+            "A" => Some(REFLECTOR_A),
+            "B" => Some(REFLECTOR_B),
+            "C" => Some(REFLECTOR_C),
+            "B-thin" => Some(REFLECTOR_B_THIN),
+            "C-thin" => Some(REFLECTOR_C_THIN),
+            "UKWK" => Some(REFLECTOR_K),
+            "UKWR" => Some(REFLECTOR_R),
+            "UKWG" => Some(REFLECTOR_G),
+            "UKWG312" => Some(REFLECTOR_G312),
+            "UKWT" => Some(REFLECTOR_T),
+            "UKWZ" => Some(REFLECTOR_Z),
+            _ => None,
         }
     }
 
+    /// The reflector id, e.g., `B`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The number of contacts, i.e., the size of the alphabet.
+    pub fn size(&self) -> usize {
+        self.alphabet.len()
+    }
+
+    /// Whether the reflector can be set to a position and ring setting.
+    pub fn is_settable(&self) -> bool {
+        self.settable
+    }
+
+    /// The reflector position, `0` for `A`.
+    pub fn position(&self) -> u8 {
+        self.pos
+    }
+
+    /// The reflector ring setting, `0` for `A` (or `01`).
+    pub fn ring(&self) -> u8 {
+        self.ring_loc
+    }
+
+    /// Step the reflector to its next position, as the gear drive of the
+    /// Enigma G does.
+    pub fn rotate(&mut self) {
+        self.pos = (self.pos + 1).rem_euclid(self.size() as u8);
+    }
+
     /// Maps an input value "through" the reflector
     ///
     /// * `input_val` - The input "character" to map
     pub fn map(&self, input_val: u8) -> u8 {
-        self.alphabet[input_val as usize]
+        let size = self.size() as i16;
+        let offset = self.pos as i16 - self.ring_loc as i16;
+        let trans = self.alphabet[(input_val as i16 + offset).rem_euclid(size) as usize] as i16;
+        (trans - offset).rem_euclid(size) as u8
+    }
+}
+
+impl fmt::Display for Reflector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+/// Serialized form of a [Reflector](Reflector).
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ReflectorState {
+    Id(String),
+    Set { id: String, position: u8, ring: u8 },
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Reflector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.pos == 0 && self.ring_loc == 0 {
+            serializer.serialize_str(&self.id)
+        } else {
+            ReflectorState::Set {
+                id: self.id.to_string(),
+                position: self.pos,
+                ring: self.ring_loc,
+            }
+            .serialize(serializer)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Reflector {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Reflector, D::Error> {
+        let (reflector_id, pos, ring_loc) = match ReflectorState::deserialize(deserializer)? {
+            ReflectorState::Id(id) => (id, 0, 0),
+            ReflectorState::Set { id, position, ring } => (id, position, ring),
+        };
+
+        let mut reflector = Reflector::from_id(&reflector_id).ok_or_else(|| {
+            serde::de::Error::custom(format!("Invalid Reflector id: {}", reflector_id))
+        })?;
+        if !reflector.settable && (pos != 0 || ring_loc != 0) {
+            return Err(serde::de::Error::custom(format!(
+                "Reflector {} can't be set",
+                reflector_id
+            )));
+        }
+
+        let size = reflector.size() as u8;
+        reflector.pos = pos.rem_euclid(size);
+        reflector.ring_loc = ring_loc.rem_euclid(size);
+        Ok(reflector)
     }
 }
 
@@ -56,13 +269,81 @@ impl Default for Reflector {
 }
 
 const REFLECTOR_A: Reflector = Reflector {
-    alphabet: &REFLECTOR_A_ALPHABET,
+    id: Cow::Borrowed("A"),
+    alphabet: Cow::Borrowed(&REFLECTOR_A_ALPHABET),
+    settable: false,
+    pos: 0,
+    ring_loc: 0,
 };
 const REFLECTOR_B: Reflector = Reflector {
-    alphabet: &REFLECTOR_B_ALPHABET,
+    id: Cow::Borrowed("B"),
+    alphabet: Cow::Borrowed(&REFLECTOR_B_ALPHABET),
+    settable: false,
+    pos: 0,
+    ring_loc: 0,
 };
 const REFLECTOR_C: Reflector = Reflector {
-    alphabet: &REFLECTOR_C_ALPHABET,
+    id: Cow::Borrowed("C"),
+    alphabet: Cow::Borrowed(&REFLECTOR_C_ALPHABET),
+    settable: false,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_B_THIN: Reflector = Reflector {
+    id: Cow::Borrowed("B-thin"),
+    alphabet: Cow::Borrowed(&REFLECTOR_B_THIN_ALPHABET),
+    settable: false,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_C_THIN: Reflector = Reflector {
+    id: Cow::Borrowed("C-thin"),
+    alphabet: Cow::Borrowed(&REFLECTOR_C_THIN_ALPHABET),
+    settable: false,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_K: Reflector = Reflector {
+    id: Cow::Borrowed("UKWK"),
+    alphabet: Cow::Borrowed(&REFLECTOR_K_ALPHABET),
+    settable: true,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_R: Reflector = Reflector {
+    id: Cow::Borrowed("UKWR"),
+    alphabet: Cow::Borrowed(&REFLECTOR_R_ALPHABET),
+    settable: true,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_G: Reflector = Reflector {
+    id: Cow::Borrowed("UKWG"),
+    alphabet: Cow::Borrowed(&REFLECTOR_K_ALPHABET),
+    settable: true,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_G312: Reflector = Reflector {
+    id: Cow::Borrowed("UKWG312"),
+    alphabet: Cow::Borrowed(&REFLECTOR_G312_ALPHABET),
+    settable: true,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_T: Reflector = Reflector {
+    id: Cow::Borrowed("UKWT"),
+    alphabet: Cow::Borrowed(&REFLECTOR_T_ALPHABET),
+    settable: true,
+    pos: 0,
+    ring_loc: 0,
+};
+const REFLECTOR_Z: Reflector = Reflector {
+    id: Cow::Borrowed("UKWZ"),
+    alphabet: Cow::Borrowed(&REFLECTOR_Z_ALPHABET),
+    settable: true,
+    pos: 0,
+    ring_loc: 0,
 };
 
 #[cfg(test)]
@@ -75,4 +356,25 @@ mod tests {
             assert_eq!(REFLECTOR_A.map(cnt as u8), item);
         }
     }
+
+    #[test]
+    fn test_settable_reflector_stays_an_involution() {
+        for (pos, ring) in [(0, 0), (1, 0), (0, 5), (17, 9), (25, 25)] {
+            for id in ["UKWK", "UKWR", "UKWG", "UKWG312", "UKWT"] {
+                let reflector = Reflector::new_with_state(id, pos, ring);
+                assert!(reflector.is_settable());
+                for val in 0..26 {
+                    let out = reflector.map(val);
+                    assert_ne!(out, val);
+                    assert_eq!(reflector.map(out), val);
+                }
+            }
+        }
+
+        // Turning the reflector by one is undone by the same ring setting
+        let set = Reflector::new_with_state("UKWK", 4, 4);
+        let unset = Reflector::new("UKWK");
+        assert!((0..26).all(|val| set.map(val) == unset.map(val)));
+        assert!(!Reflector::new("B").is_settable());
+    }
 }
@@ -1,10 +1,28 @@
 //! Enigma Rotor module.
 //!
 //! The [Rotor](Rotor) struct implements the Engima rotors functionality.
-//! Currently supports rotors `I` through `VIII`.
+//! Currently supports rotors `I` through `VIII`, the Kriegsmarine M4
+//! "greek" rotors `Beta` and `Gamma`, the commercial Enigma D and K rotors
+//! `IK`, `IIK` and `IIIK`, the Swiss-K rotors `ISK`, `IISK` and `IIISK` and
+//! the Railway rotors `IR`, `IIR` and `IIIR`, and the many-notch Enigma G
+//! rotors `IG`, `IIG` and `IIIG` (G-31) and `IG312`, `IIG312` and `IIIG312`
+//! (G-312), the five notch Enigma T rotors `IT` through `VIIIT` and the ten
+//! contact Enigma Z rotors `IZ`, `IIZ` and `IIIZ`.
 //!
+//! Rotors for other alphabets can be wired with
+//! [RotorTyre::new](RotorTyre::new) and [Rotor::new_custom](Rotor::new_custom).
+//!
+//! With the `serde` feature a [Rotor](Rotor) is serialized as its id,
+//! position and ring setting. Custom rotors can't be deserialized as their
+//! wiring isn't part of the serialized form.
+use std::borrow::Cow;
 use std::fmt;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::alphabet::Alphabet;
+
 // [E, K, M, F, L, G, D, Q, V, Z, N, T, O, W, Y, H, X, U, S, P, A, I, B, R, C, J]
 const ROTOR_1_ALPHABET_IN: [u8; 26] = [
     4, 10, 12, 5, 11, 6, 3, 16, 21, 25, 13, 19, 14, 22, 24, 7, 23, 20, 18, 15, 0, 8, 1, 17, 2, 9,
@@ -77,80 +95,620 @@ const ROTOR_8_ALPHABET_OUT: [u8; 26] = [
     16, 9, 8, 13, 18, 0, 24, 3, 21, 10, 1, 5, 17, 20, 7, 12, 2, 15, 11, 4, 22, 25, 19, 6, 23, 14,
 ];
 
-#[derive(Debug)]
+// [L, E, Y, J, V, C, N, I, X, W, P, B, Q, M, D, R, T, A, K, Z, G, F, U, H, O, S]
+const ROTOR_BETA_ALPHABET_IN: [u8; 26] = [
+    11, 4, 24, 9, 21, 2, 13, 8, 23, 22, 15, 1, 16, 12, 3, 17, 19, 0, 10, 25, 6, 5, 20, 7, 14, 18,
+];
+
+const ROTOR_BETA_ALPHABET_OUT: [u8; 26] = [
+    17, 11, 5, 14, 1, 21, 20, 23, 7, 3, 18, 0, 13, 6, 24, 10, 12, 15, 25, 16, 22, 4, 9, 8, 2, 19,
+];
+
+// [F, S, O, K, A, N, U, E, R, H, M, B, T, I, Y, C, W, L, Q, P, Z, X, V, G, J, D]
+const ROTOR_GAMMA_ALPHABET_IN: [u8; 26] = [
+    5, 18, 14, 10, 0, 13, 20, 4, 17, 7, 12, 1, 19, 8, 24, 2, 22, 11, 16, 15, 25, 23, 21, 6, 9, 3,
+];
+
+const ROTOR_GAMMA_ALPHABET_OUT: [u8; 26] = [
+    4, 11, 15, 25, 7, 0, 23, 9, 13, 24, 3, 17, 10, 5, 2, 19, 18, 8, 1, 12, 6, 22, 16, 21, 14, 20,
+];
+
+// Commercial Enigma D and K
+// [L, P, G, S, Z, M, H, A, E, O, Q, K, V, X, R, F, Y, B, U, T, N, I, C, J, D, W]
+const ROTOR_K1_ALPHABET_IN: [u8; 26] = [
+    11, 15, 6, 18, 25, 12, 7, 0, 4, 14, 16, 10, 21, 23, 17, 5, 24, 1, 20, 19, 13, 8, 2, 9, 3, 22,
+];
+
+const ROTOR_K1_ALPHABET_OUT: [u8; 26] = [
+    7, 17, 22, 24, 8, 15, 2, 6, 21, 23, 11, 0, 5, 20, 9, 1, 10, 14, 3, 19, 18, 12, 25, 13, 16, 4,
+];
+
+// [S, L, V, G, B, T, F, X, J, Q, O, H, E, W, I, R, Z, Y, A, M, K, P, C, N, D, U]
+const ROTOR_K2_ALPHABET_IN: [u8; 26] = [
+    18, 11, 21, 6, 1, 19, 5, 23, 9, 16, 14, 7, 4, 22, 8, 17, 25, 24, 0, 12, 10, 15, 2, 13, 3, 20,
+];
+
+const ROTOR_K2_ALPHABET_OUT: [u8; 26] = [
+    18, 4, 22, 24, 12, 6, 3, 11, 14, 8, 20, 1, 19, 23, 10, 21, 9, 15, 0, 5, 25, 2, 13, 7, 17, 16,
+];
+
+// [C, J, G, D, P, S, H, K, T, U, R, A, W, Z, X, F, M, Y, N, Q, O, B, V, L, I, E]
+const ROTOR_K3_ALPHABET_IN: [u8; 26] = [
+    2, 9, 6, 3, 15, 18, 7, 10, 19, 20, 17, 0, 22, 25, 23, 5, 12, 24, 13, 16, 14, 1, 21, 11, 8, 4,
+];
+
+const ROTOR_K3_ALPHABET_OUT: [u8; 26] = [
+    11, 21, 0, 3, 25, 15, 2, 6, 24, 1, 7, 23, 16, 18, 20, 4, 19, 10, 5, 8, 9, 22, 12, 14, 17, 13,
+];
+
+// Swiss-K
+// [P, E, Z, U, O, H, X, S, C, V, F, M, T, B, G, L, R, I, N, Q, J, W, A, Y, D, K]
+const ROTOR_SK1_ALPHABET_IN: [u8; 26] = [
+    15, 4, 25, 20, 14, 7, 23, 18, 2, 21, 5, 12, 19, 1, 6, 11, 17, 8, 13, 16, 9, 22, 0, 24, 3, 10,
+];
+
+const ROTOR_SK1_ALPHABET_OUT: [u8; 26] = [
+    22, 13, 8, 24, 1, 10, 14, 5, 17, 20, 25, 15, 11, 18, 4, 0, 19, 16, 7, 12, 3, 9, 21, 6, 23, 2,
+];
+
+// [Z, O, U, E, S, Y, D, K, F, W, P, C, I, Q, X, H, M, V, B, L, G, N, J, R, A, T]
+const ROTOR_SK2_ALPHABET_IN: [u8; 26] = [
+    25, 14, 20, 4, 18, 24, 3, 10, 5, 22, 15, 2, 8, 16, 23, 7, 12, 21, 1, 11, 6, 13, 9, 17, 0, 19,
+];
+
+const ROTOR_SK2_ALPHABET_OUT: [u8; 26] = [
+    24, 18, 11, 6, 3, 8, 20, 15, 12, 22, 7, 19, 16, 21, 1, 10, 13, 23, 4, 25, 2, 17, 9, 14, 5, 0,
+];
+
+// [E, H, R, V, X, G, A, O, B, Q, U, S, I, M, Z, F, L, Y, N, W, K, T, P, D, J, C]
+const ROTOR_SK3_ALPHABET_IN: [u8; 26] = [
+    4, 7, 17, 21, 23, 6, 0, 14, 1, 16, 20, 18, 8, 12, 25, 5, 11, 24, 13, 22, 10, 19, 15, 3, 9, 2,
+];
+
+const ROTOR_SK3_ALPHABET_OUT: [u8; 26] = [
+    6, 8, 25, 23, 0, 15, 5, 1, 12, 24, 20, 16, 13, 18, 7, 22, 9, 2, 11, 21, 10, 3, 19, 4, 17, 14,
+];
+
+// Railway (Rocket) Enigma
+// [J, G, D, Q, O, X, U, S, C, A, M, I, F, R, V, T, P, N, E, W, K, B, L, Z, Y, H]
+const ROTOR_R1_ALPHABET_IN: [u8; 26] = [
+    9, 6, 3, 16, 14, 23, 20, 18, 2, 0, 12, 8, 5, 17, 21, 19, 15, 13, 4, 22, 10, 1, 11, 25, 24, 7,
+];
+
+const ROTOR_R1_ALPHABET_OUT: [u8; 26] = [
+    9, 21, 8, 2, 18, 12, 1, 25, 11, 0, 20, 22, 10, 17, 4, 16, 3, 13, 7, 15, 6, 14, 19, 5, 24, 23,
+];
+
+// [N, T, Z, P, S, F, B, O, K, M, W, R, C, J, D, I, V, L, A, E, Y, U, X, H, G, Q]
+const ROTOR_R2_ALPHABET_IN: [u8; 26] = [
+    13, 19, 25, 15, 18, 5, 1, 14, 10, 12, 22, 17, 2, 9, 3, 8, 21, 11, 0, 4, 24, 20, 23, 7, 6, 16,
+];
+
+const ROTOR_R2_ALPHABET_OUT: [u8; 26] = [
+    18, 6, 12, 14, 19, 5, 24, 23, 15, 13, 8, 17, 9, 0, 7, 3, 25, 11, 4, 1, 21, 16, 10, 22, 20, 2,
+];
+
+// [J, V, I, U, B, H, T, C, D, Y, A, K, E, Q, Z, P, O, S, G, X, N, R, M, W, F, L]
+const ROTOR_R3_ALPHABET_IN: [u8; 26] = [
+    9, 21, 8, 20, 1, 7, 19, 2, 3, 24, 0, 10, 4, 16, 25, 15, 14, 18, 6, 23, 13, 17, 12, 22, 5, 11,
+];
+
+const ROTOR_R3_ALPHABET_OUT: [u8; 26] = [
+    10, 4, 7, 8, 12, 24, 18, 5, 2, 0, 11, 25, 22, 20, 16, 15, 13, 21, 17, 6, 3, 1, 23, 19, 9, 14,
+];
+
+// Enigma G-31 (Abwehr)
+// [L, P, G, S, Z, M, H, A, E, O, Q, K, V, X, R, F, Y, B, U, T, N, I, C, J, D, W]
+const ROTOR_G1_ALPHABET_IN: [u8; 26] = [
+    11, 15, 6, 18, 25, 12, 7, 0, 4, 14, 16, 10, 21, 23, 17, 5, 24, 1, 20, 19, 13, 8, 2, 9, 3, 22,
+];
+
+const ROTOR_G1_ALPHABET_OUT: [u8; 26] = [
+    7, 17, 22, 24, 8, 15, 2, 6, 21, 23, 11, 0, 5, 20, 9, 1, 10, 14, 3, 19, 18, 12, 25, 13, 16, 4,
+];
+
+// [S, L, V, G, B, T, F, X, J, Q, O, H, E, W, I, R, Z, Y, A, M, K, P, C, N, D, U]
+const ROTOR_G2_ALPHABET_IN: [u8; 26] = [
+    18, 11, 21, 6, 1, 19, 5, 23, 9, 16, 14, 7, 4, 22, 8, 17, 25, 24, 0, 12, 10, 15, 2, 13, 3, 20,
+];
+
+const ROTOR_G2_ALPHABET_OUT: [u8; 26] = [
+    18, 4, 22, 24, 12, 6, 3, 11, 14, 8, 20, 1, 19, 23, 10, 21, 9, 15, 0, 5, 25, 2, 13, 7, 17, 16,
+];
+
+// [C, J, G, D, P, S, H, K, T, U, R, A, W, Z, X, F, M, Y, N, Q, O, B, V, L, I, E]
+const ROTOR_G3_ALPHABET_IN: [u8; 26] = [
+    2, 9, 6, 3, 15, 18, 7, 10, 19, 20, 17, 0, 22, 25, 23, 5, 12, 24, 13, 16, 14, 1, 21, 11, 8, 4,
+];
+
+const ROTOR_G3_ALPHABET_OUT: [u8; 26] = [
+    11, 21, 0, 3, 25, 15, 2, 6, 24, 1, 7, 23, 16, 18, 20, 4, 19, 10, 5, 8, 9, 22, 12, 14, 17, 13,
+];
+
+// Enigma G-312 (Abwehr)
+// [D, M, T, W, S, I, L, R, U, Y, Q, N, K, F, E, J, C, A, Z, B, P, G, X, O, H, V]
+const ROTOR_G312_1_ALPHABET_IN: [u8; 26] = [
+    3, 12, 19, 22, 18, 8, 11, 17, 20, 24, 16, 13, 10, 5, 4, 9, 2, 0, 25, 1, 15, 6, 23, 14, 7, 21,
+];
+
+const ROTOR_G312_1_ALPHABET_OUT: [u8; 26] = [
+    17, 19, 16, 0, 14, 13, 21, 24, 5, 15, 12, 6, 1, 11, 23, 20, 10, 7, 4, 2, 8, 25, 3, 22, 9, 18,
+];
+
+// [H, Q, Z, G, P, J, T, M, O, B, L, N, C, I, F, D, Y, A, W, V, E, U, S, R, K, X]
+const ROTOR_G312_2_ALPHABET_IN: [u8; 26] = [
+    7, 16, 25, 6, 15, 9, 19, 12, 14, 1, 11, 13, 2, 8, 5, 3, 24, 0, 22, 21, 4, 20, 18, 17, 10, 23,
+];
+
+const ROTOR_G312_2_ALPHABET_OUT: [u8; 26] = [
+    17, 9, 12, 15, 20, 14, 3, 0, 13, 5, 24, 10, 7, 11, 8, 4, 1, 23, 22, 6, 21, 19, 18, 25, 16, 2,
+];
+
+// [U, Q, N, T, L, S, Z, F, M, R, E, H, D, P, X, K, I, B, V, Y, G, J, C, W, O, A]
+const ROTOR_G312_3_ALPHABET_IN: [u8; 26] = [
+    20, 16, 13, 19, 11, 18, 25, 5, 12, 17, 4, 7, 3, 15, 23, 10, 8, 1, 21, 24, 6, 9, 2, 22, 14, 0,
+];
+
+const ROTOR_G312_3_ALPHABET_OUT: [u8; 26] = [
+    25, 17, 22, 12, 10, 7, 20, 11, 16, 21, 15, 4, 8, 2, 24, 13, 1, 9, 5, 3, 0, 18, 23, 14, 19, 6,
+];
+
+// Rollover when stepping from any of A, B, C, E, F, G, I, K, L, O, P, Q, S, U, V, W, Z
+const ROTOR_IG_NOTCHES: [u8; 17] = [0, 1, 2, 4, 5, 6, 8, 10, 11, 14, 15, 16, 18, 20, 21, 22, 25];
+
+// Rollover when stepping from any of A, C, D, F, G, H, K, M, N, Q, S, T, V, Y, Z
+const ROTOR_IIG_NOTCHES: [u8; 15] = [0, 2, 3, 5, 6, 7, 10, 12, 13, 16, 18, 19, 21, 24, 25];
+
+// Rollover when stepping from any of A, E, F, H, K, M, N, R, U, W, X
+const ROTOR_IIIG_NOTCHES: [u8; 11] = [0, 4, 5, 7, 10, 12, 13, 17, 20, 22, 23];
+
+// Enigma T (Tirpitz)
+// [K, P, T, Y, U, E, L, O, C, V, G, R, F, Q, D, A, N, J, M, B, S, W, H, Z, X, I]
+const ROTOR_T1_ALPHABET_IN: [u8; 26] = [
+    10, 15, 19, 24, 20, 4, 11, 14, 2, 21, 6, 17, 5, 16, 3, 0, 13, 9, 12, 1, 18, 22, 7, 25, 23, 8,
+];
+
+const ROTOR_T1_ALPHABET_OUT: [u8; 26] = [
+    15, 19, 8, 14, 5, 12, 10, 22, 25, 17, 0, 6, 18, 16, 7, 1, 13, 11, 20, 2, 4, 9, 21, 24, 3, 23,
+];
+
+const ROTOR_T1_NOTCHES: [u8; 5] = [4, 10, 16, 22, 25];
+
+// [U, P, H, Z, L, W, E, Q, M, T, D, J, X, C, A, K, S, O, I, G, V, B, Y, F, N, R]
+const ROTOR_T2_ALPHABET_IN: [u8; 26] = [
+    20, 15, 7, 25, 11, 22, 4, 16, 12, 19, 3, 9, 23, 2, 0, 10, 18, 14, 8, 6, 21, 1, 24, 5, 13, 17,
+];
+
+const ROTOR_T2_ALPHABET_OUT: [u8; 26] = [
+    14, 21, 13, 10, 6, 23, 19, 2, 18, 11, 15, 4, 8, 24, 17, 1, 7, 25, 16, 9, 0, 20, 5, 12, 22, 3,
+];
+
+const ROTOR_T2_NOTCHES: [u8; 5] = [5, 11, 17, 22, 25];
+
+// [Q, U, D, L, Y, R, F, E, K, O, N, V, Z, A, X, W, H, M, G, P, J, B, S, I, C, T]
+const ROTOR_T3_ALPHABET_IN: [u8; 26] = [
+    16, 20, 3, 11, 24, 17, 5, 4, 10, 14, 13, 21, 25, 0, 23, 22, 7, 12, 6, 15, 9, 1, 18, 8, 2, 19,
+];
+
+const ROTOR_T3_ALPHABET_OUT: [u8; 26] = [
+    13, 21, 24, 2, 7, 6, 18, 16, 23, 20, 8, 3, 17, 10, 9, 19, 0, 5, 22, 25, 1, 11, 15, 14, 4, 12,
+];
+
+const ROTOR_T3_NOTCHES: [u8; 5] = [4, 10, 16, 22, 25];
+
+// [C, I, W, T, B, K, X, N, R, E, S, P, F, L, Y, D, A, G, V, H, Q, U, O, J, Z, M]
+const ROTOR_T4_ALPHABET_IN: [u8; 26] = [
+    2, 8, 22, 19, 1, 10, 23, 13, 17, 4, 18, 15, 5, 11, 24, 3, 0, 6, 21, 7, 16, 20, 14, 9, 25, 12,
+];
+
+const ROTOR_T4_ALPHABET_OUT: [u8; 26] = [
+    16, 4, 0, 15, 9, 12, 17, 19, 1, 23, 5, 13, 25, 7, 22, 11, 20, 8, 10, 3, 21, 18, 2, 6, 14, 24,
+];
+
+const ROTOR_T4_NOTCHES: [u8; 5] = [5, 11, 17, 22, 25];
+
+// [U, A, X, G, I, S, N, J, B, V, E, R, D, Y, L, F, Z, W, T, P, C, K, O, H, M, Q]
+const ROTOR_T5_ALPHABET_IN: [u8; 26] = [
+    20, 0, 23, 6, 8, 18, 13, 9, 1, 21, 4, 17, 3, 24, 11, 5, 25, 22, 19, 15, 2, 10, 14, 7, 12, 16,
+];
+
+const ROTOR_T5_ALPHABET_OUT: [u8; 26] = [
+    1, 8, 20, 12, 10, 15, 3, 23, 4, 7, 21, 14, 24, 6, 22, 19, 25, 11, 5, 18, 0, 9, 17, 2, 13, 16,
+];
+
+const ROTOR_T5_NOTCHES: [u8; 5] = [2, 5, 10, 17, 24];
+
+// [X, F, U, Z, G, A, L, V, H, C, N, Y, S, E, W, Q, T, D, M, R, B, K, P, I, O, J]
+const ROTOR_T6_ALPHABET_IN: [u8; 26] = [
+    23, 5, 20, 25, 6, 0, 11, 21, 7, 2, 13, 24, 18, 4, 22, 16, 19, 3, 12, 17, 1, 10, 15, 8, 14, 9,
+];
+
+const ROTOR_T6_ALPHABET_OUT: [u8; 26] = [
+    5, 20, 9, 17, 13, 1, 4, 8, 23, 25, 21, 6, 18, 10, 24, 22, 15, 19, 12, 16, 2, 7, 14, 0, 11, 3,
+];
+
+const ROTOR_T6_NOTCHES: [u8; 5] = [4, 8, 12, 16, 23];
+
+// [B, J, V, F, T, X, P, L, N, A, Y, O, Z, I, K, W, G, D, Q, E, R, U, C, H, S, M]
+const ROTOR_T7_ALPHABET_IN: [u8; 26] = [
+    1, 9, 21, 5, 19, 23, 15, 11, 13, 0, 24, 14, 25, 8, 10, 22, 6, 3, 16, 4, 17, 20, 2, 7, 18, 12,
+];
+
+const ROTOR_T7_ALPHABET_OUT: [u8; 26] = [
+    9, 0, 22, 17, 19, 3, 16, 23, 13, 1, 14, 7, 25, 8, 11, 6, 18, 20, 24, 4, 21, 2, 15, 5, 10, 12,
+];
+
+const ROTOR_T7_NOTCHES: [u8; 5] = [2, 5, 10, 17, 24];
+
+// [Y, M, T, P, N, Z, H, W, K, O, D, A, J, X, E, L, U, Q, V, G, C, B, I, S, F, R]
+const ROTOR_T8_ALPHABET_IN: [u8; 26] = [
+    24, 12, 19, 15, 13, 25, 7, 22, 10, 14, 3, 0, 9, 23, 4, 11, 20, 16, 21, 6, 2, 1, 8, 18, 5, 17,
+];
+
+const ROTOR_T8_ALPHABET_OUT: [u8; 26] = [
+    11, 21, 20, 10, 14, 24, 19, 6, 22, 12, 8, 15, 1, 4, 9, 3, 17, 25, 23, 2, 16, 18, 7, 13, 0, 5,
+];
+
+const ROTOR_T8_NOTCHES: [u8; 5] = [4, 8, 12, 16, 23];
+
+// The Enigma Z rotors are wired over the contacts 1 - 9, 0 of its keyboard
+// [6, 4, 1, 8, 2, 7, 0, 3, 5, 9]
+const ROTOR_Z1_ALPHABET_IN: [u8; 10] = [5, 3, 0, 7, 1, 6, 9, 2, 4, 8];
+
+const ROTOR_Z1_ALPHABET_OUT: [u8; 10] = [2, 4, 7, 1, 8, 0, 5, 3, 9, 6];
+
+// [5, 8, 4, 1, 0, 9, 7, 6, 3, 2]
+const ROTOR_Z2_ALPHABET_IN: [u8; 10] = [4, 7, 3, 0, 9, 8, 6, 5, 2, 1];
+
+const ROTOR_Z2_ALPHABET_OUT: [u8; 10] = [3, 9, 8, 2, 0, 7, 6, 1, 5, 4];
+
+// [3, 5, 8, 1, 6, 2, 0, 7, 9, 4]
+const ROTOR_Z3_ALPHABET_IN: [u8; 10] = [2, 4, 7, 0, 5, 1, 9, 6, 8, 3];
+
+const ROTOR_Z3_ALPHABET_OUT: [u8; 10] = [3, 5, 0, 9, 1, 4, 7, 2, 8, 6];
+
+#[derive(Debug, Clone)]
 enum RotorTyreNotch {
+    None,
     Single(u8),
     Double([u8; 2]),
+    /// The many-notch rotors of the Enigma G and T, and custom rotors
+    Many(Cow<'static, [u8]>),
 }
 
-#[derive(Debug)]
+/// The wiring and notches of a rotor.
+#[derive(Debug, Clone)]
 pub struct RotorTyre {
     notch: RotorTyreNotch,
-    alphabet_in: &'static [u8; 26],
-    alphabet_out: &'static [u8; 26],
+    alphabet_in: Cow<'static, [u8]>,
+    alphabet_out: Cow<'static, [u8]>,
+}
+
+impl RotorTyre {
+    /// Create the wiring and notches of a custom rotor.
+    ///
+    /// * `wiring` - The key each contact is wired to, in contact order
+    /// * `notches` - The window letters at which the rotor turns over the
+    ///   next rotor. May be empty.
+    /// * `alphabet` - The keys of the machine
+    pub fn new(wiring: &str, notches: &str, alphabet: &Alphabet) -> Result<RotorTyre, String> {
+        let alphabet_in = alphabet.wiring(wiring)?;
+        let mut alphabet_out = vec![0; alphabet_in.len()];
+        for (contact, &key) in alphabet_in.iter().enumerate() {
+            alphabet_out[key as usize] = contact as u8;
+        }
+
+        let mut notch_vals = Vec::new();
+        for c in notches.chars() {
+            let notch = alphabet
+                .index_of(c)
+                .ok_or_else(|| format!("Invalid rotor notch: {}. {} is not a key", notches, c))?;
+            if !notch_vals.contains(&notch) {
+                notch_vals.push(notch);
+            }
+        }
+        notch_vals.sort_unstable();
+
+        Ok(RotorTyre {
+            notch: RotorTyreNotch::Many(Cow::Owned(notch_vals)),
+            alphabet_in: Cow::Owned(alphabet_in),
+            alphabet_out: Cow::Owned(alphabet_out),
+        })
+    }
+
+    /// The number of contacts, i.e., the size of the alphabet.
+    pub fn size(&self) -> usize {
+        self.alphabet_in.len()
+    }
+
+    /// The same rotor inserted back to front.
+    ///
+    /// Turning a rotor around swaps its two faces and mirrors every contact,
+    /// so contact `x` of the reversed rotor is wired to `-f⁻¹(-x)`. Positions,
+    /// ring settings and notches are mirrored the same way: a reversed rotor
+    /// at position `p` shows the letter at `-p` in its window and still
+    /// turns over when a notch letter shows.
+    pub fn reversed(&self) -> RotorTyre {
+        let size = self.alphabet_in.len();
+        let mirror = |val: usize| ((size - val) % size) as u8;
+
+        let alphabet_in: Vec<u8> = (0..size)
+            .map(|contact| mirror(self.alphabet_out[mirror(contact) as usize] as usize))
+            .collect();
+        let mut alphabet_out = vec![0; size];
+        for (contact, &key) in alphabet_in.iter().enumerate() {
+            alphabet_out[key as usize] = contact as u8;
+        }
+
+        let notches = match &self.notch {
+            RotorTyreNotch::None => Vec::new(),
+            RotorTyreNotch::Single(notch) => vec![*notch],
+            RotorTyreNotch::Double(notches) => notches.to_vec(),
+            RotorTyreNotch::Many(notches) => notches.to_vec(),
+        };
+        let mut notches: Vec<u8> = notches
+            .into_iter()
+            .map(|notch| mirror(notch as usize))
+            .collect();
+        notches.sort_unstable();
+
+        RotorTyre {
+            notch: RotorTyreNotch::Many(Cow::Owned(notches)),
+            alphabet_in: Cow::Owned(alphabet_in),
+            alphabet_out: Cow::Owned(alphabet_out),
+        }
+    }
 }
 
 const ROTOR_I: RotorTyre = RotorTyre {
     // Rollover when stepping from 'Q' to 'R' (16 -> 17)
     notch: RotorTyreNotch::Single(16),
-    alphabet_in: &ROTOR_1_ALPHABET_IN,
-    alphabet_out: &ROTOR_1_ALPHABET_OUT,
+    alphabet_in: Cow::Borrowed(&ROTOR_1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_1_ALPHABET_OUT),
 };
 
 const ROTOR_II: RotorTyre = RotorTyre {
     // Rollover when stepping from 'E' to 'F' (4 -> 5)
     notch: RotorTyreNotch::Single(4),
-    alphabet_in: &ROTOR_2_ALPHABET_IN,
-    alphabet_out: &ROTOR_2_ALPHABET_OUT,
+    alphabet_in: Cow::Borrowed(&ROTOR_2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_2_ALPHABET_OUT),
 };
 
 const ROTOR_III: RotorTyre = RotorTyre {
-    // Rollover when stepping from 'V' to 'W' (20 -> 21)
-    notch: RotorTyreNotch::Single(20),
-    alphabet_in: &ROTOR_3_ALPHABET_IN,
-    alphabet_out: &ROTOR_3_ALPHABET_OUT,
+    // Rollover when stepping from 'V' to 'W' (21 -> 22)
+    notch: RotorTyreNotch::Single(21),
+    alphabet_in: Cow::Borrowed(&ROTOR_3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_3_ALPHABET_OUT),
 };
 
 const ROTOR_IV: RotorTyre = RotorTyre {
-    // Rollover when stepping from 'J' to 'K' (8 -> 9)
-    notch: RotorTyreNotch::Single(8),
-    alphabet_in: &ROTOR_4_ALPHABET_IN,
-    alphabet_out: &ROTOR_4_ALPHABET_OUT,
+    // Rollover when stepping from 'J' to 'K' (9 -> 10)
+    notch: RotorTyreNotch::Single(9),
+    alphabet_in: Cow::Borrowed(&ROTOR_4_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_4_ALPHABET_OUT),
 };
 
 const ROTOR_V: RotorTyre = RotorTyre {
     // Rollover when stepping from 'Z' to 'A' (25 -> 0)
     notch: RotorTyreNotch::Single(25),
-    alphabet_in: &ROTOR_5_ALPHABET_IN,
-    alphabet_out: &ROTOR_5_ALPHABET_OUT,
+    alphabet_in: Cow::Borrowed(&ROTOR_5_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_5_ALPHABET_OUT),
 };
 
 const ROTOR_VI: RotorTyre = RotorTyre {
-    // Rollover when stepping from 'Z' to 'A' (25 -> 0) or 'M' to 'N' (11 -> 12)
-    notch: RotorTyreNotch::Double([25, 11]),
-    alphabet_in: &ROTOR_6_ALPHABET_IN,
-    alphabet_out: &ROTOR_6_ALPHABET_OUT,
+    // Rollover when stepping from 'Z' to 'A' (25 -> 0) or 'M' to 'N' (12 -> 13)
+    notch: RotorTyreNotch::Double([25, 12]),
+    alphabet_in: Cow::Borrowed(&ROTOR_6_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_6_ALPHABET_OUT),
 };
 
 const ROTOR_VII: RotorTyre = RotorTyre {
-    // Rollover when stepping from 'Z' to 'A' (25 -> 0) or 'M' to 'N' (11 -> 12)
-    notch: RotorTyreNotch::Double([25, 11]),
-    alphabet_in: &ROTOR_7_ALPHABET_IN,
-    alphabet_out: &ROTOR_7_ALPHABET_OUT,
+    // Rollover when stepping from 'Z' to 'A' (25 -> 0) or 'M' to 'N' (12 -> 13)
+    notch: RotorTyreNotch::Double([25, 12]),
+    alphabet_in: Cow::Borrowed(&ROTOR_7_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_7_ALPHABET_OUT),
 };
 
 const ROTOR_VIII: RotorTyre = RotorTyre {
-    // Rollover when stepping from 'Z' to 'A' (25 -> 0) or 'M' to 'N' (11 -> 12)
-    notch: RotorTyreNotch::Double([25, 11]),
-    alphabet_in: &ROTOR_8_ALPHABET_IN,
-    alphabet_out: &ROTOR_8_ALPHABET_OUT,
+    // Rollover when stepping from 'Z' to 'A' (25 -> 0) or 'M' to 'N' (12 -> 13)
+    notch: RotorTyreNotch::Double([25, 12]),
+    alphabet_in: Cow::Borrowed(&ROTOR_8_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_8_ALPHABET_OUT),
+};
+
+const ROTOR_BETA: RotorTyre = RotorTyre {
+    // The greek rotors sit beside the thin reflector and never step
+    notch: RotorTyreNotch::None,
+    alphabet_in: Cow::Borrowed(&ROTOR_BETA_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_BETA_ALPHABET_OUT),
+};
+
+const ROTOR_GAMMA: RotorTyre = RotorTyre {
+    notch: RotorTyreNotch::None,
+    alphabet_in: Cow::Borrowed(&ROTOR_GAMMA_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_GAMMA_ALPHABET_OUT),
+};
+
+const ROTOR_IK: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'Y' to 'Z' (24 -> 25)
+    notch: RotorTyreNotch::Single(24),
+    alphabet_in: Cow::Borrowed(&ROTOR_K1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_K1_ALPHABET_OUT),
+};
+
+const ROTOR_IIK: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'E' to 'F' (4 -> 5)
+    notch: RotorTyreNotch::Single(4),
+    alphabet_in: Cow::Borrowed(&ROTOR_K2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_K2_ALPHABET_OUT),
+};
+
+const ROTOR_IIIK: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'N' to 'O' (13 -> 14)
+    notch: RotorTyreNotch::Single(13),
+    alphabet_in: Cow::Borrowed(&ROTOR_K3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_K3_ALPHABET_OUT),
+};
+
+const ROTOR_ISK: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'Y' to 'Z' (24 -> 25)
+    notch: RotorTyreNotch::Single(24),
+    alphabet_in: Cow::Borrowed(&ROTOR_SK1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_SK1_ALPHABET_OUT),
+};
+
+const ROTOR_IISK: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'E' to 'F' (4 -> 5)
+    notch: RotorTyreNotch::Single(4),
+    alphabet_in: Cow::Borrowed(&ROTOR_SK2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_SK2_ALPHABET_OUT),
+};
+
+const ROTOR_IIISK: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'N' to 'O' (13 -> 14)
+    notch: RotorTyreNotch::Single(13),
+    alphabet_in: Cow::Borrowed(&ROTOR_SK3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_SK3_ALPHABET_OUT),
+};
+
+const ROTOR_IR: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'N' to 'O' (13 -> 14)
+    notch: RotorTyreNotch::Single(13),
+    alphabet_in: Cow::Borrowed(&ROTOR_R1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_R1_ALPHABET_OUT),
+};
+
+const ROTOR_IIR: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'E' to 'F' (4 -> 5)
+    notch: RotorTyreNotch::Single(4),
+    alphabet_in: Cow::Borrowed(&ROTOR_R2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_R2_ALPHABET_OUT),
+};
+
+const ROTOR_IIIR: RotorTyre = RotorTyre {
+    // Rollover when stepping from 'Y' to 'Z' (24 -> 25)
+    notch: RotorTyreNotch::Single(24),
+    alphabet_in: Cow::Borrowed(&ROTOR_R3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_R3_ALPHABET_OUT),
+};
+
+const ROTOR_IG: RotorTyre = RotorTyre {
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_IG_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_G1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_G1_ALPHABET_OUT),
+};
+
+const ROTOR_IIG: RotorTyre = RotorTyre {
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_IIG_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_G2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_G2_ALPHABET_OUT),
+};
+
+const ROTOR_IIIG: RotorTyre = RotorTyre {
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_IIIG_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_G3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_G3_ALPHABET_OUT),
+};
+
+const ROTOR_IG312: RotorTyre = RotorTyre {
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_IG_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_G312_1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_G312_1_ALPHABET_OUT),
+};
+
+const ROTOR_IIG312: RotorTyre = RotorTyre {
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_IIG_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_G312_2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_G312_2_ALPHABET_OUT),
+};
+
+const ROTOR_IIIG312: RotorTyre = RotorTyre {
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_IIIG_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_G312_3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_G312_3_ALPHABET_OUT),
+};
+
+const ROTOR_IT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of E, K, Q, W, Z
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T1_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T1_ALPHABET_OUT),
+};
+const ROTOR_IIT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of F, L, R, W, Z
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T2_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T2_ALPHABET_OUT),
+};
+const ROTOR_IIIT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of E, K, Q, W, Z
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T3_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T3_ALPHABET_OUT),
+};
+const ROTOR_IVT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of F, L, R, W, Z
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T4_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T4_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T4_ALPHABET_OUT),
+};
+const ROTOR_VT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of C, F, K, R, Y
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T5_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T5_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T5_ALPHABET_OUT),
+};
+const ROTOR_VIT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of E, I, M, Q, X
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T6_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T6_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T6_ALPHABET_OUT),
+};
+const ROTOR_VIIT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of C, F, K, R, Y
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T7_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T7_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T7_ALPHABET_OUT),
+};
+const ROTOR_VIIIT: RotorTyre = RotorTyre {
+    // Rollover when stepping from any of E, I, M, Q, X
+    notch: RotorTyreNotch::Many(Cow::Borrowed(&ROTOR_T8_NOTCHES)),
+    alphabet_in: Cow::Borrowed(&ROTOR_T8_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_T8_ALPHABET_OUT),
+};
+const ROTOR_IZ: RotorTyre = RotorTyre {
+    // Rollover when stepping from 9 to 0
+    notch: RotorTyreNotch::Single(8),
+    alphabet_in: Cow::Borrowed(&ROTOR_Z1_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_Z1_ALPHABET_OUT),
+};
+const ROTOR_IIZ: RotorTyre = RotorTyre {
+    // Rollover when stepping from 9 to 0
+    notch: RotorTyreNotch::Single(8),
+    alphabet_in: Cow::Borrowed(&ROTOR_Z2_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_Z2_ALPHABET_OUT),
+};
+const ROTOR_IIIZ: RotorTyre = RotorTyre {
+    // Rollover when stepping from 9 to 0
+    notch: RotorTyreNotch::Single(8),
+    alphabet_in: Cow::Borrowed(&ROTOR_Z3_ALPHABET_IN),
+    alphabet_out: Cow::Borrowed(&ROTOR_Z3_ALPHABET_OUT),
 };
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "RotorState", into = "RotorState")
+)]
 pub struct Rotor {
-    tyre: &'static RotorTyre,
+    tyre: Cow<'static, RotorTyre>,
     pos: u8,
     ring_loc: u8,
+    /// How far the wiring core is turned within the alphabet ring
+    core_offset: u8,
+    /// Whether the rotor is inserted back to front
+    reversed: bool,
     id: String,
 }
 
@@ -160,59 +718,134 @@ impl Rotor {
     }
 
     pub fn new_with_state(rotor_id: &str, pos: u8, ring_loc: u8) -> Self {
-        let tyre = match rotor_id {
-            "I" => &ROTOR_I,
-            "II" => &ROTOR_II,
-            "III" => &ROTOR_III,
-            "IV" => &ROTOR_IV,
-            "V" => &ROTOR_V,
-            "VI" => &ROTOR_VI,
-            "VII" => &ROTOR_VII,
-            "VIII" => &ROTOR_VIII,
-            _ => panic!("Invalid rotor identifier {}", rotor_id),
-        };
+        let tyre = tyre_from_id(rotor_id)
+            .unwrap_or_else(|| panic!("Invalid rotor identifier {}", rotor_id));
+        let size = tyre.alphabet_in.len() as u8;
 
         Rotor {
-            tyre,
-            pos: (pos).rem_euclid(26),
-            ring_loc: (ring_loc).rem_euclid(26),
+            tyre: Cow::Borrowed(tyre),
+            pos: pos.rem_euclid(size),
+            ring_loc: ring_loc.rem_euclid(size),
+            core_offset: 0,
+            reversed: false,
             id: rotor_id.to_string(),
         }
     }
 
     pub fn new_custom_rotor(tyre: &'static RotorTyre, pos: u8, ring_loc: u8) -> Self {
         Rotor {
-            tyre,
+            tyre: Cow::Borrowed(tyre),
             pos,
             ring_loc,
+            core_offset: 0,
+            reversed: false,
             id: "custom".to_string(),
         }
     }
 
+    /// Create a rotor from a custom [RotorTyre](RotorTyre), e.g., for an
+    /// alphabet other than `A` - `Z`.
+    ///
+    /// * `rotor_id` - The id reported by [id](Rotor::id)
+    /// * `tyre` - The wiring and notches
+    /// * `pos` - The start position, `0` for the first key
+    /// * `ring_loc` - The ring setting, `0` for the first key
+    pub fn new_custom(rotor_id: &str, tyre: RotorTyre, pos: u8, ring_loc: u8) -> Self {
+        let size = tyre.alphabet_in.len() as u8;
+        Rotor {
+            tyre: Cow::Owned(tyre),
+            pos: pos.rem_euclid(size),
+            ring_loc: ring_loc.rem_euclid(size),
+            core_offset: 0,
+            reversed: false,
+            id: rotor_id.to_string(),
+        }
+    }
+
+    /// The number of contacts, i.e., the size of the alphabet.
+    pub fn size(&self) -> usize {
+        self.tyre.alphabet_in.len()
+    }
+
+    /// Step the rotor by one position. The letters of a reversed rotor run
+    /// backwards, so its window letter goes down.
     pub fn rotate(&mut self) {
-        self.pos = (self.pos + 1).rem_euclid(26);
+        let size = self.size() as u8;
+        self.pos = match (self.reversed, self.pos) {
+            (true, 0) => size - 1,
+            (true, pos) => pos - 1,
+            (false, pos) => (pos + 1).rem_euclid(size),
+        };
     }
 
     pub fn will_step_next_rotor(&self) -> bool {
-        match self.tyre.notch {
-            RotorTyreNotch::Single(notch_index) => self.pos == notch_index,
-            RotorTyreNotch::Double([notch1, notch2]) => self.pos == notch1 || self.pos == notch2,
+        match &self.tyre.notch {
+            RotorTyreNotch::None => false,
+            RotorTyreNotch::Single(notch_index) => self.pos == *notch_index,
+            RotorTyreNotch::Double([notch1, notch2]) => self.pos == *notch1 || self.pos == *notch2,
+            RotorTyreNotch::Many(notches) => notches.contains(&self.pos),
+        }
+    }
+
+    /// The positions at which the rotor turns over the next rotor, `0` for
+    /// `A`.
+    pub fn notches(&self) -> Vec<u8> {
+        match &self.tyre.notch {
+            RotorTyreNotch::None => Vec::new(),
+            RotorTyreNotch::Single(notch_index) => vec![*notch_index],
+            RotorTyreNotch::Double(notches) => notches.to_vec(),
+            RotorTyreNotch::Many(notches) => notches.to_vec(),
         }
     }
 
     pub fn map_in(&self, input_val: u8) -> u8 {
-        // TODO: OMFG MY EYES!!
-        let trans = self.tyre.alphabet_in
-            [(input_val as i16 + self.pos as i16 - self.ring_loc as i16).rem_euclid(26) as usize]
-            as i16;
-        (trans - self.pos as i16 + self.ring_loc as i16).rem_euclid(26) as u8
+        if self.reversed {
+            // The faces are swapped and every contact mirrored
+            self.mirror(self.map(&self.tyre.alphabet_out, self.mirror(input_val)))
+        } else {
+            self.map(&self.tyre.alphabet_in, input_val)
+        }
     }
 
     pub fn map_out(&self, input_val: u8) -> u8 {
-        let trans = self.tyre.alphabet_out
-            [(input_val as i16 + self.pos as i16 - self.ring_loc as i16).rem_euclid(26) as usize]
-            as i16;
-        (trans - self.pos as i16 + self.ring_loc as i16).rem_euclid(26) as u8
+        if self.reversed {
+            self.mirror(self.map(&self.tyre.alphabet_in, self.mirror(input_val)))
+        } else {
+            self.map(&self.tyre.alphabet_out, input_val)
+        }
+    }
+
+    fn map(&self, alphabet: &[u8], input_val: u8) -> u8 {
+        // TODO: OMFG MY EYES!!
+        let size = self.size() as i16;
+        let offset = self.pos as i16 - self.ring_loc as i16 - self.core_offset as i16;
+        let trans = alphabet[(input_val as i16 + offset).rem_euclid(size) as usize] as i16;
+        (trans - offset).rem_euclid(size) as u8
+    }
+
+    fn mirror(&self, val: u8) -> u8 {
+        let size = self.size() as u8;
+        (size - val) % size
+    }
+
+    /// The rotor id, e.g., `III`, or `custom` for custom rotors.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The current rotor position, `0` for `A`.
+    pub fn position(&self) -> u8 {
+        self.pos
+    }
+
+    /// The letter shown in the rotor window, for the `A` - `Z` alphabet.
+    pub fn position_letter(&self) -> char {
+        (self.pos + b'A') as char
+    }
+
+    /// The ring setting, `0` for `A` (or `01`).
+    pub fn ring(&self) -> u8 {
+        self.ring_loc
     }
 
     pub fn set_rotor_settings(&mut self, new_pos: u8, new_ring_loc: u8) {
@@ -227,6 +860,123 @@ impl Rotor {
     pub fn set_rotor_ring_loc(&mut self, new_ring_loc: u8) {
         self.ring_loc = new_ring_loc;
     }
+
+    /// How far the wiring core is turned within the alphabet ring, `0` for
+    /// the factory fit.
+    pub fn core_offset(&self) -> u8 {
+        self.core_offset
+    }
+
+    /// Turn the wiring core within the alphabet ring.
+    ///
+    /// The notches are on the ring, so this has the same effect as adding
+    /// the offset to the ring setting.
+    pub fn set_core_offset(&mut self, core_offset: u8) {
+        self.core_offset = core_offset.rem_euclid(self.size() as u8);
+    }
+
+    /// Whether the rotor is inserted back to front.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Insert the rotor back to front, or the right way round again.
+    ///
+    /// A reversed rotor is wired like [RotorTyre::reversed](RotorTyre::reversed)
+    /// but keeps its window letters and ring setting: the window shows the
+    /// same letter, the letters count down as the rotor steps and it still
+    /// turns over when a notch letter shows.
+    pub fn set_reversed(&mut self, reversed: bool) {
+        self.reversed = reversed;
+    }
+}
+
+fn tyre_from_id(rotor_id: &str) -> Option<&'static RotorTyre> {
+    match rotor_id {
+        "I" => Some(&ROTOR_I),
+        "II" => Some(&ROTOR_II),
+        "III" => Some(&ROTOR_III),
+        "IV" => Some(&ROTOR_IV),
+        "V" => Some(&ROTOR_V),
+        "VI" => Some(&ROTOR_VI),
+        "VII" => Some(&ROTOR_VII),
+        "VIII" => Some(&ROTOR_VIII),
+        "Beta" => Some(&ROTOR_BETA),
+        "Gamma" => Some(&ROTOR_GAMMA),
+        "IK" => Some(&ROTOR_IK),
+        "IIK" => Some(&ROTOR_IIK),
+        "IIIK" => Some(&ROTOR_IIIK),
+        "ISK" => Some(&ROTOR_ISK),
+        "IISK" => Some(&ROTOR_IISK),
+        "IIISK" => Some(&ROTOR_IIISK),
+        "IR" => Some(&ROTOR_IR),
+        "IIR" => Some(&ROTOR_IIR),
+        "IIIR" => Some(&ROTOR_IIIR),
+        "IG" => Some(&ROTOR_IG),
+        "IIG" => Some(&ROTOR_IIG),
+        "IIIG" => Some(&ROTOR_IIIG),
+        "IG312" => Some(&ROTOR_IG312),
+        "IIG312" => Some(&ROTOR_IIG312),
+        "IIIG312" => Some(&ROTOR_IIIG312),
+        "IT" => Some(&ROTOR_IT),
+        "IIT" => Some(&ROTOR_IIT),
+        "IIIT" => Some(&ROTOR_IIIT),
+        "IVT" => Some(&ROTOR_IVT),
+        "VT" => Some(&ROTOR_VT),
+        "VIT" => Some(&ROTOR_VIT),
+        "VIIT" => Some(&ROTOR_VIIT),
+        "VIIIT" => Some(&ROTOR_VIIIT),
+        "IZ" => Some(&ROTOR_IZ),
+        "IIZ" => Some(&ROTOR_IIZ),
+        "IIIZ" => Some(&ROTOR_IIIZ),
+        _ => None,
+    }
+}
+
+/// Serialized form of a [Rotor](Rotor).
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct RotorState {
+    id: String,
+    position: u8,
+    ring: u8,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    core_offset: u8,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    reversed: bool,
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(val: &u8) -> bool {
+    *val == 0
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RotorState> for Rotor {
+    type Error = String;
+
+    fn try_from(state: RotorState) -> Result<Self, Self::Error> {
+        if tyre_from_id(&state.id).is_none() {
+            return Err(format!("Invalid rotor identifier {}", state.id));
+        }
+        let mut rotor = Rotor::new_with_state(&state.id, state.position, state.ring);
+        rotor.set_core_offset(state.core_offset);
+        rotor.set_reversed(state.reversed);
+        Ok(rotor)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Rotor> for RotorState {
+    fn from(rotor: Rotor) -> Self {
+        RotorState {
+            id: rotor.id,
+            position: rotor.pos,
+            ring: rotor.ring_loc,
+            core_offset: rotor.core_offset,
+            reversed: rotor.reversed,
+        }
+    }
 }
 
 impl fmt::Display for Rotor {
@@ -270,13 +1020,40 @@ mod tests {
     #[test]
     fn test_rotor_step() {
         let r = Rotor::new_with_state("I", 17, 0);
-        assert_eq!(false, r.will_step_next_rotor());
+        assert!(!r.will_step_next_rotor());
 
         let r = Rotor::new_with_state("I", 15, 0);
-        assert_eq!(false, r.will_step_next_rotor());
+        assert!(!r.will_step_next_rotor());
 
         let r = Rotor::new_with_state("I", 16, 0);
-        assert_eq!(true, r.will_step_next_rotor());
+        assert!(r.will_step_next_rotor());
+    }
+
+    #[test]
+    fn test_notch_positions() {
+        // The next rotor turns over when stepping away from these letters
+        for (id, notches) in [
+            ("I", "Q"),
+            ("II", "E"),
+            ("III", "V"),
+            ("IV", "J"),
+            ("V", "Z"),
+            ("VI", "MZ"),
+            ("VII", "MZ"),
+            ("VIII", "MZ"),
+        ] {
+            for pos in 0..26 {
+                let letter = (b'A' + pos) as char;
+                let r = Rotor::new_with_state(id, pos, 0);
+                assert_eq!(
+                    r.will_step_next_rotor(),
+                    notches.contains(letter),
+                    "{} at {}",
+                    id,
+                    letter
+                );
+            }
+        }
     }
 
     #[test]
@@ -288,28 +1065,76 @@ mod tests {
         };
 
         let r = Rotor::new_with_state("VI", (notch1 - 1).rem_euclid(26), 0);
-        assert_eq!(false, r.will_step_next_rotor());
+        assert!(!r.will_step_next_rotor());
 
         let r = Rotor::new_with_state("VI", notch1, 0);
-        assert_eq!(true, r.will_step_next_rotor());
+        assert!(r.will_step_next_rotor());
 
         let r = Rotor::new_with_state("VI", (notch1 + 1).rem_euclid(26), 0);
-        assert_eq!(false, r.will_step_next_rotor());
+        assert!(!r.will_step_next_rotor());
 
         let r = Rotor::new_with_state("VI", (notch2 - 1).rem_euclid(26), 0);
-        assert_eq!(false, r.will_step_next_rotor());
+        assert!(!r.will_step_next_rotor());
 
         let r = Rotor::new_with_state("VI", notch2, 0);
-        assert_eq!(true, r.will_step_next_rotor());
+        assert!(r.will_step_next_rotor());
 
         let r = Rotor::new_with_state("VI", (notch2 + 1).rem_euclid(26), 0);
-        assert_eq!(false, r.will_step_next_rotor());
+        assert!(!r.will_step_next_rotor());
+    }
+
+    #[test]
+    fn test_rotor_step_many_notches() {
+        for (id, count) in [("IG", 17), ("IIG", 15), ("IIIG", 11), ("IIIG312", 11)] {
+            let notches = Rotor::new(id).notches();
+            assert_eq!(notches.len(), count);
+
+            let stepping: Vec<u8> = (0..26)
+                .filter(|&pos| Rotor::new_with_state(id, pos, 0).will_step_next_rotor())
+                .collect();
+            assert_eq!(stepping, notches);
+        }
+
+        assert_eq!(Rotor::new("VI").notches(), vec![25, 12]);
+        assert!(Rotor::new("Beta").notches().is_empty());
     }
 
     #[test]
     fn test_verify_alphabet_mappings() {
         let rotors = vec![
-            ROTOR_I, ROTOR_II, ROTOR_III, ROTOR_IV, ROTOR_V, ROTOR_VI, ROTOR_VII, ROTOR_VIII,
+            ROTOR_I,
+            ROTOR_II,
+            ROTOR_III,
+            ROTOR_IV,
+            ROTOR_V,
+            ROTOR_VI,
+            ROTOR_VII,
+            ROTOR_VIII,
+            ROTOR_BETA,
+            ROTOR_GAMMA,
+            ROTOR_IK,
+            ROTOR_IIK,
+            ROTOR_IIIK,
+            ROTOR_ISK,
+            ROTOR_IISK,
+            ROTOR_IIISK,
+            ROTOR_IR,
+            ROTOR_IIR,
+            ROTOR_IIIR,
+            ROTOR_IG,
+            ROTOR_IIG,
+            ROTOR_IIIG,
+            ROTOR_IG312,
+            ROTOR_IIG312,
+            ROTOR_IIIG312,
+            ROTOR_IT,
+            ROTOR_IIT,
+            ROTOR_IIIT,
+            ROTOR_IVT,
+            ROTOR_VT,
+            ROTOR_VIT,
+            ROTOR_VIIT,
+            ROTOR_VIIIT,
         ];
 
         for rotor in rotors {
@@ -356,15 +1181,78 @@ mod tests {
 
     #[test]
     fn test_ring_loc_mappings_changes() {
-        let mut rotor = Rotor::new_with_state("I", 0, 1);
-        assert_eq!(rotor.map_in('A' as u8 - 65), 'K' as u8 - 65);
-        assert_eq!(rotor.map_in('M' as u8 - 65), 'U' as u8 - 65);
-        assert_eq!(rotor.map_in('Z' as u8 - 65), 'D' as u8 - 65);
-
-        let mut rotor = Rotor::new_with_state("I", 0, 9);
-        assert_eq!(rotor.map_in('A' as u8 - 65), 'D' as u8 - 65);
-        assert_eq!(rotor.map_in('M' as u8 - 65), 'O' as u8 - 65);
-        assert_eq!(rotor.map_in('Z' as u8 - 65), 'G' as u8 - 65);
+        let rotor = Rotor::new_with_state("I", 0, 1);
+        assert_eq!(rotor.map_in(b'A' - 65), b'K' - 65);
+        assert_eq!(rotor.map_in(b'M' - 65), b'U' - 65);
+        assert_eq!(rotor.map_in(b'Z' - 65), b'D' - 65);
+
+        let rotor = Rotor::new_with_state("I", 0, 9);
+        assert_eq!(rotor.map_in(b'A' - 65), b'D' - 65);
+        assert_eq!(rotor.map_in(b'M' - 65), b'O' - 65);
+        assert_eq!(rotor.map_in(b'Z' - 65), b'G' - 65);
+    }
+
+    #[test]
+    fn test_reversed_tyre_mirrors_the_rotor() {
+        let mirror = |val: u8| (26 - val) % 26;
+        for (pos, ring) in [(0, 0), (3, 0), (17, 5)] {
+            let rotor = Rotor::new_with_state("I", pos, ring);
+            let reversed = Rotor::new_custom("I", ROTOR_I.reversed(), mirror(pos), mirror(ring));
+            for val in 0..26 {
+                assert_eq!(reversed.map_in(val), mirror(rotor.map_out(mirror(val))));
+                assert_eq!(reversed.map_out(val), mirror(rotor.map_in(mirror(val))));
+            }
+        }
+
+        // I turns over at Q, position K of the mirrored contacts
+        assert_eq!(
+            Rotor::new_custom("I", ROTOR_I.reversed(), 0, 0).notches(),
+            vec![b'K' - b'A']
+        );
+        let twice = ROTOR_IV.reversed().reversed();
+        assert_eq!(twice.alphabet_in, ROTOR_IV.alphabet_in);
+    }
+
+    #[test]
+    fn test_core_offset_is_a_ring_setting() {
+        for (pos, ring, core) in [(0, 0, 1), (5, 3, 20), (25, 25, 25)] {
+            let mut rotor = Rotor::new_with_state("VI", pos, ring);
+            rotor.set_core_offset(core);
+            let equivalent = Rotor::new_with_state("VI", pos, (ring + core) % 26);
+            for val in 0..26 {
+                assert_eq!(rotor.map_in(val), equivalent.map_in(val));
+                assert_eq!(rotor.map_out(val), equivalent.map_out(val));
+            }
+            assert_eq!(rotor.notches(), equivalent.notches());
+        }
+    }
+
+    #[test]
+    fn test_reversed_orientation() {
+        let mirror = |val: u8| (26 - val) % 26;
+        let mut rotor = Rotor::new_with_state("II", 7, 2);
+        rotor.set_reversed(true);
+        let mirrored = Rotor::new_custom("II", ROTOR_II.reversed(), mirror(7), mirror(2));
+        for val in 0..26 {
+            assert_eq!(rotor.map_in(val), mirrored.map_in(val));
+            assert_eq!(rotor.map_out(rotor.map_in(val)), val);
+        }
+
+        // The window letters count down and II still turns over at E
+        assert_eq!(rotor.position_letter(), 'H');
+        rotor.set_rotor_pos(5);
+        rotor.rotate();
+        assert_eq!(rotor.position_letter(), 'E');
+        assert!(rotor.will_step_next_rotor());
+        rotor.set_rotor_pos(0);
+        rotor.rotate();
+        assert_eq!(rotor.position_letter(), 'Z');
+
+        rotor.set_reversed(false);
+        assert_eq!(
+            rotor.map_in(0),
+            Rotor::new_with_state("II", 25, 2).map_in(0)
+        );
     }
 
     #[test]
@@ -0,0 +1,293 @@
+//! Enigma stepping schedule module.
+//!
+//! A [StepSchedule](StepSchedule) answers questions about how a machine's
+//! rotors will move without encrypting anything: when the middle and left
+//! rotors turn over, how many key presses it takes until the rotor positions
+//! repeat, and which positions the machine passes through around a given key
+//! press.
+//!
+//! The machine is stepped by its own
+//! [stepping mechanism](crate::stepping), so the double step, the twin
+//! notches of rotors `VI` - `VIII`, the fixed greek rotor of the M4 and the
+//! moving reflector of the Enigma G are all accounted for.
+//!
+//! ```
+//! use enigma::machine::EnigmaMachine;
+//! use enigma::schedule::StepSchedule;
+//!
+//! let em = EnigmaMachine::builder()
+//!     .reflector("B")
+//!     .rotors(vec![
+//!         ("III".to_string(), 0, 0),
+//!         ("II".to_string(), 0, 0),
+//!         ("I".to_string(), 0, 0),
+//!     ])
+//!     .try_build()
+//!     .unwrap();
+//!
+//! let schedule = StepSchedule::from_machine(&em);
+//! // With the double step the middle rotor goes round in 25 turns of the
+//! // right rotor rather than 26
+//! assert_eq!(schedule.period().length, 26 * 25 * 26);
+//!
+//! // The right rotor steps from V to W on key press 22, turning over the
+//! // middle rotor
+//! let turnover = &schedule.turnovers(26)[0];
+//! assert_eq!(turnover.key_press, 22);
+//! assert_eq!((turnover.from, turnover.to), ('A', 'B'));
+//! ```
+use crate::machine::EnigmaMachine;
+use crate::trace::RotorStep;
+
+/// A rotor other than the right (fast) rotor moving on a key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turnover {
+    /// The key press, counting from `1`.
+    pub key_press: usize,
+    /// The rotor, counting from `0` for the right (fast) rotor.
+    pub rotor: usize,
+    /// The rotor id, e.g., `II`.
+    pub rotor_id: String,
+    /// The window letter before the key press.
+    pub from: char,
+    /// The window letter after the key press.
+    pub to: char,
+    /// Whether the rotor was turned over by the rotor to its right or
+    /// stepped itself in a double step.
+    pub step: RotorStep,
+}
+
+/// How the rotor positions of a machine repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    /// The key presses before the machine first reaches a position it comes
+    /// back to. The double step means some positions can only be set by
+    /// hand, e.g., `AEA` for rotors `I`, `II` and `III` as the middle rotor
+    /// never waits at its notch, so this isn't always `0`.
+    pub lead_in: usize,
+    /// The key presses after which the positions repeat.
+    pub length: usize,
+}
+
+/// The rotor window letters after a key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledPositions {
+    /// The key press, counting from `1`. `0` is the starting position.
+    pub key_press: usize,
+    /// The window letters, right (fast) rotor first.
+    pub positions: Vec<char>,
+}
+
+/// The stepping schedule of a machine from its current positions.
+#[derive(Debug, Clone)]
+pub struct StepSchedule {
+    machine: EnigmaMachine,
+}
+
+impl StepSchedule {
+    /// The schedule of a machine from its current positions. The machine
+    /// itself isn't moved.
+    pub fn from_machine(machine: &EnigmaMachine) -> StepSchedule {
+        StepSchedule {
+            machine: machine.clone(),
+        }
+    }
+
+    /// The rotor and reflector positions, which is all that stepping
+    /// depends on.
+    fn state(machine: &EnigmaMachine) -> (Vec<u8>, u8) {
+        (machine.positions(), machine.reflector().position())
+    }
+
+    /// Every time a rotor other than the right rotor moves in the next
+    /// `count` key presses, in order.
+    pub fn turnovers(&self, count: usize) -> Vec<Turnover> {
+        let mut machine = self.machine.clone();
+        let mut turnovers = Vec::new();
+
+        for key_press in 1..=count {
+            let before = machine.position_letters();
+            let steps = machine.step_rotors_traced();
+            let after = machine.position_letters();
+
+            for (rotor, &step) in steps.iter().enumerate().skip(1) {
+                if step != RotorStep::Stationary {
+                    turnovers.push(Turnover {
+                        key_press,
+                        rotor,
+                        rotor_id: machine.rotors()[rotor].id().to_string(),
+                        from: before[rotor],
+                        to: after[rotor],
+                        step,
+                    });
+                }
+            }
+        }
+
+        turnovers
+    }
+
+    /// The period of the rotor positions, found with Brent's cycle
+    /// detection. This takes as many key presses as the lead-in and a couple
+    /// of periods, e.g., around 50,000 for three rotors.
+    pub fn period(&self) -> Period {
+        let start = StepSchedule::state(&self.machine);
+
+        // Find the length by racing a hare against a tortoise that jumps
+        // ahead to it at every power of two
+        let mut tortoise = start.clone();
+        let mut hare = self.machine.clone();
+        hare.step_rotors_traced();
+        let mut power = 1;
+        let mut length = 1;
+        while tortoise != StepSchedule::state(&hare) {
+            if power == length {
+                tortoise = StepSchedule::state(&hare);
+                power *= 2;
+                length = 0;
+            }
+            hare.step_rotors_traced();
+            length += 1;
+        }
+
+        // Then the lead-in by walking two machines `length` apart until they
+        // meet
+        let mut tortoise = self.machine.clone();
+        let mut hare = self.machine.clone();
+        for _ in 0..length {
+            hare.step_rotors_traced();
+        }
+        let mut lead_in = 0;
+        while StepSchedule::state(&tortoise) != StepSchedule::state(&hare) {
+            tortoise.step_rotors_traced();
+            hare.step_rotors_traced();
+            lead_in += 1;
+        }
+
+        Period { lead_in, length }
+    }
+
+    /// The positions from `radius` key presses before `key_press` to
+    /// `radius` key presses after it. Key press `0` is the starting position
+    /// and the positions of key press `n` are the ones its letter is
+    /// encrypted at.
+    ///
+    /// Key presses past the lead-in and the first period are wrapped around
+    /// the [period](StepSchedule::period), so any key press is cheap to look
+    /// up.
+    pub fn positions_around(&self, key_press: usize, radius: usize) -> Vec<ScheduledPositions> {
+        let first = key_press.saturating_sub(radius);
+        let last = key_press.saturating_add(radius);
+
+        let mut skip = first;
+        if skip > 0 {
+            let period = self.period();
+            if skip >= period.lead_in + period.length {
+                skip = period.lead_in + (skip - period.lead_in) % period.length;
+            }
+        }
+
+        let mut machine = self.machine.clone();
+        for _ in 0..skip {
+            machine.step_rotors_traced();
+        }
+
+        let mut positions = Vec::with_capacity(last - first + 1);
+        for key_press in first..=last {
+            if key_press > first {
+                machine.step_rotors_traced();
+            }
+            positions.push(ScheduledPositions {
+                key_press,
+                positions: machine.position_letters(),
+            });
+        }
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn schedule(cfg: &str) -> StepSchedule {
+        StepSchedule::from_machine(&Config::parse(cfg.to_string()).unwrap().build_machine())
+    }
+
+    #[test]
+    fn test_double_step() {
+        let schedule = schedule("B;I-A-A,II-D-A,III-U-A");
+        let turnovers = schedule.turnovers(3);
+        let summary: Vec<(usize, &str, char, char, RotorStep)> = turnovers
+            .iter()
+            .map(|t| (t.key_press, t.rotor_id.as_str(), t.from, t.to, t.step))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (2, "II", 'D', 'E', RotorStep::Stepped),
+                (3, "II", 'E', 'F', RotorStep::DoubleStepped),
+                (3, "I", 'A', 'B', RotorStep::Stepped),
+            ]
+        );
+
+        assert_eq!(
+            schedule.period(),
+            Period {
+                lead_in: 0,
+                length: 16900
+            }
+        );
+        let positions: Vec<Vec<char>> = schedule
+            .positions_around(1, 1)
+            .into_iter()
+            .map(|p| p.positions)
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                vec!['U', 'D', 'A'],
+                vec!['V', 'D', 'A'],
+                vec!['W', 'E', 'A']
+            ]
+        );
+    }
+
+    #[test]
+    fn test_periods() {
+        for (cfg, lead_in, length) in [
+            // AEA can only be set by hand
+            ("B;I-A-A,II-E-A,III-A-A", 1, 26 * 25 * 26),
+            // The notch of the left rotor does nothing
+            ("B;VI-A-A,II-A-A,III-A-A", 0, 26 * 25 * 26),
+            // Twin notches on the right and middle rotors
+            ("B;I-A-A,VI-A-A,III-A-A", 0, 26 * 12 * 26),
+            ("B;I-A-A,VII-A-A,VI-A-A", 1, 26 * 12 * 13),
+            ("B;VI-A-A,VII-A-A,VIII-A-A", 1, 26 * 12 * 13),
+            // The greek rotor never moves
+            ("B-thin;Beta-A-A,I-A-A,II-A-A,III-A-A", 0, 26 * 25 * 26),
+            // No double step, but the reflector moves
+            ("UKWG-A-A;IG-A-A,IIG-A-A,IIIG-A-A", 0, 26 * 26 * 26 * 26),
+        ] {
+            assert_eq!(
+                schedule(cfg).period(),
+                Period { lead_in, length },
+                "{}",
+                cfg
+            );
+        }
+    }
+
+    #[test]
+    fn test_positions_wrap_around_period() {
+        let schedule = schedule("B;I-A-A,II-A-A,III-A-A");
+        let period = schedule.period();
+        let wrapped = schedule.positions_around(period.lead_in + 7 + 3 * period.length, 2);
+        let direct = schedule.positions_around(period.lead_in + 7, 2);
+        assert_eq!(wrapped[0].key_press, period.lead_in + 5 + 3 * period.length);
+        for (a, b) in wrapped.iter().zip(direct.iter()) {
+            assert_eq!(a.positions, b.positions);
+        }
+    }
+}
@@ -0,0 +1,473 @@
+//! Enigma scrambler module.
+//!
+//! Every component of the machine maps a contact to another contact on the
+//! way to the reflector and back. The [Scrambler](Scrambler) trait gives them
+//! a common shape, so a [Pipeline](Pipeline) can chain any number of them
+//! without a fixed layout: a second plugboard, an extra stator or an
+//! [Uhr](crate::uhr::Uhr) in front of the entry wheel are just another stage.
+//!
+//! ```
+//! use enigma::entry_wheel::EntryWheel;
+//! use enigma::plugboard::PlugBoard;
+//! use enigma::reflector::Reflector;
+//! use enigma::rotor::Rotor;
+//! use enigma::scrambler::Pipeline;
+//!
+//! let mut pipeline = Pipeline::new();
+//! pipeline.push(PlugBoard::new_with_mapping(vec![('A', 'B')]).unwrap()).unwrap();
+//! pipeline.push(EntryWheel::identity()).unwrap();
+//! // A stator that never moves, in front of the three rotors
+//! pipeline.push(EntryWheel::qwertz()).unwrap();
+//! pipeline.push(Rotor::new("III")).unwrap();
+//! pipeline.push(Rotor::new("II")).unwrap();
+//! pipeline.push(Rotor::new("I")).unwrap();
+//! pipeline.push(Reflector::new("B")).unwrap();
+//!
+//! let cipher = pipeline.translate_text("HELLOWORLD".chars());
+//! assert_eq!(cipher.len(), 10);
+//!
+//! // The rotors of the Enigma Z have ten contacts
+//! assert!(pipeline.push(Rotor::new("IZ")).is_err());
+//! ```
+use std::fmt;
+
+use crate::alphabet::Alphabet;
+use crate::entry_wheel::EntryWheel;
+use crate::machine::EnigmaMachine;
+use crate::plugboard::PlugBoard;
+use crate::reflector::Reflector;
+use crate::rotor::Rotor;
+use crate::stepping::{Stepping, SteppingMechanism};
+use crate::uhr::Uhr;
+
+/// A stage of the signal path.
+pub trait Scrambler: fmt::Debug + Send + Sync {
+    /// Map a contact on the way towards the reflector.
+    fn forward(&self, input_val: u8) -> u8;
+
+    /// Map a contact on the way back from the reflector.
+    fn backward(&self, input_val: u8) -> u8;
+
+    /// The number of contacts, or `None` for a stage that leaves the
+    /// contacts of any alphabet in place, e.g., an empty plugboard.
+    fn size(&self) -> Option<usize>;
+
+    /// Move for a key press.
+    ///
+    /// * `carry` - Whether the stage before this one turns it over. The
+    ///   first stage always receives `true`.
+    ///
+    /// Returns whether the next stage is turned over. Stages that never move
+    /// pass the carry on unchanged, so they can sit between two rotors.
+    fn step(&mut self, carry: bool) -> bool {
+        carry
+    }
+
+    /// The stage as a rotor, for pipelines stepped by a
+    /// [Stepping](crate::stepping::Stepping). `None` for any other stage.
+    fn as_rotor_mut(&mut self) -> Option<&mut Rotor> {
+        None
+    }
+
+    /// The stage as a reflector, for pipelines stepped by a
+    /// [Stepping](crate::stepping::Stepping). `None` for any other stage.
+    fn as_reflector_mut(&mut self) -> Option<&mut Reflector> {
+        None
+    }
+}
+
+impl Scrambler for PlugBoard {
+    fn forward(&self, input_val: u8) -> u8 {
+        self.map(input_val)
+    }
+
+    fn backward(&self, input_val: u8) -> u8 {
+        self.map(input_val)
+    }
+
+    fn size(&self) -> Option<usize> {
+        if self.pairs().is_empty() {
+            None
+        } else {
+            Some(26)
+        }
+    }
+}
+
+impl Scrambler for Uhr {
+    fn forward(&self, input_val: u8) -> u8 {
+        self.map_in(input_val)
+    }
+
+    fn backward(&self, input_val: u8) -> u8 {
+        self.map_out(input_val)
+    }
+
+    fn size(&self) -> Option<usize> {
+        Some(26)
+    }
+}
+
+impl Scrambler for EntryWheel {
+    fn forward(&self, input_val: u8) -> u8 {
+        self.map_in(input_val)
+    }
+
+    fn backward(&self, input_val: u8) -> u8 {
+        self.map_out(input_val)
+    }
+
+    fn size(&self) -> Option<usize> {
+        Some(EntryWheel::size(self))
+    }
+}
+
+/// Rotors step with the lever stepping of
+/// [LeverStepping](crate::stepping::LeverStepping): on a carry, or on their
+/// own notch along with the next rotor. The pipeline moves its last rotor,
+/// the left one, only on a carry, as it has no rotor to turn over.
+impl Scrambler for Rotor {
+    fn forward(&self, input_val: u8) -> u8 {
+        self.map_in(input_val)
+    }
+
+    fn backward(&self, input_val: u8) -> u8 {
+        self.map_out(input_val)
+    }
+
+    fn size(&self) -> Option<usize> {
+        Some(Rotor::size(self))
+    }
+
+    fn step(&mut self, carry: bool) -> bool {
+        let at_notch = self.will_step_next_rotor();
+        if carry || at_notch {
+            self.rotate();
+        }
+        at_notch
+    }
+
+    fn as_rotor_mut(&mut self) -> Option<&mut Rotor> {
+        Some(self)
+    }
+}
+
+/// Reflectors never move and end the carry.
+impl Scrambler for Reflector {
+    fn forward(&self, input_val: u8) -> u8 {
+        self.map(input_val)
+    }
+
+    fn backward(&self, input_val: u8) -> u8 {
+        self.map(input_val)
+    }
+
+    fn size(&self) -> Option<usize> {
+        Some(Reflector::size(self))
+    }
+
+    fn step(&mut self, _carry: bool) -> bool {
+        false
+    }
+
+    fn as_reflector_mut(&mut self) -> Option<&mut Reflector> {
+        Some(self)
+    }
+}
+
+/// A machine built from a list of stages.
+///
+/// A key press steps every stage in order, or the rotors and reflector with
+/// the pipeline's [stepping](Pipeline::set_stepping) when it has one, then
+/// sends the signal forward through every stage. The last stage turns the
+/// signal around, like a reflector, and it travels backward through the other
+/// stages in reverse order. Every stage has as many contacts as the
+/// alphabet has keys.
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    alphabet: Alphabet,
+    stages: Vec<Box<dyn Scrambler>>,
+    stepping: Option<Stepping>,
+}
+
+impl Pipeline {
+    /// An empty pipeline for the `A` - `Z` alphabet.
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// An empty pipeline for another alphabet.
+    pub fn with_alphabet(alphabet: Alphabet) -> Pipeline {
+        Pipeline {
+            alphabet,
+            stages: Vec::new(),
+            stepping: None,
+        }
+    }
+
+    /// The stages of a machine in its current state: the plugboard (or
+    /// Uhr), the entry wheel, the rotors from right to left and the
+    /// reflector. The pipeline is stepped by the machine's stepping.
+    pub fn from_machine(machine: &EnigmaMachine) -> Pipeline {
+        // The components of a machine always fit its alphabet
+        let mut stages: Vec<Box<dyn Scrambler>> = Vec::new();
+        match machine.uhr() {
+            Some(uhr) => stages.push(Box::new(uhr.clone())),
+            None => stages.push(Box::new(machine.plugboard().clone())),
+        }
+        stages.push(Box::new(machine.entry_wheel().clone()));
+        for rotor in machine.rotors() {
+            stages.push(Box::new(rotor.clone()));
+        }
+        stages.push(Box::new(machine.reflector().clone()));
+
+        Pipeline {
+            alphabet: machine.alphabet().clone(),
+            stages,
+            stepping: Some(machine.stepping().clone()),
+        }
+    }
+
+    /// Step the rotor and reflector stages with a stepping mechanism rather
+    /// than stage by stage. The rotors are handed to it in pipeline order,
+    /// along with the first reflector.
+    pub fn set_stepping(&mut self, stepping: Stepping) {
+        self.stepping = Some(stepping);
+    }
+
+    /// The stepping mechanism, if the pipeline has one.
+    pub fn stepping(&self) -> Option<&Stepping> {
+        self.stepping.as_ref()
+    }
+
+    /// Add a stage after the existing ones. Fails if the stage doesn't fit
+    /// the alphabet.
+    pub fn push<S: Scrambler + 'static>(&mut self, stage: S) -> Result<(), String> {
+        self.check_size(&stage)?;
+        self.stages.push(Box::new(stage));
+        Ok(())
+    }
+
+    /// Add a stage before the stage at `index`, e.g., `0` for a stage in
+    /// front of everything else. Fails if the stage doesn't fit the
+    /// alphabet.
+    ///
+    /// Panics if `index` is past the end of the pipeline.
+    pub fn insert<S: Scrambler + 'static>(&mut self, index: usize, stage: S) -> Result<(), String> {
+        self.check_size(&stage)?;
+        self.stages.insert(index, Box::new(stage));
+        Ok(())
+    }
+
+    fn check_size(&self, stage: &dyn Scrambler) -> Result<(), String> {
+        match stage.size() {
+            Some(size) if size != self.alphabet.len() => Err(format!(
+                "The stage has {} contacts but the alphabet has {} keys",
+                size,
+                self.alphabet.len()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The stages, starting at the keyboard.
+    pub fn stages(&self) -> &[Box<dyn Scrambler>] {
+        &self.stages
+    }
+
+    /// The keys of the machine.
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    fn step(&mut self) {
+        let stepping = match &self.stepping {
+            Some(stepping) => stepping,
+            None => {
+                // The left rotor has no rotor to turn over, so only a carry
+                // moves it
+                let left = self
+                    .stages
+                    .iter_mut()
+                    .rposition(|stage| stage.as_rotor_mut().is_some());
+                let mut carry = true;
+                for (i, stage) in self.stages.iter_mut().enumerate() {
+                    if Some(i) == left {
+                        if carry {
+                            stage.as_rotor_mut().unwrap().rotate();
+                        }
+                        carry = false;
+                    } else {
+                        carry = stage.step(carry);
+                    }
+                }
+                return;
+            }
+        };
+
+        // The mechanism wants the rotors side by side, so step copies and
+        // put them back
+        let mut rotors: Vec<Rotor> = self
+            .stages
+            .iter_mut()
+            .filter_map(|stage| stage.as_rotor_mut().map(|rotor| rotor.clone()))
+            .collect();
+        let mut reflector = self
+            .stages
+            .iter_mut()
+            .find_map(|stage| stage.as_reflector_mut().map(|reflector| reflector.clone()))
+            .unwrap_or_default();
+        stepping.step(&mut rotors, &mut reflector);
+
+        let mut rotors = rotors.into_iter();
+        let mut reflector = Some(reflector);
+        for stage in self.stages.iter_mut() {
+            if let Some(rotor) = stage.as_rotor_mut() {
+                *rotor = rotors.next().unwrap();
+            } else if let Some(stage) = stage.as_reflector_mut() {
+                if let Some(reflector) = reflector.take() {
+                    *stage = reflector;
+                }
+            }
+        }
+    }
+
+    /// Translate a single key, stepping the stages first. Characters outside
+    /// the alphabet, or any key of an empty pipeline, are returned
+    /// unmodified without stepping.
+    pub fn translate(&mut self, input: char) -> char {
+        let mut val = match self.alphabet.index_of(input) {
+            Some(val) if !self.stages.is_empty() => val,
+            _ => return input,
+        };
+
+        self.step();
+        let (turn, stages) = self.stages.split_last().unwrap();
+
+        for stage in stages {
+            val = stage.forward(val);
+        }
+        val = turn.forward(val);
+        for stage in stages.iter().rev() {
+            val = stage.backward(val);
+        }
+
+        self.alphabet.letter(val)
+    }
+
+    pub fn translate_text(&mut self, chars: impl Iterator<Item = char>) -> String {
+        chars.map(|c| self.translate(c)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_matches_enigma_machine() {
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(30);
+        for cfg in [
+            "B;I-A-A,II-A-A,III-A-A;A-B,C-D",
+            "C;VI-Q-C,VIII-E-X,VII-U-D;Q-Z,W-E,R-T,Y-U,I-O,P-A",
+            "UKWK-B-A;IIIK-A-A,IIK-A-A,IK-A-A",
+            // The left rotor sits at its own notch and must not step itself
+            "B;I-Q-A,II-A-A,III-A-A",
+            // M4 with the left rotor of the three at its notch, next to the
+            // greek rotor that never moves
+            "B-thin;Beta-C-A,I-Q-A,II-E-A,III-V-A;A-B",
+            // G with odometer stepping and a moving reflector
+            "UKWG-C-B;IG-Z-A,IIG-Y-A,IIIG-X-A",
+        ] {
+            let mut em = Config::parse(cfg.to_string()).unwrap().build_machine();
+            let mut pipeline = Pipeline::from_machine(&em);
+            assert_eq!(pipeline.stages().len(), em.rotors().len() + 3);
+            assert_eq!(pipeline.stepping(), Some(em.stepping()));
+            assert_eq!(
+                pipeline.translate_text(text.chars()),
+                em.translate_text(text.chars())
+            );
+        }
+    }
+
+    #[test]
+    fn test_extra_stages() {
+        let cfg = Config::parse("B;I-A-A,II-A-A,III-A-A".to_string()).unwrap();
+        let em = cfg.clone().build_machine();
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG";
+
+        let mut pipeline = Pipeline::from_machine(&em);
+        // A second plugboard and a stator between the right and middle rotor
+        pipeline
+            .insert(0, PlugBoard::new_with_mapping(vec![('E', 'X')]).unwrap())
+            .unwrap();
+        pipeline.insert(4, EntryWheel::qwertz()).unwrap();
+        let cipher = pipeline.translate_text(text.chars());
+
+        let mut pipeline = Pipeline::from_machine(&em);
+        pipeline
+            .insert(0, PlugBoard::new_with_mapping(vec![('E', 'X')]).unwrap())
+            .unwrap();
+        pipeline.insert(4, EntryWheel::qwertz()).unwrap();
+        assert_eq!(pipeline.translate_text(cipher.chars()), text);
+
+        // The stator passes the turnover of the right rotor on
+        let mut pipeline = Pipeline::from_machine(&em);
+        pipeline.insert(3, EntryWheel::identity()).unwrap();
+        let mut em = cfg.build_machine();
+        let text = text.repeat(10);
+        assert_eq!(
+            pipeline.translate_text(text.chars()),
+            em.translate_text(text.chars())
+        );
+    }
+
+    #[test]
+    fn test_stage_by_stage_stepping() {
+        // Without a stepping the left rotor, sitting at its notch, must not
+        // step itself
+        for cfg in ["B;I-Q-A,II-A-A,III-A-A", "B;I-Q-A,II-E-A,III-V-A;A-B"] {
+            let mut em = Config::parse(cfg.to_string()).unwrap().build_machine();
+            let mut pipeline = Pipeline::new();
+            pipeline.push(em.plugboard().clone()).unwrap();
+            pipeline.push(em.entry_wheel().clone()).unwrap();
+            for rotor in em.rotors() {
+                pipeline.push(rotor.clone()).unwrap();
+            }
+            pipeline.push(em.reflector().clone()).unwrap();
+
+            let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(30);
+            assert_eq!(
+                pipeline.translate_text(text.chars()),
+                em.translate_text(text.chars())
+            );
+        }
+    }
+
+    #[test]
+    fn test_stage_sizes() {
+        let mut pipeline = Pipeline::new();
+        assert!(pipeline.push(Rotor::new("IZ")).is_err());
+        assert!(pipeline.push(EntryWheel::straight(&Alphabet::z())).is_err());
+        assert!(pipeline.push(Rotor::new("I")).is_ok());
+        assert!(pipeline.insert(0, Reflector::new("UKWZ")).is_err());
+        assert_eq!(pipeline.stages().len(), 1);
+
+        let mut pipeline = Pipeline::with_alphabet(Alphabet::z());
+        assert!(pipeline.push(PlugBoard::new()).is_ok());
+        assert!(pipeline
+            .push(PlugBoard::new_with_mapping(vec![('A', 'B')]).unwrap())
+            .is_err());
+        assert!(pipeline.push(Rotor::new("I")).is_err());
+        assert!(pipeline.push(Rotor::new("IZ")).is_ok());
+        assert!(pipeline.insert(1, Reflector::new("B")).is_err());
+    }
+
+    #[test]
+    fn test_empty_pipeline() {
+        let mut pipeline = Pipeline::new();
+        assert_eq!(pipeline.translate('A'), 'A');
+        assert_eq!(pipeline.translate('1'), '1');
+    }
+}
@@ -0,0 +1,573 @@
+//! Enigma operator session module.
+//!
+//! A [Session](Session) keeps a single [EnigmaMachine](EnigmaMachine) alive
+//! while its settings are changed the way an operator would change them:
+//! turning rotors, moving ring settings and adding or removing plugs. Every
+//! change (including translating text, which steps the rotors) can be undone
+//! and the session can be reset to the settings it started with.
+//!
+//! Rotor settings are given in the same order as
+//! [EnigmaMachineBuilder::rotors](crate::machine::EnigmaMachineBuilder::rotors),
+//! i.e., the right (fast) rotor first.
+//!
+//! ```
+//! use enigma::session::Session;
+//!
+//! let mut session = Session::new(
+//!     "B",
+//!     vec![
+//!         ("I".to_string(), 0, 0),
+//!         ("II".to_string(), 0, 0),
+//!         ("III".to_string(), 0, 0),
+//!     ],
+//!     vec![('A', 'B')],
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(session.translate_text("AAAA"), "WUPG");
+//! session.undo();
+//! assert_eq!(session.translate_text("AAAA"), "WUPG");
+//! ```
+//!
+//! Only the last [MAX_UNDO](MAX_UNDO) changes are kept for undoing.
+//!
+//! With the `serde` feature a session is serialized with its current, initial
+//! and undo history settings, so a restored session can still be undone and
+//! reset.
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::alphabet::Alphabet;
+use crate::config::{Config, UhrConfig, REFLECTOR_IDS, ROTOR_IDS};
+use crate::entry_wheel::EntryWheel;
+use crate::machine::EnigmaMachine;
+use crate::model::EnigmaModel;
+use crate::plugboard::PlugBoard;
+use crate::stepping::Stepping;
+use crate::uhr::Uhr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Settings {
+    reflector_id: String,
+    /// Position and ring setting of a settable reflector
+    #[cfg_attr(feature = "serde", serde(default))]
+    reflector_state: (u8, u8),
+    rotors: Vec<(String, u8, u8)>,
+    plugs: Vec<(char, char)>,
+    /// Position of an Uhr whose cables are the plugs, `a` plug first
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    uhr: Option<u8>,
+    /// Model the machine is built as
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    model: Option<EnigmaModel>,
+    /// Entry wheel other than the one of the reflector's model
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    entry_wheel: Option<EntryWheel>,
+    /// Stepping other than that of the reflector's model
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    stepping: Option<Stepping>,
+}
+
+/// The most changes a [Session](Session) can undo.
+pub const MAX_UNDO: usize = 1000;
+
+/// A long running machine whose settings can be changed and undone.
+#[derive(Debug)]
+pub struct Session {
+    settings: Settings,
+    initial: Settings,
+    history: VecDeque<Settings>,
+    machine: EnigmaMachine,
+}
+
+impl Session {
+    /// Create a new session.
+    ///
+    /// * `reflector_id` - The reflector id
+    /// * `rotors` - Rotor id, position and ring setting of each rotor, right
+    ///   rotor first
+    /// * `plugs` - Plugboard cable connections
+    pub fn new(
+        reflector_id: &str,
+        rotors: Vec<(String, u8, u8)>,
+        plugs: Vec<(char, char)>,
+    ) -> Result<Session, String> {
+        Session::from_settings(Settings {
+            reflector_id: reflector_id.to_string(),
+            reflector_state: (0, 0),
+            rotors,
+            plugs: normalize_plugs(plugs),
+            uhr: None,
+            model: None,
+            entry_wheel: None,
+            stepping: None,
+        })
+    }
+
+    fn from_settings(settings: Settings) -> Result<Session, String> {
+        if settings.rotors.is_empty() {
+            return Err("At least one rotor is required".to_string());
+        }
+
+        let machine = build(&settings)?;
+
+        Ok(Session {
+            initial: settings.clone(),
+            settings,
+            history: VecDeque::new(),
+            machine,
+        })
+    }
+
+    /// Create a new session from a parsed configuration string.
+    pub fn from_config(cfg: Config) -> Result<Session, String> {
+        let plugs = cfg
+            .plugboard_config
+            .map(|pb| pb.plugboard_maps)
+            .unwrap_or_default();
+
+        // The order of the Uhr cables matters
+        let uhr = cfg.uhr_config.map(|uhrcfg| uhrcfg.position);
+        let plugs = match uhr {
+            Some(_) => plugs,
+            None => normalize_plugs(plugs),
+        };
+
+        Session::from_settings(Settings {
+            reflector_id: cfg.reflector_config.reflector_id,
+            reflector_state: (cfg.reflector_config.position, cfg.reflector_config.ring),
+            rotors: cfg.rotor_config.rotor_ids,
+            plugs,
+            uhr,
+            model: cfg.model,
+            entry_wheel: cfg.entry_wheel,
+            stepping: cfg.stepping,
+        })
+    }
+
+    /// Translate text, stepping the rotors as it goes.
+    pub fn translate_text(&mut self, text: &str) -> String {
+        self.checkpoint();
+        self.machine.translate_text(text.chars())
+    }
+
+    /// Set every rotor position, right rotor first.
+    pub fn set_positions(&mut self, positions: &[u8]) -> Result<(), String> {
+        self.check_rotor_count(positions.len())?;
+
+        let size = self.machine.alphabet().len();
+        let mut settings = self.current();
+        for (rotor, &pos) in settings.rotors.iter_mut().zip(positions) {
+            rotor.1 = (pos as usize % size) as u8;
+        }
+        self.apply(settings)
+    }
+
+    /// Set every ring setting, right rotor first.
+    pub fn set_rings(&mut self, rings: &[u8]) -> Result<(), String> {
+        self.check_rotor_count(rings.len())?;
+
+        let size = self.machine.alphabet().len();
+        let mut settings = self.current();
+        for (rotor, &ring) in settings.rotors.iter_mut().zip(rings) {
+            rotor.2 = (ring as usize % size) as u8;
+        }
+        self.apply(settings)
+    }
+
+    /// Connect two letters on the plugboard.
+    pub fn add_plug(&mut self, in1: char, in2: char) -> Result<(), String> {
+        let (in1, in2) = (in1.to_ascii_uppercase(), in2.to_ascii_uppercase());
+        if in1 == in2 {
+            return Err(format!("Can't plug {} into itself", in1));
+        }
+
+        let mut settings = self.current();
+        settings.plugs.push((in1, in2));
+        settings.plugs = normalize_plugs(settings.plugs);
+        self.apply(settings)
+    }
+
+    /// Remove the cable connecting two letters on the plugboard.
+    pub fn remove_plug(&mut self, in1: char, in2: char) -> Result<(), String> {
+        let plug = normalize_plugs(vec![(in1, in2)])[0];
+
+        let mut settings = self.current();
+        let before = settings.plugs.len();
+        settings.plugs.retain(|&p| p != plug);
+        if settings.plugs.len() == before {
+            return Err(format!("No plug connects {} and {}", plug.0, plug.1));
+        }
+        self.apply(settings)
+    }
+
+    /// Return to the settings the session started with.
+    ///
+    /// The reset itself can be undone.
+    pub fn reset(&mut self) {
+        let initial = self.initial.clone();
+        // The initial settings were validated when the session was created
+        self.apply(initial).unwrap();
+    }
+
+    /// Undo the last change. Returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(settings) => {
+                self.machine = build(&settings).unwrap();
+                self.settings = settings;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The current rotor positions, right rotor first.
+    pub fn positions(&self) -> Vec<u8> {
+        self.machine.positions()
+    }
+
+    /// The letters of the machine's keyboard.
+    pub fn alphabet(&self) -> &Alphabet {
+        self.machine.alphabet()
+    }
+
+    /// The current settings as an **enigma** configuration string.
+    pub fn config_string(&self) -> String {
+        self.config().to_string()
+    }
+
+    /// The current settings.
+    pub fn config(&self) -> Config {
+        let settings = self.current();
+        let (position, ring) = settings.reflector_state;
+        let mut cfg = Config::new(&settings.reflector_id, settings.rotors, settings.plugs)
+            .with_reflector_state(position, ring);
+        cfg.uhr_config = settings.uhr.map(|position| UhrConfig { position });
+        cfg.model = settings.model;
+        cfg.entry_wheel = settings.entry_wheel;
+        cfg.stepping = settings.stepping;
+        cfg
+    }
+
+    /// Settings with the rotor (and stepping reflector) positions the
+    /// machine has stepped to.
+    fn current(&self) -> Settings {
+        let mut settings = self.settings.clone();
+        for (rotor, pos) in settings.rotors.iter_mut().zip(self.positions()) {
+            rotor.1 = pos;
+        }
+        settings.reflector_state.0 = self.machine.reflector().position();
+        settings
+    }
+
+    fn checkpoint(&mut self) {
+        if self.history.len() >= MAX_UNDO {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.current());
+    }
+
+    fn apply(&mut self, settings: Settings) -> Result<(), String> {
+        let machine = build(&settings)?;
+        self.checkpoint();
+        self.settings = settings;
+        self.machine = machine;
+        Ok(())
+    }
+
+    fn check_rotor_count(&self, count: usize) -> Result<(), String> {
+        if count != self.settings.rotors.len() {
+            return Err(format!(
+                "Expected {} rotor settings, received {}",
+                self.settings.rotors.len(),
+                count
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Serialized form of a [Session](Session).
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SessionState {
+    settings: Settings,
+    initial: Settings,
+    history: VecDeque<Settings>,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Session {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SessionState {
+            settings: self.current(),
+            initial: self.initial.clone(),
+            history: self.history.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Session {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Session, D::Error> {
+        let mut state = SessionState::deserialize(deserializer)?;
+        let excess = state.history.len().saturating_sub(MAX_UNDO);
+        state.history.drain(..excess);
+
+        // Undo and reset rebuild the machine without checking, so every
+        // stored setting has to be valid up front.
+        for settings in state.history.iter().chain([&state.initial]) {
+            build(settings).map_err(serde::de::Error::custom)?;
+        }
+        let machine = build(&state.settings).map_err(serde::de::Error::custom)?;
+
+        Ok(Session {
+            settings: state.settings,
+            initial: state.initial,
+            history: state.history,
+            machine,
+        })
+    }
+}
+
+fn build(settings: &Settings) -> Result<EnigmaMachine, String> {
+    // The builder panics on unknown ids and only reports invalid plugboards
+    // on stderr so check everything up front.
+    if !REFLECTOR_IDS.contains(&settings.reflector_id.as_str()) {
+        return Err(format!("Invalid reflector id: {}", settings.reflector_id));
+    }
+    if let Some((id, _, _)) = settings
+        .rotors
+        .iter()
+        .find(|(id, _, _)| !ROTOR_IDS.contains(&id.as_str()))
+    {
+        return Err(format!("Invalid rotor id: {}", id));
+    }
+
+    let (position, ring) = settings.reflector_state;
+    let mut builder = EnigmaMachine::builder()
+        .reflector_with_state(&settings.reflector_id, position, ring)
+        .rotors(settings.rotors.clone());
+    if let Some(model) = settings.model {
+        builder = builder.model(model);
+    }
+    if let Some(entry_wheel) = &settings.entry_wheel {
+        builder = builder.entry_wheel(entry_wheel.clone());
+    }
+    if let Some(stepping) = &settings.stepping {
+        builder = builder.stepping(stepping.clone());
+    }
+    match settings.uhr {
+        Some(uhr_position) => builder.uhr(Uhr::new(settings.plugs.clone(), uhr_position)?),
+        None => {
+            PlugBoard::new_with_mapping(settings.plugs.clone())?;
+            builder.plugboard(settings.plugs.clone())
+        }
+    }
+    .try_build()
+}
+
+/// Upper case each plug with the letters in alphabetical order.
+fn normalize_plugs(plugs: Vec<(char, char)>) -> Vec<(char, char)> {
+    plugs
+        .into_iter()
+        .map(|(a, b)| {
+            let (a, b) = (a.to_ascii_uppercase(), b.to_ascii_uppercase());
+            if a <= b {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session::new(
+            "B",
+            vec![
+                ("I".to_string(), 0, 0),
+                ("II".to_string(), 0, 0),
+                ("III".to_string(), 0, 0),
+            ],
+            vec![('A', 'B')],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_translate_steps_and_undoes() {
+        let mut s = session();
+        assert_eq!(s.translate_text("AAAA"), "WUPG");
+        assert_eq!(s.positions(), vec![4, 0, 0]);
+        assert_eq!(s.translate_text("A"), "N");
+
+        assert!(s.undo());
+        assert_eq!(s.positions(), vec![4, 0, 0]);
+        assert!(s.undo());
+        assert_eq!(s.positions(), vec![0, 0, 0]);
+        assert!(!s.undo());
+    }
+
+    #[test]
+    fn test_set_positions_and_rings() {
+        let mut s = session();
+        assert!(s.set_positions(&[1, 2]).is_err());
+        s.set_positions(&[4, 0, 0]).unwrap();
+        assert_eq!(s.translate_text("A"), "N");
+
+        s.set_rings(&[1, 2, 3]).unwrap();
+        assert_eq!(s.config_string(), "B;III-A-D,II-A-C,I-F-B;a-b");
+
+        s.set_positions(&[27, 0, 0]).unwrap();
+        assert_eq!(s.positions(), vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut s = session();
+        for _ in 0..MAX_UNDO + 5 {
+            s.translate_text("A");
+        }
+        let mut undone = 0;
+        while s.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO);
+        assert_eq!(s.positions(), vec![5, 0, 0]);
+    }
+
+    #[test]
+    fn test_plugs() {
+        let mut s = session();
+        assert!(s.add_plug('c', 'C').is_err());
+        assert!(s.add_plug('B', 'C').is_err());
+        s.add_plug('d', 'c').unwrap();
+        assert_eq!(s.config_string(), "B;III-A-A,II-A-A,I-A-A;a-b,c-d");
+
+        assert!(s.remove_plug('E', 'F').is_err());
+        s.remove_plug('B', 'A').unwrap();
+        assert_eq!(s.config_string(), "B;III-A-A,II-A-A,I-A-A;c-d");
+
+        assert!(s.undo());
+        assert_eq!(s.config_string(), "B;III-A-A,II-A-A,I-A-A;a-b,c-d");
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut s = session();
+        s.translate_text("HELLO");
+        s.add_plug('X', 'Y').unwrap();
+        s.reset();
+        assert_eq!(s.config_string(), "B;III-A-A,II-A-A,I-A-A;a-b");
+
+        assert!(s.undo());
+        assert_eq!(s.config_string(), "B;III-A-A,II-A-A,I-F-A;a-b,x-y");
+    }
+
+    #[test]
+    fn test_config_string_round_trips() {
+        let cfg = Config::parse("C;IV-Q-B,V-E-Z,I-A-M;q-w,e-r".to_string()).unwrap();
+        let s = Session::from_config(cfg).unwrap();
+        assert_eq!(s.config_string(), "C;IV-Q-B,V-E-Z,I-A-M;q-w,e-r");
+
+        // Uhr cables keep their order and the a plug first
+        let uhr = "B;I-A-A,II-A-A,III-A-A;b-a,c-d,e-f,g-h,i-j,k-l,m-n,o-p,q-r,s-t;UHR-07";
+        let mut s = Session::from_config(Config::parse(uhr.to_string()).unwrap()).unwrap();
+        assert_eq!(s.config_string(), uhr);
+        assert!(s.add_plug('U', 'V').is_err());
+    }
+
+    #[test]
+    fn test_keeps_entry_wheel_and_stepping() {
+        let mut em = EnigmaMachine::builder()
+            .entry_wheel(EntryWheel::qwertz())
+            .stepping(Stepping::Odometer)
+            .reflector("B")
+            .rotors(vec![
+                ("I".to_string(), 0, 0),
+                ("II".to_string(), 0, 0),
+                ("III".to_string(), 0, 0),
+            ])
+            .try_build()
+            .unwrap();
+        let mut s = Session::from_config(em.config()).unwrap();
+
+        // Changing a setting rebuilds the machine
+        s.add_plug('X', 'Y').unwrap();
+        s.remove_plug('X', 'Y').unwrap();
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG".repeat(30);
+        assert_eq!(s.translate_text(&text), em.translate_text(text.chars()));
+        assert_eq!(s.config().entry_wheel, Some(EntryWheel::qwertz()));
+        assert_eq!(s.config().stepping, Some(Stepping::Odometer));
+    }
+
+    #[test]
+    fn test_keeps_model() {
+        let cfg = Config::parse("UKWK;IIIK-A-A,IIK-A-A,IK-A-A".to_string()).unwrap();
+        let mut s = Session::from_config(cfg.clone()).unwrap();
+        assert!(s.add_plug('A', 'B').is_ok());
+
+        // The Enigma K has no plugboard
+        let mut s = Session::from_config(cfg.with_model(EnigmaModel::K)).unwrap();
+        assert!(s.add_plug('A', 'B').is_err());
+        assert_eq!(s.config().model, Some(EnigmaModel::K));
+    }
+
+    #[test]
+    fn test_enigma_z() {
+        let cfg = Config::parse("UKWZ;IIIZ-1-1,IIZ-1-1,IZ-1-1".to_string()).unwrap();
+        let mut s = Session::from_config(cfg).unwrap();
+        assert_eq!(s.alphabet(), &Alphabet::z());
+        let cipher = s.translate_text("0815");
+        s.reset();
+        assert_eq!(s.translate_text(&cipher), "0815");
+
+        s.set_positions(&[12, 0, 1]).unwrap();
+        assert_eq!(s.config_string(), "UKWZ;IIIZ-2-1,IIZ-1-1,IZ-3-1");
+        assert!(s.add_plug('A', 'B').is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut s = session();
+        s.translate_text("HELLO");
+        s.add_plug('X', 'Y').unwrap();
+
+        let json = serde_json::to_string(&s).unwrap();
+        let mut restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.config_string(), s.config_string());
+        assert_eq!(restored.translate_text("WORLD"), s.translate_text("WORLD"));
+
+        assert!(restored.undo());
+        assert!(restored.undo());
+        assert_eq!(restored.config_string(), "B;III-A-A,II-A-A,I-F-A;a-b");
+        restored.reset();
+        assert_eq!(restored.config_string(), "B;III-A-A,II-A-A,I-A-A;a-b");
+
+        let invalid = json.replace("\"reflector_id\":\"B\"", "\"reflector_id\":\"Q\"");
+        assert!(serde_json::from_str::<Session>(&invalid).is_err());
+    }
+}
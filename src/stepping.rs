@@ -0,0 +1,274 @@
+//! Enigma stepping mechanism module.
+//!
+//! A [SteppingMechanism](SteppingMechanism) moves the rotors (and, on the
+//! Enigma G, the reflector) before each key press is encrypted. The
+//! mechanisms of the real machines are selected with
+//! [Stepping](Stepping), which is also how a machine stores its mechanism
+//! when serialized.
+//!
+//! Custom stepping rules can be tried by implementing the trait and passing
+//! [Stepping::custom](Stepping::custom) to
+//! [EnigmaMachineBuilder::stepping](crate::machine::EnigmaMachineBuilder::stepping).
+//!
+//! ```
+//! use enigma::machine::EnigmaMachine;
+//! use enigma::reflector::Reflector;
+//! use enigma::rotor::Rotor;
+//! use enigma::stepping::{Stepping, SteppingMechanism};
+//!
+//! /// Steps every rotor on every key press.
+//! #[derive(Debug)]
+//! struct AllAtOnce;
+//!
+//! impl SteppingMechanism for AllAtOnce {
+//!     fn step(&self, rotors: &mut [Rotor], _reflector: &mut Reflector) {
+//!         rotors.iter_mut().for_each(|rotor| rotor.rotate());
+//!     }
+//! }
+//!
+//! let mut em = EnigmaMachine::builder()
+//!     .stepping(Stepping::custom(AllAtOnce))
+//!     .reflector("B")
+//!     .rotors(vec![
+//!         ("III".to_string(), 0, 0),
+//!         ("II".to_string(), 0, 0),
+//!         ("I".to_string(), 0, 0),
+//!     ])
+//!     .try_build()
+//!     .unwrap();
+//!
+//! em.translate_text("AAA".chars());
+//! assert_eq!(em.position_letters(), vec!['D', 'D', 'D']);
+//! ```
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::reflector::Reflector;
+use crate::rotor::Rotor;
+
+/// Moves the rotors of a machine for a single key press.
+pub trait SteppingMechanism: fmt::Debug + Send + Sync {
+    /// Step the rotors and reflector as a key press would.
+    ///
+    /// * `rotors` - The rotors, right (fast) rotor first
+    /// * `reflector` - The reflector
+    fn step(&self, rotors: &mut [Rotor], reflector: &mut Reflector);
+}
+
+/// Ratchet and pawl stepping with the double step of the middle rotor.
+///
+/// Every rotor has a pawl. The right rotor steps on every key press. Any
+/// other rotor steps when the rotor to its right turns over, or when it is
+/// sitting on its own notch, in which case it steps itself along with the
+/// rotor to its left. There's no rotor to the left of the left rotor, so its
+/// own notch does nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeverStepping;
+
+impl SteppingMechanism for LeverStepping {
+    fn step(&self, rotors: &mut [Rotor], _reflector: &mut Reflector) {
+        let mut rotation_triggered = false;
+        let last = rotors.len().saturating_sub(1);
+
+        for (i, rotor) in rotors.iter_mut().enumerate() {
+            if i == 0 || (i < last && rotor.will_step_next_rotor()) || rotation_triggered {
+                rotation_triggered = rotor.will_step_next_rotor();
+                rotor.rotate();
+            }
+        }
+    }
+}
+
+/// The lever stepping of the Kriegsmarine M4.
+///
+/// There are only three pawls, so the fourth ("greek") rotor beside the thin
+/// reflector never moves.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedGreekStepping;
+
+impl SteppingMechanism for FixedGreekStepping {
+    fn step(&self, rotors: &mut [Rotor], reflector: &mut Reflector) {
+        let pawls = rotors.len().min(3);
+        LeverStepping.step(&mut rotors[..pawls], reflector);
+    }
+}
+
+/// Stepping that works like an odometer.
+///
+/// The right rotor steps on every key press and any other rotor only steps
+/// when the rotor to its right turns over. There is no double step.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OdometerStepping;
+
+impl OdometerStepping {
+    /// Step the rotors, returning whether the left rotor turned over.
+    fn step_rotors(rotors: &mut [Rotor]) -> bool {
+        let mut rotation_triggered = true;
+
+        for rotor in rotors.iter_mut() {
+            if !rotation_triggered {
+                break;
+            }
+            rotation_triggered = rotor.will_step_next_rotor();
+            rotor.rotate();
+        }
+
+        rotation_triggered
+    }
+}
+
+impl SteppingMechanism for OdometerStepping {
+    fn step(&self, rotors: &mut [Rotor], _reflector: &mut Reflector) {
+        OdometerStepping::step_rotors(rotors);
+    }
+}
+
+/// The cog-wheel stepping of the Enigma G.
+///
+/// The rotors step like an [odometer](OdometerStepping), and the left rotor
+/// carries on into a settable reflector.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GearStepping;
+
+impl SteppingMechanism for GearStepping {
+    fn step(&self, rotors: &mut [Rotor], reflector: &mut Reflector) {
+        if OdometerStepping::step_rotors(rotors) && reflector.is_settable() {
+            reflector.rotate();
+        }
+    }
+}
+
+/// How the rotors of a machine are stepped.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Stepping {
+    /// See [LeverStepping](LeverStepping)
+    #[default]
+    Lever,
+    /// See [FixedGreekStepping](FixedGreekStepping)
+    FixedGreek,
+    /// See [OdometerStepping](OdometerStepping)
+    Odometer,
+    /// See [GearStepping](GearStepping)
+    Gear,
+    /// A user supplied mechanism. Machines using one can't be serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<dyn SteppingMechanism>),
+}
+
+impl Stepping {
+    /// Use a custom stepping mechanism.
+    pub fn custom<M: SteppingMechanism + 'static>(mechanism: M) -> Stepping {
+        Stepping::Custom(Arc::new(mechanism))
+    }
+}
+
+impl SteppingMechanism for Stepping {
+    fn step(&self, rotors: &mut [Rotor], reflector: &mut Reflector) {
+        match self {
+            Stepping::Lever => LeverStepping.step(rotors, reflector),
+            Stepping::FixedGreek => FixedGreekStepping.step(rotors, reflector),
+            Stepping::Odometer => OdometerStepping.step(rotors, reflector),
+            Stepping::Gear => GearStepping.step(rotors, reflector),
+            Stepping::Custom(mechanism) => mechanism.step(rotors, reflector),
+        }
+    }
+}
+
+/// Built in mechanisms are equal by kind, custom mechanisms only to
+/// themselves.
+impl PartialEq for Stepping {
+    fn eq(&self, other: &Stepping) -> bool {
+        match (self, other) {
+            (Stepping::Custom(a), Stepping::Custom(b)) => Arc::ptr_eq(a, b),
+            (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        }
+    }
+}
+
+impl Eq for Stepping {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotors(settings: &[(&str, u8)]) -> Vec<Rotor> {
+        settings
+            .iter()
+            .map(|&(id, pos)| Rotor::new_with_state(id, pos, 0))
+            .collect()
+    }
+
+    fn letters(rotors: &[Rotor]) -> String {
+        rotors.iter().map(|rotor| rotor.position_letter()).collect()
+    }
+
+    #[test]
+    fn test_lever_double_step() {
+        // Right rotor first: ADU -> ADV -> AEW -> BFX from the front
+        let mut rotors = rotors(&[("III", 20), ("II", 3), ("I", 0)]);
+        let mut reflector = Reflector::new("B");
+        for expected in ["VDA", "WEA", "XFB"] {
+            Stepping::Lever.step(&mut rotors, &mut reflector);
+            assert_eq!(letters(&rotors), expected);
+        }
+    }
+
+    #[test]
+    fn test_lever_left_rotor_at_notch() {
+        // I sits at its notch Q, but there's no pawl to push it along
+        let mut rotors = rotors(&[("III", 0), ("II", 0), ("I", 16)]);
+        let mut reflector = Reflector::new("B");
+        Stepping::Lever.step(&mut rotors, &mut reflector);
+        assert_eq!(letters(&rotors), "BAQ");
+    }
+
+    #[test]
+    fn test_fixed_greek_wheel() {
+        let mut reflector = Reflector::new("B-thin");
+        let mut lever = rotors(&[("III", 21), ("II", 4), ("I", 16), ("IV", 9)]);
+        let mut greek = rotors(&[("III", 21), ("II", 4), ("I", 16), ("Beta", 0)]);
+        let mut fixed = rotors(&[("III", 21), ("II", 4), ("I", 16), ("IV", 9)]);
+
+        Stepping::Lever.step(&mut lever, &mut reflector);
+        Stepping::FixedGreek.step(&mut greek, &mut reflector);
+        Stepping::FixedGreek.step(&mut fixed, &mut reflector);
+
+        // With a pawl for each rotor I turns over the fourth rotor
+        assert_eq!(letters(&lever), "WFRK");
+        assert_eq!(letters(&greek), "WFRA");
+        assert_eq!(letters(&fixed), "WFRJ");
+    }
+
+    #[test]
+    fn test_odometer_and_gear() {
+        let mut odometer = rotors(&[("III", 21), ("II", 4), ("I", 16)]);
+        let mut gear = rotors(&[("III", 21), ("II", 4), ("I", 16)]);
+        let mut reflector = Reflector::new("UKWG");
+
+        Stepping::Odometer.step(&mut odometer, &mut reflector);
+        assert_eq!(letters(&odometer), "WFR");
+        assert_eq!(reflector.position(), 0);
+
+        Stepping::Gear.step(&mut gear, &mut reflector);
+        assert_eq!(letters(&gear), "WFR");
+        assert_eq!(reflector.position(), 1);
+
+        // No double step for II sitting at F
+        Stepping::Odometer.step(&mut odometer, &mut reflector);
+        assert_eq!(letters(&odometer), "XFR");
+    }
+
+    #[test]
+    fn test_equality() {
+        let custom = Stepping::custom(LeverStepping);
+        assert_eq!(custom, custom.clone());
+        assert_ne!(custom, Stepping::custom(LeverStepping));
+        assert_ne!(custom, Stepping::Lever);
+        assert_eq!(Stepping::Gear, Stepping::Gear);
+        assert_ne!(Stepping::Gear, Stepping::Odometer);
+    }
+}
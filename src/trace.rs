@@ -0,0 +1,58 @@
+//! Enigma signal trace module.
+//!
+//! A [SignalTrace](SignalTrace) records everything that happens to a single
+//! key press as it travels through an
+//! [EnigmaMachine](crate::machine::EnigmaMachine). See
+//! [EnigmaMachine::translate_traced](crate::machine::EnigmaMachine::translate_traced).
+//!
+//! Rotor related values are listed in the same order as the machine's rotors,
+//! i.e., the right (fast) rotor first. The exception is
+//! [backward](SignalTrace::backward) which follows the signal on its way back
+//! from the reflector and so starts at the left rotor.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How a rotor moved when a key was pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RotorStep {
+    /// The rotor didn't move.
+    Stationary,
+    /// The rotor was stepped by its pawl. This is every key press for the
+    /// right rotor or a turnover from the rotor to its right.
+    Stepped,
+    /// The rotor stepped itself along with the rotor to its left because it
+    /// was sitting at its own notch (the "double step").
+    DoubleStepped,
+}
+
+/// The path of a single key press through the machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SignalTrace {
+    /// The key that was pressed.
+    pub input: char,
+    /// Rotor window letters before the key press.
+    pub positions_before: Vec<char>,
+    /// Rotor window letters after stepping, i.e., the positions used to
+    /// encrypt this letter.
+    pub positions_after: Vec<char>,
+    /// How each rotor moved.
+    pub steps: Vec<RotorStep>,
+    /// The letter leaving the plugboard on its way in.
+    pub plugboard_in: char,
+    /// The contact reached through the entry wheel on the way in.
+    pub entry_in: char,
+    /// The letter leaving each rotor on the way to the reflector.
+    pub forward: Vec<char>,
+    /// The letter leaving the reflector.
+    pub reflector: char,
+    /// The letter leaving each rotor on the way back from the reflector,
+    /// starting with the left rotor.
+    pub backward: Vec<char>,
+    /// The key reached through the entry wheel on the way out.
+    pub entry_out: char,
+    /// The letter leaving the plugboard on its way out. This is the lamp
+    /// that lights.
+    pub output: char,
+}
@@ -0,0 +1,395 @@
+//! Typex module.
+//!
+//! The [TypexMachine](TypexMachine) emulates the British Typex, built from
+//! the same [Rotor](Rotor) primitives as the Enigma. A Typex has five
+//! rotors. The two on the right are stators: they are set to a position when
+//! the key is set up but never move. The three on the left step like the
+//! rotors of an Enigma, including the double step of the middle rotor, but
+//! every rotor has several notches.
+//!
+//! Any rotor can be inserted back to front, which turns it into a different
+//! wiring. A reversed rotor still shows the letter of its position in the
+//! window, but its letters run backwards as it steps.
+//!
+//! With a reflector the signal travels through the rotors and back, and the
+//! machine is reciprocal like an Enigma. Without one the signal leaves the
+//! left rotor straight to the printer, so the same key encrypts to different
+//! letters in each direction and the machine has to be switched to
+//! [TypexMode::Decipher](TypexMode::Decipher) to read a message.
+//!
+//! The wirings of the service rotors were never published. The rotors `A` -
+//! `H` and the reflector provided here are example wirings to experiment
+//! with, and rotors of your own can be added with
+//! [custom_rotor](TypexMachineBuilder::custom_rotor).
+//!
+//! ```
+//! use enigma::typex::{TypexMachine, TypexMode};
+//!
+//! // Right (stator) rotor first, the left (slow) rotor last
+//! let builder = TypexMachine::builder()
+//!     .rotor("A", 0, 0, false)
+//!     .rotor("B", 4, 0, true)
+//!     .rotor("C", 2, 0, false)
+//!     .rotor("D", 21, 0, false)
+//!     .rotor("E", 10, 0, true)
+//!     .without_reflector();
+//!
+//! let mut typex = builder.clone().try_build().unwrap();
+//! let cipher = typex.translate_text("ATTACKATDAWN".chars());
+//!
+//! let mut typex = builder.mode(TypexMode::Decipher).try_build().unwrap();
+//! assert_eq!(typex.translate_text(cipher.chars()), "ATTACKATDAWN");
+//! ```
+use crate::alphabet::Alphabet;
+use crate::reflector::Reflector;
+use crate::rotor::{Rotor, RotorTyre};
+
+/// The number of rotors in a Typex.
+pub const TYPEX_ROTOR_COUNT: usize = 5;
+
+/// The number of stators, the rotors on the right that never step.
+pub const TYPEX_STATORS: usize = 2;
+
+/// Example rotors as `(id, wiring, notches)`. The notches are the window
+/// letters at which a rotor turns over the next one.
+pub const TYPEX_ROTORS: [(&str, &str, &str); 8] = [
+    ("A", "FKTYWCQXGZAOIVJHPLBDMRNUES", "CLOUY"),
+    ("B", "XSGFZYWIUVQKBPTDLNOHAJCREM", "AIKMO"),
+    ("C", "GJZTSIFDEWRBYLUMAXVPKHOQNC", "DINRX"),
+    ("D", "ISBGFQJATNVMCEODYXLKUZHRWP", "BEJOY"),
+    ("E", "NVCQMEBAGOIZLFWXRPHUDYSTJK", "GIKNS"),
+    ("F", "JOMKEPQIFADGCZBRVSLYTXNUWH", "FJRYZ"),
+    ("G", "BYQOSDJNLWPFAGIKEHTURMVXCZ", "DLOUY"),
+    ("H", "SPQYDFKJEOXWZRBUMVNCGHTIAL", "BCFMS"),
+];
+
+/// Example reflector wiring.
+pub const TYPEX_REFLECTOR: &str = "ITNPVRYLAMXHJCWDSFQBZEOKGU";
+
+/// Which way a Typex without a reflector is switched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TypexMode {
+    /// Keys enter at the stators and letters leave the left rotor.
+    #[default]
+    Encipher,
+    /// Keys enter at the left rotor and letters leave the stators.
+    Decipher,
+}
+
+#[derive(Clone)]
+pub struct TypexMachineBuilder {
+    rotors: Vec<Result<Rotor, String>>,
+    reflector: Option<Reflector>,
+    mode: TypexMode,
+}
+
+impl Default for TypexMachineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypexMachineBuilder {
+    pub fn new() -> TypexMachineBuilder {
+        let reflector = Reflector::new_custom("Typex", TYPEX_REFLECTOR, &Alphabet::latin());
+        TypexMachineBuilder {
+            rotors: Vec::new(),
+            reflector: reflector.ok(),
+            mode: TypexMode::Encipher,
+        }
+    }
+
+    /// Add one of the example rotors to the left of the rotors added so far.
+    /// The first two rotors added are the stators.
+    ///
+    /// * `rotor_id` - One of `A` - `H`
+    /// * `pos` - The window letter, `0` for `A`
+    /// * `ring_loc` - The ring setting, `0` for `A`
+    /// * `reversed` - Whether the rotor is inserted back to front
+    pub fn rotor(self, rotor_id: &str, pos: u8, ring_loc: u8, reversed: bool) -> Self {
+        let tyre = TYPEX_ROTORS
+            .iter()
+            .find(|(id, _, _)| *id == rotor_id)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid Typex rotor id: {}. Expected one of A, B, C, D, E, F, G, H",
+                    rotor_id
+                )
+            })
+            .and_then(|(_, wiring, notches)| RotorTyre::new(wiring, notches, &Alphabet::latin()));
+        self.push_rotor(rotor_id, tyre, pos, ring_loc, reversed)
+    }
+
+    /// Add a rotor with a custom wiring to the left of the rotors added so
+    /// far. See [rotor](TypexMachineBuilder::rotor).
+    pub fn custom_rotor(
+        self,
+        rotor_id: &str,
+        tyre: RotorTyre,
+        pos: u8,
+        ring_loc: u8,
+        reversed: bool,
+    ) -> Self {
+        self.push_rotor(rotor_id, Ok(tyre), pos, ring_loc, reversed)
+    }
+
+    fn push_rotor(
+        mut self,
+        rotor_id: &str,
+        tyre: Result<RotorTyre, String>,
+        pos: u8,
+        ring_loc: u8,
+        reversed: bool,
+    ) -> Self {
+        let rotor = tyre.and_then(|tyre| {
+            if tyre.size() != 26 {
+                return Err(format!("Typex rotor {} must have 26 contacts", rotor_id));
+            }
+            let mut rotor = Rotor::new_custom(rotor_id, tyre, pos, ring_loc);
+            rotor.set_reversed(reversed);
+            Ok(rotor)
+        });
+        self.rotors.push(rotor);
+        self
+    }
+
+    /// Use a reflector in place of the example one.
+    pub fn reflector(mut self, reflector: Reflector) -> Self {
+        self.reflector = Some(reflector);
+        self
+    }
+
+    /// Take the reflector out, so the signal leaves the left rotor.
+    pub fn without_reflector(mut self) -> Self {
+        self.reflector = None;
+        self
+    }
+
+    /// Switch between enciphering and deciphering. Only matters without a
+    /// reflector.
+    pub fn mode(mut self, mode: TypexMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn try_build(self) -> Result<TypexMachine, String> {
+        if self.rotors.len() != TYPEX_ROTOR_COUNT {
+            return Err(format!(
+                "A Typex has {} rotors, received {}",
+                TYPEX_ROTOR_COUNT,
+                self.rotors.len()
+            ));
+        }
+        let rotors = self.rotors.into_iter().collect::<Result<Vec<_>, _>>()?;
+        if let Some(reflector) = &self.reflector {
+            if reflector.size() != 26 {
+                return Err(format!(
+                    "Reflector {} must have 26 contacts",
+                    reflector.id()
+                ));
+            }
+        }
+
+        Ok(TypexMachine {
+            rotors,
+            reflector: self.reflector,
+            mode: self.mode,
+        })
+    }
+}
+
+/// British Typex.
+#[derive(Clone)]
+pub struct TypexMachine {
+    /// Right rotor first, starting with the two stators
+    rotors: Vec<Rotor>,
+    reflector: Option<Reflector>,
+    mode: TypexMode,
+}
+
+impl TypexMachine {
+    pub fn builder() -> TypexMachineBuilder {
+        TypexMachineBuilder::new()
+    }
+
+    /// Step the three moving rotors, right (fast) rotor first.
+    fn step_rotors(&mut self) {
+        let moving = &mut self.rotors[TYPEX_STATORS..];
+        let fast_at_notch = moving[0].will_step_next_rotor();
+        let middle_at_notch = moving[1].will_step_next_rotor();
+
+        if middle_at_notch {
+            moving[1].rotate();
+            moving[2].rotate();
+        } else if fast_at_notch {
+            moving[1].rotate();
+        }
+        moving[0].rotate();
+    }
+
+    /// Translate a single letter, stepping the rotors first. Anything but
+    /// `A` - `Z` (of either case) is returned unmodified without stepping.
+    pub fn translate(&mut self, input: char) -> char {
+        if !input.is_ascii_alphabetic() {
+            return input;
+        }
+
+        self.step_rotors();
+
+        let mut val = input.to_ascii_uppercase() as u8 - b'A';
+        let forward = self.reflector.is_some() || self.mode == TypexMode::Encipher;
+        if forward {
+            val = self.rotors.iter().fold(val, |val, rotor| rotor.map_in(val));
+        }
+        if let Some(reflector) = &self.reflector {
+            val = reflector.map(val);
+        }
+        if self.reflector.is_some() || self.mode == TypexMode::Decipher {
+            val = self
+                .rotors
+                .iter()
+                .rev()
+                .fold(val, |val, rotor| rotor.map_out(val));
+        }
+
+        (val + b'A') as char
+    }
+
+    pub fn translate_text(&mut self, chars: impl Iterator<Item = char>) -> String {
+        chars.map(|c| self.translate(c)).collect()
+    }
+
+    /// Rotor ids, right rotor first.
+    pub fn rotor_ids(&self) -> Vec<&str> {
+        self.rotors.iter().map(|rotor| rotor.id()).collect()
+    }
+
+    /// Letters shown in the rotor windows, right rotor first.
+    pub fn position_letters(&self) -> Vec<char> {
+        self.rotors
+            .iter()
+            .map(|rotor| rotor.position_letter())
+            .collect()
+    }
+
+    /// Whether each rotor is inserted back to front, right rotor first.
+    pub fn reversed(&self) -> Vec<bool> {
+        self.rotors
+            .iter()
+            .map(|rotor| rotor.is_reversed())
+            .collect()
+    }
+
+    /// The reflector, if there is one.
+    pub fn reflector(&self) -> Option<&Reflector> {
+        self.reflector.as_ref()
+    }
+
+    pub fn mode(&self) -> TypexMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: TypexMode) {
+        self.mode = mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> TypexMachineBuilder {
+        TypexMachine::builder()
+            .rotor("A", 0, 0, false)
+            .rotor("B", 4, 0, true)
+            .rotor("C", 2, 3, false)
+            .rotor("D", 21, 0, false)
+            .rotor("E", 10, 7, true)
+    }
+
+    #[test]
+    fn test_regression_vectors() {
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOGXAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+        let mut typex = builder().try_build().unwrap();
+        let expected = "OKXWYKBNFSVLBWSUETRZEHZLPFNBFEJSOZUNPHFSOURZNMYZGTGULPDVFPXXVJ";
+        assert_eq!(typex.translate_text(text.chars()), expected);
+        assert_eq!(typex.position_letters(), vec!['A', 'E', 'M', 'L', 'G']);
+
+        let mut typex = builder().without_reflector().try_build().unwrap();
+        let expected = "WMHGWZTTWBXTKMWRAGEQWJZUJAMVVIODWRNSGPZUVYBGWUXRTXKMHWBTZMIAAQ";
+        assert_eq!(typex.translate_text(text.chars()), expected);
+    }
+
+    #[test]
+    fn test_reflector_makes_it_reciprocal() {
+        let text = "ATTACKATDAWN";
+        let cipher = builder().try_build().unwrap().translate_text(text.chars());
+        let plain = builder()
+            .try_build()
+            .unwrap()
+            .translate_text(cipher.chars());
+        assert_eq!(plain, text);
+    }
+
+    #[test]
+    fn test_without_reflector_needs_decipher_mode() {
+        let text = "ATTACKATDAWN";
+        let open = || builder().without_reflector();
+        let cipher = open().try_build().unwrap().translate_text(text.chars());
+
+        let mut typex = open().try_build().unwrap();
+        assert_ne!(typex.translate_text(cipher.chars()), text);
+
+        let mut typex = open().mode(TypexMode::Decipher).try_build().unwrap();
+        assert_eq!(typex.translate_text(cipher.chars()), text);
+    }
+
+    #[test]
+    fn test_stators_never_move() {
+        let mut typex = builder().try_build().unwrap();
+        typex.translate_text(std::iter::repeat_n('A', 1000));
+        let letters = typex.position_letters();
+        assert_eq!(&letters[..2], &['A', 'E']);
+    }
+
+    #[test]
+    fn test_reversed_rotor_steps_backwards() {
+        let mut typex = TypexMachine::builder()
+            .rotor("A", 0, 0, false)
+            .rotor("B", 0, 0, false)
+            .rotor("C", 2, 0, true)
+            .rotor("D", 0, 0, false)
+            .rotor("E", 0, 0, false)
+            .try_build()
+            .unwrap();
+        typex.translate('A');
+        assert_eq!(typex.position_letters()[2], 'B');
+        // Neither C nor B is a notch of C, so the middle rotor stays put
+        typex.translate('A');
+        assert_eq!(typex.position_letters(), vec!['A', 'A', 'A', 'A', 'A']);
+    }
+
+    #[test]
+    fn test_invalid_machines() {
+        assert!(builder().rotor("F", 0, 0, false).try_build().is_err());
+        assert!(TypexMachine::builder()
+            .rotor("A", 0, 0, false)
+            .rotor("B", 0, 0, false)
+            .rotor("C", 0, 0, false)
+            .rotor("D", 0, 0, false)
+            .rotor("I", 0, 0, false)
+            .try_build()
+            .is_err());
+        let digits = Alphabet::digits();
+        let tyre = RotorTyre::new("9876543210", "0", &digits).unwrap();
+        assert!(TypexMachine::builder()
+            .rotor("A", 0, 0, false)
+            .rotor("B", 0, 0, false)
+            .rotor("C", 0, 0, false)
+            .rotor("D", 0, 0, false)
+            .custom_rotor("Z", tyre, 0, 0, false)
+            .try_build()
+            .is_err());
+    }
+}
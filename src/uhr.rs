@@ -0,0 +1,295 @@
+//! Enigma Uhr module.
+//!
+//! The [Uhr](Uhr) was a box the Luftwaffe plugged into the plugboard in
+//! place of the ten plug cables. Its ten cables are numbered `1` - `10` and
+//! come in two groups, the `a` plugs and the `b` plugs. A 40 position rotary
+//! switch scrambles the connections between the groups, so unlike a plug
+//! cable the steckering is not reciprocal: with `A` on plug `1a`, the key `A`
+//! may reach the entry wheel as `F` while the key `F` reaches it as `K`.
+//!
+//! Every plug has an inbound and an outbound pin on one of the 40 contacts of
+//! its side of the switch. Plug `n` sits on contact `4(n - 1)` inbound and
+//! `4(n - 1) + 2` outbound. The switch wires the contacts of the `a` side to
+//! those of the `b` side following [UHR_WIRING](UHR_WIRING), turned by the
+//! switch position. At the positions divisible by four the steckering is
+//! reciprocal again.
+//!
+//! ```
+//! use enigma::uhr::Uhr;
+//!
+//! // 1a = A, 1b = B, 2a = C, 2b = D, ...
+//! let pairs = vec![
+//!     ('A', 'B'), ('C', 'D'), ('E', 'F'), ('G', 'H'), ('I', 'J'),
+//!     ('K', 'L'), ('M', 'N'), ('O', 'P'), ('Q', 'R'), ('S', 'T'),
+//! ];
+//! let uhr = Uhr::new(pairs, 27).unwrap();
+//!
+//! for val in 0..26 {
+//!     assert_eq!(uhr.map_out(uhr.map_in(val)), val);
+//! }
+//! assert!(Uhr::new(vec![('A', 'B')], 0).is_err());
+//! ```
+//!
+//! With the `serde` feature an [Uhr](Uhr) is serialized as its position and
+//! its plugs in order, e.g., `{"position":27,"plugs":[["A","B"], ...]}`.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The number of positions of the switch, `00` - `39`.
+pub const UHR_POSITIONS: u8 = 40;
+
+/// The number of plug cables, each with an `a` and a `b` plug.
+pub const UHR_PLUGS: usize = 10;
+
+/// The `b` side contact wired to each `a` side contact at position `00`.
+pub const UHR_WIRING: [u8; 40] = [
+    6, 31, 4, 29, 18, 39, 16, 25, 30, 23, 28, 1, 38, 11, 36, 37, 26, 27, 24, 21, 14, 3, 12, 17, 2,
+    7, 0, 33, 10, 35, 8, 5, 22, 19, 20, 13, 34, 15, 32, 9,
+];
+
+/// Enigma Uhr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "UhrSettings", into = "UhrSettings")
+)]
+pub struct Uhr {
+    /// Letters of the `a` and `b` plug of each cable
+    plugs: Vec<(u8, u8)>,
+    position: u8,
+    /// Entry wheel letter reached by each key
+    forward: [u8; 26],
+    /// Key reached from each entry wheel letter
+    backward: [u8; 26],
+}
+
+impl Uhr {
+    /// Create an Uhr.
+    ///
+    /// * `plugs` - The letters the `a` and `b` plug of cables `1` - `10` are
+    ///   plugged into, in order. Case is ignored.
+    /// * `position` - The switch position, `0` - `39`
+    pub fn new<T>(plugs: T, position: u8) -> Result<Uhr, String>
+    where
+        T: IntoIterator<Item = (char, char)>,
+    {
+        let plugs: Vec<(char, char)> = plugs.into_iter().collect();
+        if plugs.len() != UHR_PLUGS {
+            return Err(format!(
+                "Invalid Uhr plugs: expected {} cables, received {}",
+                UHR_PLUGS,
+                plugs.len()
+            ));
+        }
+
+        let mut used = [false; 26];
+        let mut plug_vals = Vec::with_capacity(UHR_PLUGS);
+        for (a, b) in plugs {
+            let mut vals = [0; 2];
+            for (val, letter) in vals.iter_mut().zip([a, b]) {
+                if !letter.is_ascii_alphabetic() {
+                    return Err(format!(
+                        "Invalid Uhr plug: {}. Can only plug alphabetic characters",
+                        letter
+                    ));
+                }
+
+                *val = letter.to_ascii_uppercase() as u8 - b'A';
+                if used[*val as usize] {
+                    return Err(format!(
+                        "Invalid Uhr plugs: letter {} is plugged more than once",
+                        letter.to_ascii_uppercase()
+                    ));
+                }
+                used[*val as usize] = true;
+            }
+            plug_vals.push((vals[0], vals[1]));
+        }
+
+        let mut uhr = Uhr {
+            plugs: plug_vals,
+            position: 0,
+            forward: [0; 26],
+            backward: [0; 26],
+        };
+        uhr.set_position(position)?;
+        Ok(uhr)
+    }
+
+    /// Turn the switch to a position, `0` - `39`.
+    pub fn set_position(&mut self, position: u8) -> Result<(), String> {
+        if position >= UHR_POSITIONS {
+            return Err(format!(
+                "Invalid Uhr position: {}. Expected 00 - 39",
+                position
+            ));
+        }
+
+        self.position = position;
+        self.wire();
+        Ok(())
+    }
+
+    /// Work out where every key is steckered to at the current position.
+    fn wire(&mut self) {
+        let pos = self.position as usize;
+        let turns = UHR_POSITIONS as usize;
+        let mut inverse = [0; 40];
+        for (a_contact, &b_contact) in UHR_WIRING.iter().enumerate() {
+            inverse[b_contact as usize] = a_contact;
+        }
+
+        // The outbound pin reached from the inbound pin of a plug, as the
+        // number of the plug on the other side
+        let across = |wiring: &dyn Fn(usize) -> usize, plug: usize| {
+            let contact = (wiring((4 * plug + pos) % turns) + turns - pos) % turns;
+            (contact - 2) / 4
+        };
+
+        for val in 0..26 {
+            self.forward[val] = val as u8;
+        }
+        for (plug, &(a, b)) in self.plugs.iter().enumerate() {
+            let b_plug = across(&|contact| UHR_WIRING[contact] as usize, plug);
+            let a_plug = across(&|contact| inverse[contact], plug);
+            self.forward[a as usize] = self.plugs[b_plug].1;
+            self.forward[b as usize] = self.plugs[a_plug].0;
+        }
+
+        for (key, &letter) in self.forward.iter().enumerate() {
+            self.backward[letter as usize] = key as u8;
+        }
+    }
+
+    /// The switch position, `0` - `39`.
+    pub fn position(&self) -> u8 {
+        self.position
+    }
+
+    /// The letters of the `a` and `b` plug of cables `1` - `10`.
+    pub fn plugs(&self) -> Vec<(char, char)> {
+        self.plugs
+            .iter()
+            .map(|&(a, b)| ((a + b'A') as char, (b + b'A') as char))
+            .collect()
+    }
+
+    /// Whether the steckering is reciprocal at the current position.
+    pub fn is_reciprocal(&self) -> bool {
+        (0..26).all(|val| self.forward[self.forward[val] as usize] as usize == val)
+    }
+
+    /// Maps a key to the entry wheel letter it is steckered to.
+    ///
+    /// * `input_val` - The key, `0` - `25`
+    pub fn map_in(&self, input_val: u8) -> u8 {
+        self.forward[input_val as usize]
+    }
+
+    /// Maps an entry wheel letter back to its key.
+    ///
+    /// * `input_val` - The entry wheel letter, `0` - `25`
+    pub fn map_out(&self, input_val: u8) -> u8 {
+        self.backward[input_val as usize]
+    }
+}
+
+/// Formats the Uhr as it appears in a configuration string, e.g.,
+/// `UHR-27`.
+impl fmt::Display for Uhr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UHR-{:02}", self.position)
+    }
+}
+
+/// The settings an [Uhr](Uhr) is serialized as.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct UhrSettings {
+    position: u8,
+    plugs: Vec<(char, char)>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UhrSettings> for Uhr {
+    type Error = String;
+
+    fn try_from(settings: UhrSettings) -> Result<Self, Self::Error> {
+        Uhr::new(settings.plugs, settings.position)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Uhr> for UhrSettings {
+    fn from(uhr: Uhr) -> Self {
+        UhrSettings {
+            position: uhr.position,
+            plugs: uhr.plugs(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugs() -> Vec<(char, char)> {
+        "AB CD EF GH IJ KL MN OP QR ST"
+            .split(' ')
+            .map(|pair| {
+                let mut chars = pair.chars();
+                (chars.next().unwrap(), chars.next().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wiring_is_a_permutation() {
+        let mut contacts = UHR_WIRING.to_vec();
+        contacts.sort();
+        assert_eq!(contacts, (0..40).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_every_position_is_a_permutation() {
+        for pos in 0..UHR_POSITIONS {
+            let uhr = Uhr::new(plugs(), pos).unwrap();
+            for val in 0..26 {
+                assert_eq!(uhr.map_out(uhr.map_in(val)), val);
+            }
+            // Unplugged letters pass straight through
+            for val in 20..26 {
+                assert_eq!(uhr.map_in(val), val);
+            }
+            // An a plug always reaches a b plug
+            for val in (0..20).step_by(2) {
+                assert_eq!(uhr.map_in(val) % 2, 1);
+            }
+            assert_eq!(uhr.is_reciprocal(), pos % 4 == 0);
+        }
+    }
+
+    #[test]
+    fn test_position_00() {
+        let uhr = Uhr::new(plugs(), 0).unwrap();
+        // 1a reaches 2b and 2b reaches 1a
+        assert_eq!(uhr.map_in(0), 3);
+        assert_eq!(uhr.map_in(3), 0);
+        assert_eq!(uhr.to_string(), "UHR-00");
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(Uhr::new(plugs(), 40).is_err());
+        assert!(Uhr::new(plugs().into_iter().take(9), 0).is_err());
+
+        let mut plugs = plugs();
+        plugs[9] = ('S', 'A');
+        assert!(Uhr::new(plugs.clone(), 0).is_err());
+        plugs[9] = ('S', '1');
+        assert!(Uhr::new(plugs, 0).is_err());
+    }
+}